@@ -0,0 +1,14 @@
+create table task_dependencies
+(
+    blocker_id uuid                                   not null
+        references tasks
+            on update cascade on delete cascade,
+    blocked_id uuid                                   not null
+        references tasks
+            on update cascade on delete cascade,
+    created_at timestamp with time zone default now() not null,
+    primary key (blocker_id, blocked_id)
+);
+
+create index task_dependencies_blocked_id_idx
+    on task_dependencies (blocked_id);
@@ -0,0 +1,2 @@
+alter table tasks
+    add archived_at timestamp with time zone;
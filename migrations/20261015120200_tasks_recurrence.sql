@@ -0,0 +1,11 @@
+alter table tasks
+    add recurrence varchar;
+
+alter table tasks
+    add recurrence_source_id uuid
+        references tasks
+            on update cascade on delete set null;
+
+-- one instance per occurrence, so concurrent materializations can't duplicate them
+create unique index tasks_recurrence_source_id_due_date_key
+    on tasks (recurrence_source_id, due_date);
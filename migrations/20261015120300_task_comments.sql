@@ -0,0 +1,25 @@
+create table task_comments
+(
+    id         uuid                     default gen_random_uuid() not null
+        primary key,
+    created_at timestamp with time zone default now()             not null,
+    updated_at timestamp with time zone default now()             not null,
+    task_id    uuid                                               not null
+        references tasks
+            on update cascade on delete cascade,
+    author_id  uuid                                               not null
+        references members
+            on update cascade on delete cascade,
+    content    text                                               not null
+);
+
+create index task_comments_task_id_idx
+    on task_comments (task_id);
+
+create trigger set_public_task_comments_updated_at
+    before update
+    on task_comments
+    for each row
+execute procedure set_current_timestamp_updated_at();
+
+comment on trigger set_public_task_comments_updated_at on task_comments is 'trigger to set value of column "updated_at" to current timestamp on row update';
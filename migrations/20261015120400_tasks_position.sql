@@ -0,0 +1,33 @@
+alter table tasks
+    add position integer;
+
+update tasks
+set position = ordered.position
+from (select id, row_number() over (partition by project_id order by created_at, count) - 1 as position
+      from tasks) as ordered
+where tasks.id = ordered.id;
+
+alter table tasks
+    alter column position set not null;
+
+create function set_task_position() returns trigger
+    language plpgsql
+as
+$$
+BEGIN
+  IF NEW."position" IS NULL THEN
+    SELECT COALESCE(MAX("position") + 1, 0) INTO NEW."position"
+    FROM tasks
+    WHERE project_id IS NOT DISTINCT FROM NEW.project_id;
+  END IF;
+  RETURN NEW;
+END;
+$$;
+
+create trigger set_public_tasks_position
+    before insert
+    on tasks
+    for each row
+execute procedure set_task_position();
+
+comment on trigger set_public_tasks_position on tasks is 'trigger to append new tasks at the end of their project ordering';
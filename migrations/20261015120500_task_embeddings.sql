@@ -0,0 +1,19 @@
+create table task_embeddings
+(
+    task_id    uuid                                   not null
+        primary key
+        references tasks
+            on update cascade on delete cascade,
+    created_at timestamp with time zone default now() not null,
+    updated_at timestamp with time zone default now() not null,
+    model      text                                   not null,
+    embedding  real[]                                 not null
+);
+
+create trigger set_public_task_embeddings_updated_at
+    before update
+    on task_embeddings
+    for each row
+execute procedure set_current_timestamp_updated_at();
+
+comment on trigger set_public_task_embeddings_updated_at on task_embeddings is 'trigger to set value of column "updated_at" to current timestamp on row update';
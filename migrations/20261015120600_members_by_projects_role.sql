@@ -0,0 +1,2 @@
+alter table members_by_projects
+    add column role varchar default 'Member'::character varying not null;
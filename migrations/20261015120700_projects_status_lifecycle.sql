@@ -0,0 +1,9 @@
+update projects
+set status = case status
+                 when 'Done' then 'Completed'
+                 when 'Canceled' then 'Archived'
+                 else 'Active'
+    end;
+
+alter table projects
+    alter column status set default 'Active';
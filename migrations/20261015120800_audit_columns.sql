@@ -0,0 +1,7 @@
+alter table tasks
+    add column created_by uuid,
+    add column updated_by uuid;
+
+alter table labels
+    add column created_by uuid,
+    add column updated_by uuid;
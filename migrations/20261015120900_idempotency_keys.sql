@@ -0,0 +1,5 @@
+alter table tasks
+    add column idempotency_key text unique;
+
+alter table labels
+    add column idempotency_key text unique;
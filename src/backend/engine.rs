@@ -1,68 +1,537 @@
-use std::{env::var, time::Duration};
+use std::{
+    env::var,
+    fs::read_to_string,
+    future::Future,
+    num::NonZeroUsize,
+    path::Path,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use async_openai::{config::OpenAIConfig, Client};
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use derive_builder::Builder;
+use hashlink::LruCache;
+use sqlx::{pool::PoolConnection, postgres::PgPoolOptions, PgConnection, Pool, Postgres, Transaction};
+use strum_macros::{Display, EnumString};
+use tokio::sync::Semaphore;
+use toml_edit::{Document, Table};
+use tracing::{debug, warn};
+use uuid::Uuid;
 
-use crate::errors::sdk::SDKError;
+use crate::{
+    backend::events::{EventSink, SDKEvent},
+    cognition::prompts::CognitionPrompts,
+    common::commons::{sql_debug, SQLArgument},
+    errors::sdk::SDKError,
+};
 
-#[derive(Clone)]
+#[derive(Debug, Copy, Clone, Default, Display, EnumString, Eq, PartialEq)]
+#[strum(ascii_case_insensitive)]
+pub enum LlmProvider {
+    #[default]
+    OpenAI,
+    Anthropic,
+}
+
+impl LlmProvider {
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            LlmProvider::OpenAI => "gpt-3.5-turbo",
+            LlmProvider::Anthropic => "claude-3-haiku-20240307",
+        }
+    }
+}
+
+/// Build one with [`SDKConfigBuilder`], or load it with [`SDKConfig::from_env`] or [`SDKConfig::from_file`].
+/// Only `database_url` and `llm_api_key` are required, everything else has a default.
+#[derive(Clone, Builder)]
+#[builder(pattern = "owned", build_fn(private, name = "build_fields"))]
 pub struct SDKConfig {
+    #[builder(setter(into))]
     pub database_url: String,
+    /// Read-only operations go to this database when set. Replication lag means a read right after a write may be stale.
+    #[builder(setter(into, strip_option), default)]
+    pub read_replica_url: Option<String>,
+    #[builder(setter(into))]
     pub llm_api_key: String,
+    /// Defaults to the provider's [`LlmProvider::default_model`].
+    #[builder(
+        setter(into),
+        default = "self.llm_provider.unwrap_or_default().default_model().to_string()"
+    )]
     pub llm_model_name: String,
+    #[builder(default)]
+    pub llm_provider: LlmProvider,
+    #[builder(default = "3")]
+    pub llm_max_attempts: u32,
+    /// Maximum number of cognition answers kept in memory; `None` disables the cache.
+    #[builder(setter(strip_option), default)]
+    pub llm_cache_capacity: Option<NonZeroUsize>,
+    #[builder(default)]
+    pub cognition_prompts: CognitionPrompts,
+    /// Existing tasks given to the model as context for suggestions, capped at [`MAX_COGNITION_CONTEXT_SIZE`](crate::cognition::operations::MAX_COGNITION_CONTEXT_SIZE).
+    #[builder(default = "10")]
+    pub cognition_context_size: u32,
+    /// Maximum number of LLM calls in flight at once, others queue; `None` leaves them unbounded.
+    #[builder(setter(strip_option), default)]
+    pub cognition_max_concurrency: Option<NonZeroUsize>,
+    /// Seconds a single LLM request may take before it is cancelled with `SDKError::Timeout`.
+    #[builder(default = "30")]
+    pub cognition_timeout_secs: u64,
+    /// Largest `limit` honoured by list operations, and the one used when none is given; bigger ones are clamped to it.
+    #[builder(default = "1000")]
+    pub max_page_size: u32,
+    /// Longest task title accepted, in characters, after trimming.
+    #[builder(default = "512")]
+    pub max_title_length: u32,
+    #[builder(default = "10")]
+    pub max_connections: u32,
+    #[builder(default)]
+    pub min_connections: u32,
+    #[builder(default = "60")]
+    pub acquire_timeout_secs: u64,
+    /// Apply pending migrations when the engine is created.
+    #[builder(default)]
+    pub auto_migrate: bool,
+    /// Log the SQL and bound values of filtered list queries at debug level.
+    #[builder(default)]
+    pub log_sql: bool,
     // pub with_changes_registration: bool,
 }
 
+impl SDKConfigBuilder {
+    /// Fails with `SDKError::InvalidConfig` when a required field is missing or a value is out of range.
+    pub fn build(self) -> Result<SDKConfig, SDKError> {
+        let config = self
+            .build_fields()
+            .map_err(|err| SDKError::InvalidConfig(err.to_string()))?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Looks settings up in the environment first and then in the optional config file.
+#[derive(Default)]
+struct ConfigSource<'a> {
+    file: Option<&'a Table>,
+}
+
+impl ConfigSource<'_> {
+    fn get(&self, env_names: &[&str], key: &str) -> Option<String> {
+        env_names.iter().find_map(|name| var(name).ok()).or_else(|| {
+            let value = self.file?.get(key)?.as_value()?;
+
+            value
+                .as_str()
+                .map(|value| value.to_string())
+                .or_else(|| value.as_integer().map(|value| value.to_string()))
+                .or_else(|| value.as_bool().map(|value| value.to_string()))
+        })
+    }
+
+    /// `None` when the setting is absent, leaving the builder's default in place.
+    fn parse<T: FromStr>(&self, env_name: &str, key: &str) -> Result<Option<T>, SDKError> {
+        self.get(&[env_name], key)
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .map_err(|_| SDKError::InvalidConfig(format!("invalid {}: {}", key, value)))
+            })
+            .transpose()
+    }
+}
+
 impl SDKConfig {
-    pub fn from_env() -> SDKConfig {
-        let database_url = var("DATABASE_URL").unwrap();
-        let llm_api_key = var("OPENAI_API_KEY").unwrap();
-        let llm_model_name = var("OPENAI_MODEL_NAME").unwrap_or("gpt-3.5-turbo".to_string());
+    pub fn validate(&self) -> Result<(), SDKError> {
+        if self.llm_max_attempts == 0 {
+            return Err(SDKError::InvalidConfig("invalid llm_max_attempts: 0".to_string()));
+        }
+
+        if self.max_connections == 0 {
+            return Err(SDKError::InvalidConfig(
+                "max_connections must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.min_connections > self.max_connections {
+            return Err(SDKError::InvalidConfig(format!(
+                "min_connections ({}) is greater than max_connections ({})",
+                self.min_connections, self.max_connections
+            )));
+        }
+
+        if self.max_page_size == 0 {
+            return Err(SDKError::InvalidConfig(
+                "max_page_size must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.max_title_length == 0 {
+            return Err(SDKError::InvalidConfig(
+                "max_title_length must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.cognition_timeout_secs == 0 {
+            return Err(SDKError::InvalidConfig(
+                "cognition_timeout_secs must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn from_env() -> Result<SDKConfig, SDKError> {
+        Self::load(ConfigSource::default())
+    }
+
+    /// Reads the settings from a TOML file; environment variables take precedence over the file values.
+    pub fn from_file(path: &Path) -> Result<SDKConfig, SDKError> {
+        let content = read_to_string(path)
+            .map_err(|err| SDKError::InvalidConfig(format!("cannot read {}: {}", path.display(), err)))?;
+
+        let document = content
+            .parse::<Document>()
+            .map_err(|err| SDKError::InvalidConfig(format!("malformed {}: {}", path.display(), err)))?;
+
+        Self::load(ConfigSource {
+            file: Some(document.as_table()),
+        })
+    }
+
+    fn load(source: ConfigSource) -> Result<SDKConfig, SDKError> {
+        let database_url = source
+            .get(&["DATABASE_URL"], "database_url")
+            .ok_or_else(|| SDKError::InvalidConfig("DATABASE_URL is not set".to_string()))?;
+
+        let read_replica_url = source.get(&["PLEXO_DB_READ_REPLICA_URL"], "read_replica_url");
+
+        let llm_provider = match source.get(&["PLEXO_LLM_PROVIDER"], "llm_provider") {
+            Some(provider) => LlmProvider::from_str(&provider)
+                .map_err(|_| SDKError::InvalidConfig(format!("unknown LLM provider: {}", provider)))?,
+            None => LlmProvider::default(),
+        };
+
+        let api_key_var = match llm_provider {
+            LlmProvider::OpenAI => "OPENAI_API_KEY",
+            LlmProvider::Anthropic => "ANTHROPIC_API_KEY",
+        };
+
+        let llm_api_key = source
+            .get(&[api_key_var], "llm_api_key")
+            .ok_or_else(|| SDKError::InvalidConfig(format!("missing API key for {}", llm_provider)))?;
+
+        let mut builder = SDKConfigBuilder::default()
+            .database_url(database_url)
+            .llm_api_key(llm_api_key)
+            .llm_provider(llm_provider);
+
+        if let Some(read_replica_url) = read_replica_url {
+            builder = builder.read_replica_url(read_replica_url);
+        }
+
+        if let Some(llm_model_name) = source.get(&["PLEXO_LLM_MODEL", "OPENAI_MODEL_NAME"], "llm_model_name") {
+            builder = builder.llm_model_name(llm_model_name);
+        }
+
+        if let Some(llm_max_attempts) = source.parse("PLEXO_LLM_MAX_ATTEMPTS", "llm_max_attempts")? {
+            builder = builder.llm_max_attempts(llm_max_attempts);
+        }
+
+        // 0 disables the cache and lifts the concurrency limit, the same as leaving them unset
+        if let Some(capacity) = source
+            .parse("PLEXO_LLM_CACHE_CAPACITY", "llm_cache_capacity")?
+            .and_then(NonZeroUsize::new)
+        {
+            builder = builder.llm_cache_capacity(capacity);
+        }
+
+        if let Some(context_size) = source.parse("PLEXO_COGNITION_CONTEXT_SIZE", "cognition_context_size")? {
+            builder = builder.cognition_context_size(context_size);
+        }
+
+        if let Some(max_concurrency) = source
+            .parse("PLEXO_COGNITION_MAX_CONCURRENCY", "cognition_max_concurrency")?
+            .and_then(NonZeroUsize::new)
+        {
+            builder = builder.cognition_max_concurrency(max_concurrency);
+        }
+
+        if let Some(timeout_secs) = source.parse("PLEXO_COGNITION_TIMEOUT_SECS", "cognition_timeout_secs")? {
+            builder = builder.cognition_timeout_secs(timeout_secs);
+        }
+
+        if let Some(max_page_size) = source.parse("PLEXO_MAX_PAGE_SIZE", "max_page_size")? {
+            builder = builder.max_page_size(max_page_size);
+        }
+
+        if let Some(max_title_length) = source.parse("PLEXO_MAX_TITLE_LENGTH", "max_title_length")? {
+            builder = builder.max_title_length(max_title_length);
+        }
+
+        if let Some(max_connections) = source.parse("PLEXO_DB_MAX_CONNECTIONS", "max_connections")? {
+            builder = builder.max_connections(max_connections);
+        }
+
+        if let Some(min_connections) = source.parse("PLEXO_DB_MIN_CONNECTIONS", "min_connections")? {
+            builder = builder.min_connections(min_connections);
+        }
+
+        if let Some(acquire_timeout_secs) = source.parse("PLEXO_DB_ACQUIRE_TIMEOUT_SECS", "acquire_timeout_secs")? {
+            builder = builder.acquire_timeout_secs(acquire_timeout_secs);
+        }
+
+        if let Some(auto_migrate) = source.parse("PLEXO_AUTO_MIGRATE", "auto_migrate")? {
+            builder = builder.auto_migrate(auto_migrate);
+        }
+
+        if let Some(log_sql) = source.parse("PLEXO_LOG_SQL", "log_sql")? {
+            builder = builder.log_sql(log_sql);
+        }
+
         // let with_changes_registration = var("WITH_CHANGES_REGISTRATION")
         //     .unwrap_or("true".to_string())
         //     .parse::<bool>()
         //     .unwrap();
 
-        SDKConfig {
-            database_url,
-            llm_api_key,
-            llm_model_name,
-            // with_changes_registration,
-        }
+        builder.build()
     }
 }
 
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(30);
+const ACQUIRE_BACKOFF: Duration = Duration::from_millis(50);
+const ACQUIRE_MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+pub type TransactionFuture<'c, T> = Pin<Box<dyn Future<Output = Result<T, SDKError>> + Send + 'c>>;
+
+/// Connection counts of the write pool at the moment [`SDKEngine::pool_stats`] was called.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PoolStats {
+    /// Open connections, idle or not.
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+}
+
 #[derive(Clone)]
 pub struct SDKEngine {
     pub config: SDKConfig,
     pub db_pool: Box<Pool<Postgres>>,
+    /// Pool for read-only queries; the same pool as `db_pool` unless a read replica is configured.
+    pub read_pool: Box<Pool<Postgres>>,
     pub llm_client: Box<Client<OpenAIConfig>>,
+    pub http_client: Box<reqwest::Client>,
+    pub llm_cache: Option<Arc<Mutex<LruCache<u64, String>>>>,
+    /// Shared by every clone of the engine; `None` when `cognition_max_concurrency` is unset.
+    pub cognition_permits: Option<Arc<Semaphore>>,
+    /// Where committed writes are published; `None` skips building the events altogether.
+    pub event_sink: Option<Arc<dyn EventSink>>,
 }
 
 impl SDKEngine {
     pub async fn new(config: SDKConfig) -> Result<SDKEngine, SDKError> {
-        let pool = PgPoolOptions::new()
-            .max_connections(10)
-            .acquire_timeout(Duration::from_secs(60))
-            .connect(config.database_url.as_str())
-            .await?;
+        config.validate()?;
+
+        let pool_options = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs));
+
+        let pool = pool_options.clone().connect(config.database_url.as_str()).await?;
+
+        let read_pool = match &config.read_replica_url {
+            Some(read_replica_url) => pool_options.connect(read_replica_url.as_str()).await?,
+            None => pool.clone(),
+        };
 
         let llm_config = OpenAIConfig::default().with_api_key(config.llm_api_key.clone());
 
         let llm_client = Box::new(Client::with_config(llm_config));
+        let http_client = Box::new(reqwest::Client::new());
+
+        let llm_cache = config
+            .llm_cache_capacity
+            .map(|capacity| Arc::new(Mutex::new(LruCache::new(capacity.get()))));
+
+        let cognition_permits = config
+            .cognition_max_concurrency
+            .map(|limit| Arc::new(Semaphore::new(limit.get())));
 
         let db_pool = Box::new(pool);
+        let read_pool = Box::new(read_pool);
 
-        Ok(SDKEngine {
+        let engine = SDKEngine {
             config,
             db_pool,
+            read_pool,
             llm_client,
-        })
+            http_client,
+            llm_cache,
+            cognition_permits,
+            event_sink: None,
+        };
+
+        if engine.config.auto_migrate {
+            engine.run_migrations().await?;
+        }
+
+        Ok(engine)
     }
 
-    pub async fn migrate(&self) -> Result<(), SDKError> {
+    /// Applies the embedded migrations; already applied ones are skipped, so it is safe to call repeatedly.
+    pub async fn run_migrations(&self) -> Result<(), SDKError> {
         sqlx::migrate!().run(self.db_pool.as_ref()).await?;
 
         Ok(())
     }
+
+    /// Readiness probe for the database: errors (including a closed pool or a timeout) become `SDKError::Unavailable`.
+    pub async fn health_check(&self) -> Result<(), SDKError> {
+        let probe = sqlx::query("SELECT 1").execute(self.db_pool.as_ref());
+
+        match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, probe).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(sqlx::Error::PoolClosed)) => Err(sqlx::Error::PoolClosed.into()),
+            Ok(Err(err)) => Err(SDKError::unavailable(err.to_string())),
+            Err(_) => Err(SDKError::unavailable("database health check timed out")),
+        }
+    }
+
+    pub async fn migrate(&self) -> Result<(), SDKError> {
+        self.run_migrations().await
+    }
+
+    /// Stops handing out connections and waits for checked-out ones to be returned, for up to 30 seconds.
+    /// Every clone of the engine shares the pools, so all of them fail with `SDKError::Unavailable` afterwards.
+    pub async fn close(&self) -> Result<(), SDKError> {
+        let drain = async {
+            tokio::join!(self.db_pool.close(), self.read_pool.close());
+        };
+
+        tokio::time::timeout(CLOSE_TIMEOUT, drain)
+            .await
+            .map_err(|_| SDKError::Timeout("connections still in use after closing the engine".to_string()))
+    }
+
+    pub fn with_event_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    /// Hands the event built by `event` to the sink, if any; without one `event` is never called.
+    pub(crate) fn emit(&self, event: impl FnOnce() -> SDKEvent) {
+        if let Some(sink) = &self.event_sink {
+            sink.emit(event());
+        }
+    }
+
+    /// Reads the pool's atomic counters without acquiring anything, so it is cheap enough to poll for gauges.
+    pub fn pool_stats(&self) -> PoolStats {
+        let size = self.db_pool.size();
+        let idle = u32::try_from(self.db_pool.num_idle()).unwrap_or(u32::MAX).min(size);
+
+        PoolStats {
+            size,
+            idle,
+            in_use: size - idle,
+        }
+    }
+
+    /// Logs a compiled query with its bound values when `log_sql` is enabled.
+    pub fn log_sql(&self, query: &str, args: &[SQLArgument]) {
+        if self.config.log_sql {
+            debug!(sql = %sql_debug(query, args), "compiled query");
+        }
+    }
+
+    /// Checks a connection out of the write pool, see [`SDKEngine::retry_acquire`].
+    pub(crate) async fn acquire(&self) -> Result<PoolConnection<Postgres>, SDKError> {
+        self.retry_acquire(|| self.db_pool.acquire()).await
+    }
+
+    /// Checks a connection out of the read pool, see [`SDKEngine::retry_acquire`]. The dataloaders are the
+    /// exception and read from the pool directly, since their error type is a plain `sqlx::Error`.
+    pub(crate) async fn acquire_read(&self) -> Result<PoolConnection<Postgres>, SDKError> {
+        self.retry_acquire(|| self.read_pool.acquire()).await
+    }
+
+    /// Starts a transaction on the write pool, see [`SDKEngine::retry_acquire`].
+    pub(crate) async fn begin(&self) -> Result<Transaction<'static, Postgres>, SDKError> {
+        self.retry_acquire(|| self.db_pool.begin()).await
+    }
+
+    /// Retries `acquire` with jittered exponential backoff while it fails transiently (too many connections,
+    /// a dropped socket), giving up once `acquire_timeout_secs` have passed in total. Every failure to get a
+    /// connection, transient or not, is reported as `SDKError::Unavailable`.
+    async fn retry_acquire<T, F, Fut>(&self, acquire: F) -> Result<T, SDKError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let budget = Duration::from_secs(self.config.acquire_timeout_secs);
+        let deadline = Instant::now() + budget;
+        let mut backoff = ACQUIRE_BACKOFF;
+        let mut attempt = 1;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let err = match tokio::time::timeout(remaining, acquire()).await {
+                Ok(Ok(conn)) => return Ok(conn),
+                Ok(Err(err)) => SDKError::from(err),
+                Err(_) => SDKError::Database(sqlx::Error::PoolTimedOut),
+            };
+
+            // a closed pool is already `Unavailable` and never comes back
+            if err.is_closed() {
+                return Err(err);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            if !err.is_retryable() || remaining.is_zero() {
+                return Err(SDKError::unavailable(format!(
+                    "no database connection after {} attempt(s) in {}s: {}",
+                    attempt,
+                    budget.as_secs(),
+                    err
+                )));
+            }
+
+            warn!(%err, attempt, "acquiring a database connection failed, retrying");
+
+            // sleep between half and all of the backoff so callers that failed together don't retry together
+            let jitter = (Uuid::new_v4().as_u128() % 1000) as u32;
+            tokio::time::sleep((backoff / 2 + backoff * jitter / 2000).min(remaining)).await;
+
+            backoff = (backoff * 2).min(ACQUIRE_MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+
+    /// Runs `f` inside a database transaction, committing when it returns `Ok` and rolling back otherwise.
+    /// Use the `*TransactionOperations` traits on the connection to compose writes atomically.
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, SDKError>
+    where
+        F: for<'c> FnOnce(&'c mut PgConnection) -> TransactionFuture<'c, T> + Send,
+        T: Send,
+    {
+        let mut tx = self.begin().await?;
+
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(err) => {
+                tx.rollback().await?;
+                Err(err)
+            }
+        }
+    }
 }
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::resources::{labels::label::Label, projects::project::Project, tasks::task::Task};
+
+/// A committed write to a task, label or project. Deletions only carry the id, as batch deletes don't load the rows.
+/// Serializes as `{"type": "task_created", "data": {...}}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum SDKEvent {
+    TaskCreated(Task),
+    TaskUpdated(Task),
+    TaskDeleted { id: Uuid },
+    LabelCreated(Label),
+    LabelUpdated(Label),
+    LabelDeleted { id: Uuid },
+    ProjectCreated(Project),
+    ProjectUpdated(Project),
+    ProjectDeleted { id: Uuid },
+}
+
+/// Receives an [`SDKEvent`] after each write on [`SDKEngine`](crate::backend::engine::SDKEngine) commits.
+/// It is called inline by the writing operation, so hand the event off (e.g. to a channel) instead of blocking.
+/// Writes made through the `*TransactionOperations` traits emit nothing, as the caller owns the commit.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: SDKEvent);
+}
@@ -1,40 +1,359 @@
-use std::sync::Arc;
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
 
-use async_graphql::dataloader::DataLoader;
+use async_graphql::dataloader::{CacheFactory, CacheStorage, DataLoader};
 
 use crate::resources::{
-    assets::loader::AssetLoader, changes::loader::ChangeLoader, labels::loader::LabelLoader,
-    members::loader::MemberLoader, projects::loader::ProjectLoader, tasks::loader::TaskLoader,
+    assets::loader::AssetLoader,
+    changes::loader::ChangeLoader,
+    labels::loader::{LabelLoader, TaskLabelsLoader},
+    members::loader::MemberLoader,
+    projects::loader::ProjectLoader,
+    tasks::loader::TaskLoader,
     teams::loader::TeamLoader,
 };
 
+use uuid::Uuid;
+
 use super::engine::SDKEngine;
 
 // #[derive(Clone)]
 pub struct SDKLoaders {
     pub task_loader: DataLoader<TaskLoader>,
-    pub member_loader: DataLoader<MemberLoader>,
+    pub member_loader: DataLoader<MemberLoader, TtlCache>,
     pub project_loader: DataLoader<ProjectLoader>,
     pub team_loader: DataLoader<TeamLoader>,
     pub asset_loader: DataLoader<AssetLoader>,
-    pub label_loader: DataLoader<LabelLoader>,
+    pub label_loader: DataLoader<LabelLoader, TtlCache>,
+    pub task_labels_loader: DataLoader<TaskLabelsLoader>,
     pub change_loader: DataLoader<ChangeLoader>,
 
     pub engine: Arc<SDKEngine>,
+
+    cache: TtlCache,
 }
 
 impl SDKLoaders {
+    /// Caches every loaded value for the lifetime of the loaders, like `DataLoader::new`.
     pub fn new(engine: Arc<SDKEngine>) -> Self {
+        Self::build(engine, TtlCache::without_expiry())
+    }
+
+    /// Keeps members and labels, which rarely change, cached for `ttl` across loads;
+    /// a zero `ttl` disables their cache.
+    pub fn with_cache(engine: Arc<SDKEngine>, ttl: Duration) -> Self {
+        Self::build(engine, TtlCache::new(ttl))
+    }
+
+    fn build(engine: Arc<SDKEngine>, cache: TtlCache) -> Self {
         Self {
             task_loader: DataLoader::new(TaskLoader::new(engine.clone()), tokio::spawn),
-            member_loader: DataLoader::new(MemberLoader::new(engine.clone()), tokio::spawn),
+            member_loader: DataLoader::with_cache(MemberLoader::new(engine.clone()), tokio::spawn, cache.clone()),
             project_loader: DataLoader::new(ProjectLoader::new(engine.clone()), tokio::spawn),
             team_loader: DataLoader::new(TeamLoader::new(engine.clone()), tokio::spawn),
             asset_loader: DataLoader::new(AssetLoader::new(engine.clone()), tokio::spawn),
-            label_loader: DataLoader::new(LabelLoader::new(engine.clone()), tokio::spawn),
+            label_loader: DataLoader::with_cache(LabelLoader::new(engine.clone()), tokio::spawn, cache.clone()),
+            task_labels_loader: DataLoader::new(TaskLabelsLoader::new(engine.clone()), tokio::spawn),
             change_loader: DataLoader::new(ChangeLoader::new(engine.clone()), tokio::spawn),
 
             engine,
+            cache,
+        }
+    }
+
+    /// Evicts `key` from the cached loaders, e.g. after updating the member or label it identifies.
+    pub fn invalidate(&self, key: Uuid) {
+        self.cache.invalidate(&key);
+    }
+
+    pub fn clear(&self) {
+        self.member_loader.clear::<Uuid>();
+        self.label_loader.clear::<Uuid>();
+    }
+}
+
+fn key_hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Invalidations a storage holds before it stops tracking keys and clears itself instead.
+const MAX_PENDING_EVICTIONS: usize = 1024;
+
+/// DataLoader cache whose entries expire `ttl` after being loaded, or never without a `ttl`.
+/// Clones share their invalidations, so one handle can evict keys from every loader built with it.
+#[derive(Clone)]
+pub struct TtlCache {
+    ttl: Option<Duration>,
+    // the eviction queue of every storage created from this cache, dropped along with its storage
+    storages: Arc<Mutex<Vec<Weak<Mutex<PendingEvictions>>>>>,
+}
+
+/// Keys invalidated since a storage last applied its evictions, by hash; a collision only causes an extra refetch.
+#[derive(Default)]
+struct PendingEvictions {
+    keys: HashSet<u64>,
+    clear: bool,
+}
+
+impl PendingEvictions {
+    fn push(&mut self, hash: u64) {
+        if self.clear {
+            return;
+        }
+
+        if self.keys.len() >= MAX_PENDING_EVICTIONS {
+            self.keys.clear();
+            self.clear = true;
+        } else {
+            self.keys.insert(hash);
+        }
+    }
+
+    fn evicts(&self, hash: u64) -> bool {
+        self.clear || self.keys.contains(&hash)
+    }
+}
+
+impl TtlCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_ttl(Some(ttl))
+    }
+
+    pub fn without_expiry() -> Self {
+        Self::with_ttl(None)
+    }
+
+    fn with_ttl(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            storages: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn invalidate<K: Hash>(&self, key: &K) {
+        let hash = key_hash(key);
+
+        self.storages.lock().unwrap().retain(|pending| match pending.upgrade() {
+            Some(pending) => {
+                pending.lock().unwrap().push(hash);
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+impl CacheFactory for TtlCache {
+    fn create<K, V>(&self) -> Box<dyn CacheStorage<Key = K, Value = V>>
+    where
+        K: Send + Sync + Clone + Eq + Hash + 'static,
+        V: Send + Sync + Clone + 'static,
+    {
+        let pending = Arc::new(Mutex::new(PendingEvictions::default()));
+        self.storages.lock().unwrap().push(Arc::downgrade(&pending));
+
+        Box::new(TtlCacheStorage {
+            ttl: self.ttl,
+            pending,
+            entries: HashMap::new(),
+        })
+    }
+}
+
+struct TtlCacheStorage<K, V> {
+    ttl: Option<Duration>,
+    pending: Arc<Mutex<PendingEvictions>>,
+    entries: HashMap<K, (V, Instant)>,
+}
+
+impl<K: Hash + Eq, V> TtlCacheStorage<K, V> {
+    fn is_fresh(&self, key: &K, loaded_at: &Instant) -> bool {
+        self.ttl.is_none_or(|ttl| loaded_at.elapsed() < ttl) && !self.pending.lock().unwrap().evicts(key_hash(key))
+    }
+
+    /// Removes the entries invalidated since the last call, so nothing is remembered past its eviction.
+    fn apply_evictions(&mut self) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+
+        if pending.clear {
+            self.entries.clear();
+        } else if !pending.keys.is_empty() {
+            self.entries.retain(|key, _| !pending.keys.contains(&key_hash(key)));
+        }
+    }
+}
+
+impl<K, V> CacheStorage for TtlCacheStorage<K, V>
+where
+    K: Send + Sync + Clone + Eq + Hash + 'static,
+    V: Send + Sync + Clone + 'static,
+{
+    type Key = K;
+    type Value = V;
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.apply_evictions();
+
+        let expired = self
+            .entries
+            .get(key)
+            .is_some_and(|(_, loaded_at)| !self.is_fresh(key, loaded_at));
+
+        if expired {
+            self.entries.remove(key);
+        }
+
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    fn insert(&mut self, key: Cow<'_, K>, value: Cow<'_, V>) {
+        self.apply_evictions();
+
+        if self.ttl.is_some_and(|ttl| ttl.is_zero()) {
+            return;
+        }
+
+        self.entries
+            .insert(key.into_owned(), (value.into_owned(), Instant::now()));
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.apply_evictions();
+        self.entries.remove(key);
+    }
+
+    fn clear(&mut self) {
+        self.apply_evictions();
+        self.entries.clear();
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&'_ K, &'_ V)> + '_> {
+        Box::new(
+            self.entries
+                .iter()
+                .filter(|(key, (_, loaded_at))| self.is_fresh(key, loaded_at))
+                .map(|(key, (value, _))| (key, value)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(cache: &TtlCache) -> Box<dyn CacheStorage<Key = Uuid, Value = String>> {
+        cache.create()
+    }
+
+    #[test]
+    fn without_expiry_keeps_loaded_values() {
+        let cache = TtlCache::without_expiry();
+        let mut entries = storage(&cache);
+        let key = Uuid::new_v4();
+
+        entries.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+
+        assert_eq!(entries.get(&key).map(String::as_str), Some("ada"));
+        assert_eq!(entries.iter().count(), 1);
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let cache = TtlCache::new(Duration::from_millis(20));
+        let mut entries = storage(&cache);
+        let key = Uuid::new_v4();
+
+        entries.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+        assert!(entries.get(&key).is_some());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(entries.get(&key).is_none());
+        assert_eq!(entries.iter().count(), 0);
+    }
+
+    #[test]
+    fn a_zero_ttl_caches_nothing() {
+        let cache = TtlCache::new(Duration::ZERO);
+        let mut entries = storage(&cache);
+        let key = Uuid::new_v4();
+
+        entries.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+
+        assert!(entries.get(&key).is_none());
+    }
+
+    #[test]
+    fn invalidation_evicts_from_every_storage() {
+        let cache = TtlCache::without_expiry();
+        let mut members = storage(&cache);
+        let mut labels = storage(&cache.clone());
+        let key = Uuid::new_v4();
+
+        members.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+        labels.insert(Cow::Owned(key), Cow::Owned("bug".to_string()));
+
+        cache.invalidate(&key);
+
+        assert!(members.get(&key).is_none());
+        assert!(labels.get(&key).is_none());
+
+        // values loaded after the invalidation are cached again
+        members.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+
+        assert!(members.get(&key).is_some());
+    }
+
+    #[test]
+    fn invalidations_are_forgotten_once_applied() {
+        let cache = TtlCache::without_expiry();
+        let mut entries = storage(&cache);
+
+        for _ in 0..10 {
+            let key = Uuid::new_v4();
+
+            entries.insert(Cow::Owned(key), Cow::Owned("ada".to_string()));
+            cache.invalidate(&key);
+
+            assert!(entries.get(&key).is_none());
+        }
+
+        assert!(entries.iter().next().is_none());
+
+        let storages = cache.storages.lock().unwrap();
+        assert_eq!(storages.len(), 1);
+        assert!(storages[0].upgrade().unwrap().lock().unwrap().keys.is_empty());
+    }
+
+    #[test]
+    fn too_many_pending_invalidations_clear_the_storage() {
+        let cache = TtlCache::without_expiry();
+        let mut entries = storage(&cache);
+        let kept = Uuid::new_v4();
+
+        entries.insert(Cow::Owned(kept), Cow::Owned("ada".to_string()));
+
+        for _ in 0..=MAX_PENDING_EVICTIONS {
+            cache.invalidate(&Uuid::new_v4());
         }
+
+        assert!(entries.get(&kept).is_none());
+    }
+
+    #[test]
+    fn dropped_storages_stop_receiving_invalidations() {
+        let cache = TtlCache::without_expiry();
+        drop(storage(&cache));
+
+        cache.invalidate(&Uuid::new_v4());
+
+        assert!(cache.storages.lock().unwrap().is_empty());
     }
 }
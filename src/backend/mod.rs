@@ -1,2 +1,4 @@
 pub mod engine;
+pub mod events;
 pub mod loaders;
+pub mod webhook;
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tracing::{error, warn};
+
+use super::events::{EventSink, SDKEvent};
+
+/// Events waiting to be delivered; once full, new ones are dropped rather than blocking the write.
+const WEBHOOK_QUEUE_CAPACITY: usize = 1024;
+const WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+const WEBHOOK_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// [`EventSink`] that POSTs every event as JSON to a URL from a background task, in commit order.
+/// A failed delivery is retried with exponential backoff, then logged and dropped; it never fails the write.
+pub struct WebhookSink {
+    queue: Sender<SDKEvent>,
+}
+
+impl WebhookSink {
+    /// Spawns the delivery task, so it has to be called from within a Tokio runtime.
+    pub fn new(url: impl Into<String>) -> WebhookSink {
+        let (queue, events) = mpsc::channel(WEBHOOK_QUEUE_CAPACITY);
+
+        tokio::spawn(deliver(reqwest::Client::new(), url.into(), events));
+
+        WebhookSink { queue }
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn emit(&self, event: SDKEvent) {
+        match self.queue.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("webhook queue is full, dropping event"),
+            Err(TrySendError::Closed(_)) => error!("webhook delivery task has stopped, dropping event"),
+        }
+    }
+}
+
+async fn deliver(client: reqwest::Client, url: String, mut events: Receiver<SDKEvent>) {
+    while let Some(event) = events.recv().await {
+        let mut backoff = WEBHOOK_INITIAL_BACKOFF;
+
+        for attempt in 1..=WEBHOOK_MAX_ATTEMPTS {
+            let delivery = client
+                .post(&url)
+                .timeout(WEBHOOK_REQUEST_TIMEOUT)
+                .json(&event)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match delivery {
+                Ok(_) => break,
+                Err(err) if attempt < WEBHOOK_MAX_ATTEMPTS => {
+                    warn!(%err, attempt, "webhook delivery failed, retrying");
+
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(err) => error!(%err, attempt, "webhook delivery failed, dropping event"),
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use async_openai::types::CreateEmbeddingRequestArgs;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    backend::engine::{LlmProvider, SDKEngine},
+    errors::sdk::SDKError,
+    resources::tasks::{
+        operations::{GetTasksInputBuilder, GetTasksWhereBuilder, TaskCrudOperations},
+        task::Task,
+    },
+};
+
+pub const EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+#[async_trait]
+pub trait TaskEmbeddingOperations {
+    async fn embed_task(&self, task_id: Uuid) -> Result<Vec<f32>, SDKError>;
+    async fn find_similar_tasks(&self, task_id: Uuid, limit: usize) -> Result<Vec<(Task, f32)>, SDKError>;
+}
+
+/// Returns 0.0 when the vectors differ in length or either of them is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
+
+fn task_embedding_text(task: &Task) -> String {
+    format!("{}\n\n{}", task.title, task.description.clone().unwrap_or_default())
+}
+
+#[async_trait]
+impl TaskEmbeddingOperations for SDKEngine {
+    async fn embed_task(&self, task_id: Uuid) -> Result<Vec<f32>, SDKError> {
+        if self.config.llm_provider == LlmProvider::Anthropic {
+            return Err(SDKError::InvalidConfig(
+                "embeddings are not available with the Anthropic provider".to_string(),
+            ));
+        }
+
+        let task = self.get_task(task_id).await?;
+
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(EMBEDDING_MODEL)
+            .input(task_embedding_text(&task))
+            .build()
+            .unwrap();
+
+        let response = self
+            .llm_client
+            .embeddings()
+            .create(request)
+            .await
+            .map_err(|err| SDKError::LLMResponseError(err.to_string()))?;
+
+        let embedding = response
+            .data
+            .into_iter()
+            .next()
+            .map(|data| data.embedding)
+            .ok_or_else(|| SDKError::LLMResponseError("response has no embedding".to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO task_embeddings (task_id, model, embedding)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (task_id) DO UPDATE
+            SET model = EXCLUDED.model, embedding = EXCLUDED.embedding
+            "#,
+            task_id,
+            EMBEDDING_MODEL,
+            &embedding,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(embedding)
+    }
+
+    /// Only tasks that already have a stored embedding are ranked; the reference task is embedded on demand.
+    async fn find_similar_tasks(&self, task_id: Uuid, limit: usize) -> Result<Vec<(Task, f32)>, SDKError> {
+        let stored = sqlx::query!(
+            r#"
+            SELECT embedding FROM task_embeddings
+            WHERE task_id = $1 AND model = $2
+            "#,
+            task_id,
+            EMBEDDING_MODEL,
+        )
+        .fetch_optional(&mut *self.acquire_read().await?)
+        .await?;
+
+        let reference = match stored {
+            Some(row) => row.embedding,
+            None => self.embed_task(task_id).await?,
+        };
+
+        let candidates = sqlx::query!(
+            r#"
+            SELECT task_id, embedding FROM task_embeddings
+            WHERE task_id <> $1 AND model = $2
+            "#,
+            task_id,
+            EMBEDDING_MODEL,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        let mut scores: Vec<(Uuid, f32)> = candidates
+            .into_iter()
+            .map(|candidate| (candidate.task_id, cosine_similarity(&reference, &candidate.embedding)))
+            .collect();
+
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(limit);
+
+        if scores.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let filter = GetTasksWhereBuilder::default()
+            .ids(scores.iter().map(|(id, _)| *id).collect())
+            .build()
+            .unwrap();
+
+        let input = GetTasksInputBuilder::default()
+            .filter(filter)
+            .limit(scores.len() as i32)
+            .include_archived(true)
+            .build()
+            .unwrap();
+
+        let mut tasks = self.get_tasks(Some(input)).await?;
+
+        Ok(scores
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let index = tasks.iter().position(|task| task.id == id)?;
+                Some((tasks.swap_remove(index), score))
+            })
+            .collect())
+    }
+}
@@ -1,2 +1,4 @@
+pub mod embeddings;
 pub mod operations;
+pub mod prompts;
 pub mod suggestions;
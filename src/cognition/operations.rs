@@ -1,27 +1,37 @@
+use std::{collections::BTreeSet, future::Future, pin::Pin};
+
 use async_graphql::{InputObject, SimpleObject};
 use async_trait::async_trait;
 use chrono::{DateTime, Local, Utc};
 use derive_builder::Builder;
+use futures::future::join_all;
 use poem_openapi::Object;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     backend::engine::SDKEngine,
     errors::sdk::SDKError,
-    resources::tasks::{
-        operations::TaskCrudOperations,
-        task::{TaskPriority, TaskStatus},
+    resources::{
+        labels::{label::Label, operations::is_hex_color},
+        tasks::{
+            extensions::{CreateTasksInput, TasksExtensionOperations},
+            operations::{CreateTaskInput, TaskCrudOperations},
+            task::{Task, TaskPriority, TaskStatus},
+        },
     },
 };
 
-use super::suggestions::CognitionCapabilities;
+use super::{prompts::render_prompt, suggestions::CognitionCapabilities};
 
 #[derive(Default, Builder, Object, InputObject)]
 #[builder(pattern = "owned")]
 pub struct TaskSuggestionInput {
     #[builder(setter(strip_option), default)]
     pub project_id: Option<Uuid>,
+    /// Limits the context tasks to this member's own, e.g. for personal suggestions.
+    #[builder(setter(strip_option), default)]
+    pub owner_id: Option<Uuid>,
 
     #[builder(setter(strip_option), default)]
     pub title: Option<String>,
@@ -33,9 +43,15 @@ pub struct TaskSuggestionInput {
     pub priority: Option<TaskPriority>,
     #[builder(setter(strip_option), default)]
     pub due_date: Option<DateTime<Utc>>,
+
+    #[builder(setter(strip_option), default)]
+    pub temperature: Option<f32>,
+    /// Language the model should answer in, e.g. `es` or `fr`; English when unset.
+    #[builder(setter(into, strip_option), default)]
+    pub locale: Option<String>,
 }
 
-#[derive(Debug, Default, Builder, Object, SimpleObject, Deserialize)]
+#[derive(Debug, Default, Builder, Object, SimpleObject, Deserialize, Serialize)]
 #[builder(pattern = "owned")]
 pub struct TaskSuggestion {
     pub title: String,
@@ -43,96 +59,514 @@ pub struct TaskSuggestion {
     pub status: TaskStatus,
     pub priority: TaskPriority,
     pub due_date: DateTime<Utc>,
+
+    #[builder(default)]
+    #[serde(skip)]
+    pub usage: Option<TokenUsage>,
+}
+
+/// A suggestion as the model answered it, so a missing field can be reported by name
+/// instead of failing the whole parse or falling back to a default.
+#[derive(Deserialize)]
+struct RawTaskSuggestion {
+    title: Option<String>,
+    description: Option<String>,
+    status: Option<TaskStatus>,
+    priority: Option<TaskPriority>,
+    due_date: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<RawTaskSuggestion> for TaskSuggestion {
+    type Error = SDKError;
+
+    fn try_from(raw: RawTaskSuggestion) -> Result<Self, Self::Error> {
+        let missing = |field| SDKError::IncompleteSuggestion { field };
+
+        Ok(TaskSuggestion {
+            title: raw.title.ok_or_else(|| missing("title"))?,
+            description: raw.description.ok_or_else(|| missing("description"))?,
+            status: raw.status.ok_or_else(|| missing("status"))?,
+            priority: raw.priority.ok_or_else(|| missing("priority"))?,
+            due_date: raw.due_date.ok_or_else(|| missing("due_date"))?,
+            usage: None,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Object, SimpleObject)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+#[derive(Debug, Default, Object, SimpleObject)]
+pub struct SubdivideTaskResult {
+    pub subtasks: Vec<TaskSuggestion>,
+    pub usage: TokenUsage,
 }
 
 #[derive(Default, Builder, Object, InputObject)]
 #[builder(pattern = "owned")]
 pub struct SubdivideTaskInput {
     pub task_id: Uuid,
-    pub subtasks: u8, // TODO: validate it or die
+    pub subtasks: u8,
+
+    #[builder(setter(strip_option), default)]
+    pub temperature: Option<f32>,
+    /// Language the model should answer in, e.g. `es` or `fr`; English when unset.
+    #[builder(setter(into, strip_option), default)]
+    pub locale: Option<String>,
 }
 
+#[derive(Debug, Object, SimpleObject)]
+pub struct SuggestionNode {
+    pub suggestion: TaskSuggestion,
+    pub children: Vec<SuggestionNode>,
+}
+
+pub const MAX_SUBTASKS: u8 = 20;
+pub const MAX_SUBDIVISION_DEPTH: u8 = 3;
+/// Most suggestions a recursive subdivision may generate over all its levels, bounding its LLM calls.
+pub const MAX_SUGGESTION_NODES: usize = 500;
+/// Upper bound for `SDKConfig::cognition_context_size`, keeping the prompt within the model's context window.
+pub const MAX_COGNITION_CONTEXT_SIZE: u32 = 100;
+
+// minute precision keeps prompts stable between close calls, so they can be served from the cache
+const PROMPT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M %:z";
+
 #[async_trait]
 pub trait CognitionOperations {
     async fn get_suggestions(&self, input: TaskSuggestionInput) -> Result<TaskSuggestion, SDKError>;
-    async fn subdivide_task(&self, input: SubdivideTaskInput) -> Result<Vec<TaskSuggestion>, SDKError>;
+    async fn subdivide_task(&self, input: SubdivideTaskInput) -> Result<SubdivideTaskResult, SDKError>;
+    async fn subdivide_tasks(&self, inputs: Vec<SubdivideTaskInput>) -> Vec<Result<SubdivideTaskResult, SDKError>>;
+    async fn subdivide_task_recursive(
+        &self,
+        input: SubdivideTaskInput,
+        depth: u8,
+    ) -> Result<Vec<SuggestionNode>, SDKError>;
+    async fn suggest_labels(&self, task_id: Uuid) -> Result<Vec<Label>, SDKError>;
+    async fn accept_suggestion(
+        &self,
+        suggestion: TaskSuggestion,
+        owner_id: Uuid,
+        project_id: Option<Uuid>,
+    ) -> Result<Task, SDKError>;
+    async fn accept_suggestions(
+        &self,
+        parent_id: Uuid,
+        suggestions: Vec<TaskSuggestion>,
+    ) -> Result<Vec<Task>, SDKError>;
+    async fn suggest_label_color(&self, name: String) -> Result<String, SDKError>;
+}
+
+/// `#rgb` or `#rrggbb` as lowercase `#rrggbb`, so the same color always compares equal.
+fn normalize_color(color: &str) -> Option<String> {
+    let color = color.trim();
+
+    if !is_hex_color(color) {
+        return None;
+    }
+
+    let digits = &color[1..];
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        _ => digits.to_string(),
+    };
+
+    Some(format!("#{}", digits.to_ascii_lowercase()))
+}
+
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// Keeps the model's color when it is valid hex and not taken, otherwise walks the hue circle by the
+/// golden angle, which keeps consecutive picks far apart, until it finds a color that isn't in `used`.
+fn pick_label_color(answer: &str, used: &BTreeSet<String>) -> String {
+    if let Some(color) = normalize_color(answer).filter(|color| !used.contains(color)) {
+        return color;
+    }
+
+    (0u32..)
+        .map(|i| hsl_to_hex((f64::from(i) * 137.508) % 360.0, 0.65, 0.5))
+        .find(|color| !used.contains(color))
+        .unwrap()
+}
+
+fn suggestion_to_input(suggestion: TaskSuggestion, owner_id: Uuid, project_id: Option<Uuid>) -> CreateTaskInput {
+    CreateTaskInput {
+        title: suggestion.title,
+        owner_id,
+        status: Some(suggestion.status),
+        priority: Some(suggestion.priority),
+        description: Some(suggestion.description),
+        due_date: Some(suggestion.due_date),
+        project_id,
+        ..Default::default()
+    }
+}
+
+fn localize_system_message(system_message: String, locale: Option<&str>) -> String {
+    match locale {
+        Some(locale) => format!(
+            "{}
+        Write every text value in the language of the locale `{}`, but keep the JSON keys and the TaskStatus and TaskPriority values in English.",
+            system_message, locale,
+        ),
+        None => system_message,
+    }
+}
+
+/// Checked before any LLM call, so an absurd count never reaches the model.
+fn validate_subtask_count(subtasks: u8) -> Result<(), SDKError> {
+    if subtasks == 0 || subtasks > MAX_SUBTASKS {
+        return Err(SDKError::InvalidSubtaskCount(subtasks));
+    }
+
+    Ok(())
+}
+
+/// A full tree of `depth` extra levels holds `subtasks + subtasks² + … + subtasks^(depth + 1)` suggestions,
+/// each level but the last costing one LLM call per suggestion, so it is checked before the first call.
+fn validate_subdivision_size(subtasks: u8, depth: u8) -> Result<(), SDKError> {
+    let nodes = (1..=u32::from(depth) + 1)
+        .map(|level| usize::from(subtasks).pow(level))
+        .sum();
+
+    if nodes > MAX_SUGGESTION_NODES {
+        return Err(SDKError::SubdivisionTooLarge(nodes));
+    }
+
+    Ok(())
+}
+
+async fn generate_subtasks(
+    engine: &SDKEngine,
+    parent_fingerprint: String,
+    input: &SubdivideTaskInput,
+) -> Result<SubdivideTaskResult, SDKError> {
+    let prompts = &engine.config.cognition_prompts;
+
+    let system_message = localize_system_message(prompts.subdivide_system.clone(), input.locale.as_deref());
+    let user_message = render_prompt(
+        &prompts.subdivide_user,
+        &[
+            ("time", &Local::now().format(PROMPT_TIME_FORMAT).to_string()),
+            ("context", &parent_fingerprint),
+            ("count", &input.subtasks.to_string()),
+        ],
+    );
+
+    let (subtasks, usage): (Vec<RawTaskSuggestion>, _) = engine
+        .chat_completion_json(system_message, user_message, input.temperature)
+        .await?;
+
+    let subtasks = subtasks
+        .into_iter()
+        .map(TaskSuggestion::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SubdivideTaskResult { subtasks, usage })
+}
+
+fn subdivide_suggestion<'a>(
+    engine: &'a SDKEngine,
+    suggestion: TaskSuggestion,
+    input: &'a SubdivideTaskInput,
+    depth: u8,
+) -> Pin<Box<dyn Future<Output = Result<SuggestionNode, SDKError>> + Send + 'a>> {
+    Box::pin(async move {
+        if depth == 0 {
+            return Ok(SuggestionNode {
+                suggestion,
+                children: vec![],
+            });
+        }
+
+        let fingerprint = serde_json::to_string(&suggestion)?;
+        let result = generate_subtasks(engine, fingerprint, input).await?;
+
+        let mut children = Vec::with_capacity(result.subtasks.len());
+
+        // extra suggestions from the model would grow the tree past the checked size
+        for child in result.subtasks.into_iter().take(input.subtasks.into()) {
+            children.push(subdivide_suggestion(engine, child, input, depth - 1).await?);
+        }
+
+        Ok(SuggestionNode { suggestion, children })
+    })
 }
 
 #[async_trait]
 impl CognitionOperations for SDKEngine {
     async fn get_suggestions(&self, input: TaskSuggestionInput) -> Result<TaskSuggestion, SDKError> {
-        let tasks_fingerprints = self.acquire_tasks_fingerprints(10, input.project_id).await;
-
-        let system_message =
-            "The user pass to you a list of tasks and you should predict the following based on the input of the user.
-        Please return only a valid json with the following struct {
-                title: String,
-                description: String,
-                status: TaskStatus,
-                priority: TaskPriority,
-                due_date: DateTime<Utc>
-        }"
-            .to_string();
-
-        let user_message = format!(
-            "
-            Current Time:
-            {}
-
-            Current Tasks Context: 
-            {}
-            
-            With the above context, complete the following task, only fill the <suggest> fields:
-            {}",
-            Local::now(),
-            tasks_fingerprints.join("\n\n"),
-            Self::calculate_task_suggestion_fingerprint(input),
+        let context_size = self.config.cognition_context_size.min(MAX_COGNITION_CONTEXT_SIZE);
+        let tasks_fingerprints = self
+            .acquire_tasks_fingerprints(context_size, input.project_id, input.owner_id)
+            .await?;
+        let temperature = input.temperature;
+        let locale = input.locale.clone();
+
+        let prompts = &self.config.cognition_prompts;
+
+        let system_message = localize_system_message(prompts.suggestion_system.clone(), locale.as_deref());
+        let user_message = render_prompt(
+            &prompts.suggestion_user,
+            &[
+                ("time", &Local::now().format(PROMPT_TIME_FORMAT).to_string()),
+                ("context", &tasks_fingerprints.join("\n\n")),
+                ("task", &Self::calculate_task_suggestion_fingerprint(input)),
+            ],
         );
 
-        let result = self.chat_completion(system_message, user_message).await;
-        let result = result.trim().trim_matches('`');
+        let (raw_suggestion, usage): (RawTaskSuggestion, _) = self
+            .chat_completion_json(system_message, user_message, temperature)
+            .await?;
 
-        let suggestion_result: TaskSuggestion = serde_json::from_str(result)?;
+        let mut suggestion_result = TaskSuggestion::try_from(raw_suggestion)?;
+        suggestion_result.usage = Some(usage);
 
         Ok(suggestion_result)
     }
 
-    async fn subdivide_task(&self, input: SubdivideTaskInput) -> Result<Vec<TaskSuggestion>, SDKError> {
+    async fn subdivide_task(&self, input: SubdivideTaskInput) -> Result<SubdivideTaskResult, SDKError> {
+        validate_subtask_count(input.subtasks)?;
+
+        let task = self.get_task(input.task_id).await?;
+
+        generate_subtasks(self, Self::calculate_task_fingerprint(task), &input).await
+    }
+
+    /// Runs `subdivide_task` for every input at once, still within `cognition_max_concurrency`.
+    /// Results come back in input order, and a failing input doesn't cancel the others.
+    async fn subdivide_tasks(&self, inputs: Vec<SubdivideTaskInput>) -> Vec<Result<SubdivideTaskResult, SDKError>> {
+        join_all(inputs.into_iter().map(|input| self.subdivide_task(input))).await
+    }
+
+    /// Depth 0 returns the same suggestions as `subdivide_task`, as leaves; each extra level
+    /// subdivides every suggestion again with the same subtask count. Trees that would hold more
+    /// than [`MAX_SUGGESTION_NODES`] suggestions are rejected before any LLM call.
+    async fn subdivide_task_recursive(
+        &self,
+        input: SubdivideTaskInput,
+        depth: u8,
+    ) -> Result<Vec<SuggestionNode>, SDKError> {
+        if depth > MAX_SUBDIVISION_DEPTH {
+            return Err(SDKError::InvalidSubdivisionDepth(depth));
+        }
+
+        validate_subtask_count(input.subtasks)?;
+        validate_subdivision_size(input.subtasks, depth)?;
+
         let task = self.get_task(input.task_id).await?;
+        let result = generate_subtasks(self, Self::calculate_task_fingerprint(task), &input).await?;
+
+        let mut nodes = Vec::with_capacity(result.subtasks.len());
+
+        for suggestion in result.subtasks.into_iter().take(input.subtasks.into()) {
+            nodes.push(subdivide_suggestion(self, suggestion, &input, depth).await?);
+        }
 
-        let system_message = "The user pass to you one task and you should predict a list of subtasks.
-        Please return only a valid json with the following struct [{
-                title: String,
-                description: String,
-                status: TaskStatus,
-                priority: TaskPriority,
-                due_date: DateTime<Utc>
-        }]
-        For TaskStatus and TaskPriority, please use the following values:
-        TaskStatus: None, Backlog, ToDo, InProgress, Done, Canceled
-        TaskPriority: None, Low, Medium, High, Urgent
-        "
-        .to_string();
-
-        let user_message = format!(
-            "
-            Current Time:
-            {}
-
-            Parent Task: 
-            {}
-            
-            With the above context, generate {} subtasks.",
-            Local::now(),
-            Self::calculate_task_fingerprint(task),
-            input.subtasks,
+        Ok(nodes)
+    }
+
+    /// Names returned by the model that don't match an existing label are ignored.
+    async fn suggest_labels(&self, task_id: Uuid) -> Result<Vec<Label>, SDKError> {
+        let task = self.get_task(task_id).await?;
+
+        let labels = sqlx::query!(
+            r#"
+            SELECT * FROM labels
+            ORDER BY name ASC
+            "#,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        if labels.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let prompts = &self.config.cognition_prompts;
+
+        let system_message = prompts.labels_system.clone();
+        let user_message = render_prompt(
+            &prompts.labels_user,
+            &[
+                (
+                    "context",
+                    &labels
+                        .iter()
+                        .map(|label| match &label.description {
+                            Some(description) => format!("- {}: {}", label.name, description),
+                            None => format!("- {}", label.name),
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                ),
+                ("task", &Self::calculate_task_fingerprint(task)),
+            ],
         );
 
-        let result = self.chat_completion(system_message, user_message).await;
-        let result = result.trim().trim_matches('`');
+        let (names, _): (Vec<String>, _) = self.chat_completion_json(system_message, user_message, None).await?;
+
+        let names: Vec<String> = names.iter().map(|name| name.trim().to_lowercase()).collect();
+
+        Ok(labels
+            .into_iter()
+            .filter(|label| names.contains(&label.name.to_lowercase()))
+            .map(|label_info| Label {
+                id: label_info.id,
+                created_at: label_info.created_at,
+                updated_at: label_info.updated_at,
+                name: label_info.name,
+                owner_id: label_info.owner_id,
+                description: label_info.description,
+                color: label_info.color,
+            })
+            .collect())
+    }
+
+    /// Asks the model for a color that stands apart from the existing labels' ones. The result is always a
+    /// lowercase `#rrggbb` that no label uses yet, even when the model's answer was invalid or taken.
+    async fn suggest_label_color(&self, name: String) -> Result<String, SDKError> {
+        let used: BTreeSet<String> = sqlx::query!(
+            r#"
+            SELECT DISTINCT color AS "color!" FROM labels
+            WHERE color IS NOT NULL
+            "#,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?
+        .iter()
+        .filter_map(|label| normalize_color(&label.color))
+        .collect();
+
+        let context = match used.is_empty() {
+            true => "none".to_string(),
+            false => used.iter().cloned().collect::<Vec<String>>().join("\n"),
+        };
+
+        let prompts = &self.config.cognition_prompts;
+
+        let system_message = prompts.label_color_system.clone();
+        let user_message = render_prompt(&prompts.label_color_user, &[("name", &name), ("context", &context)]);
+
+        let (answer, _): (String, _) = self.chat_completion_json(system_message, user_message, None).await?;
+
+        Ok(pick_label_color(&answer, &used))
+    }
+
+    /// Creates the task a suggestion describes, through `create_task`, so the usual validation applies.
+    async fn accept_suggestion(
+        &self,
+        suggestion: TaskSuggestion,
+        owner_id: Uuid,
+        project_id: Option<Uuid>,
+    ) -> Result<Task, SDKError> {
+        self.create_task(suggestion_to_input(suggestion, owner_id, project_id))
+            .await
+    }
+
+    /// Creates subdivision suggestions as subtasks of `parent_id`, in its project and owned by its owner.
+    /// All of them are created in one transaction, or none are.
+    async fn accept_suggestions(
+        &self,
+        parent_id: Uuid,
+        suggestions: Vec<TaskSuggestion>,
+    ) -> Result<Vec<Task>, SDKError> {
+        let parent = self.get_task(parent_id).await?;
+
+        let tasks = suggestions
+            .into_iter()
+            .map(|suggestion| CreateTaskInput {
+                parent_id: Some(parent.id),
+                ..suggestion_to_input(suggestion, parent.owner_id, parent.project_id)
+            })
+            .collect();
+
+        self.create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtask_counts_must_be_between_one_and_the_max() {
+        assert!(matches!(
+            validate_subtask_count(0),
+            Err(SDKError::InvalidSubtaskCount(0))
+        ));
+        assert!(validate_subtask_count(1).is_ok());
+        assert!(validate_subtask_count(MAX_SUBTASKS).is_ok());
+        assert!(matches!(
+            validate_subtask_count(21),
+            Err(SDKError::InvalidSubtaskCount(21))
+        ));
+    }
+
+    #[test]
+    fn locale_adds_a_language_directive_to_the_system_message() {
+        let localized = localize_system_message("system".to_string(), Some("es"));
+
+        assert!(localized.starts_with("system\n"));
+        assert!(localized.contains("language of the locale `es`"));
+        assert_eq!(localize_system_message("system".to_string(), None), "system");
+    }
+
+    #[test]
+    fn subdivision_size_counts_every_level() {
+        assert!(validate_subdivision_size(2, 2).is_ok());
+        assert!(validate_subdivision_size(MAX_SUBTASKS, 1).is_ok());
+        assert!(matches!(
+            validate_subdivision_size(MAX_SUBTASKS, 2),
+            Err(SDKError::SubdivisionTooLarge(8420))
+        ));
+        assert!(matches!(
+            validate_subdivision_size(MAX_SUBTASKS, MAX_SUBDIVISION_DEPTH),
+            Err(SDKError::SubdivisionTooLarge(168420))
+        ));
+    }
+
+    #[test]
+    fn picked_label_colors_are_normalized_and_never_taken() {
+        assert_eq!(normalize_color(" #ABC "), Some("#aabbcc".to_string()));
+        assert_eq!(normalize_color("red"), None);
+
+        let mut used = BTreeSet::new();
+        assert_eq!(pick_label_color("#ABC", &used), "#aabbcc");
+
+        // an invalid or taken answer falls back to a generated color, distinct from every earlier pick
+        for answer in ["not a color", "#aabbcc"] {
+            used.insert("#aabbcc".to_string());
 
-        let subtasks: Vec<TaskSuggestion> = serde_json::from_str(result)?;
+            for _ in 0..10 {
+                let color = pick_label_color(answer, &used);
 
-        Ok(subtasks)
+                assert!(normalize_color(&color).as_ref() == Some(&color));
+                assert!(used.insert(color));
+            }
+        }
     }
 }
@@ -0,0 +1,149 @@
+/// Templates used by the cognition operations. `{name}` placeholders are filled by [`render_prompt`]:
+///
+/// - suggestions: `{time}`, `{context}` (recent tasks) and `{task}` (the partial task to complete)
+/// - subdivision: `{time}`, `{context}` (the parent task) and `{count}` (number of subtasks)
+/// - labels: `{context}` (the available label names) and `{task}` (the task to label)
+/// - label colors: `{name}` (the new label) and `{context}` (the colors already in use)
+#[derive(Debug, Clone)]
+pub struct CognitionPrompts {
+    pub suggestion_system: String,
+    pub suggestion_user: String,
+    pub subdivide_system: String,
+    pub subdivide_user: String,
+    pub labels_system: String,
+    pub labels_user: String,
+    pub label_color_system: String,
+    pub label_color_user: String,
+}
+
+impl Default for CognitionPrompts {
+    fn default() -> Self {
+        CognitionPrompts {
+            suggestion_system:
+                "The user pass to you a list of tasks and you should predict the following based on the input of the user.
+        Please return only a valid json with the following struct {
+                title: String,
+                description: String,
+                status: TaskStatus,
+                priority: TaskPriority,
+                due_date: DateTime<Utc>
+        }"
+                .to_string(),
+            suggestion_user: "
+            Current Time:
+            {time}
+
+            Current Tasks Context: 
+            {context}
+            
+            With the above context, complete the following task, only fill the <suggest> fields:
+            {task}"
+                .to_string(),
+            subdivide_system: "The user pass to you one task and you should predict a list of subtasks.
+        Please return only a valid json with the following struct [{
+                title: String,
+                description: String,
+                status: TaskStatus,
+                priority: TaskPriority,
+                due_date: DateTime<Utc>
+        }]
+        For TaskStatus and TaskPriority, please use the following values:
+        TaskStatus: None, Backlog, ToDo, InProgress, Done, Canceled
+        TaskPriority: None, Low, Medium, High, Urgent
+        "
+            .to_string(),
+            subdivide_user: "
+            Current Time:
+            {time}
+
+            Parent Task: 
+            {context}
+            
+            With the above context, generate {count} subtasks."
+                .to_string(),
+            labels_system: "The user pass to you a task and the list of available labels, you should pick the labels that apply to the task.
+        Please return only a valid json array with the names of the chosen labels, e.g. [\"bug\", \"frontend\"].
+        Only use names from the list of available labels, return an empty array if none of them apply.
+        "
+            .to_string(),
+            labels_user: "
+            Available Labels:
+            {context}
+
+            Task:
+            {task}"
+                .to_string(),
+            label_color_system: "The user pass to you the name of a new label and the colors of the existing labels, you should pick a color for the new label.
+        Please return only a valid json string with a hex color, e.g. \"#1f77b4\".
+        The color should suit the label name and be easy to tell apart from every color in use, never return one of them.
+        "
+            .to_string(),
+            label_color_user: "
+            Colors In Use:
+            {context}
+
+            New Label:
+            {name}"
+                .to_string(),
+        }
+    }
+}
+
+/// Replaces every `{name}` placeholder; unknown placeholders and other braces are left untouched.
+/// Only the template is scanned, so a value containing a placeholder is inserted as is.
+pub fn render_prompt(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let value = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, *value))
+        });
+
+        match value {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_replaced_and_other_braces_kept() {
+        assert_eq!(
+            render_prompt(
+                "{task} in {project}: {\"json\": 1} {unknown}",
+                &[("task", "ship"), ("project", "launch")]
+            ),
+            "ship in launch: {\"json\": 1} {unknown}"
+        );
+    }
+
+    #[test]
+    fn values_are_not_rendered_again() {
+        let rendered = render_prompt(
+            "Context:\n{context}\nTask:\n{task}",
+            &[("context", "a task titled {task}"), ("task", "the draft")],
+        );
+
+        assert_eq!(rendered, "Context:\na task titled {task}\nTask:\nthe draft");
+    }
+}
@@ -1,108 +1,478 @@
 use async_openai::types::{
     ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs,
 };
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    time::{Duration, Instant},
+};
+
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tracing::{instrument, Span};
 
 use uuid::Uuid;
 
-use super::operations::TaskSuggestionInput;
+use super::operations::{TaskSuggestionInput, TokenUsage};
 use crate::{
-    backend::engine::SDKEngine,
+    backend::engine::{LlmProvider, SDKEngine},
+    common::commons::FilterOp,
+    errors::sdk::SDKError,
     resources::tasks::{
-        operations::{GetTasksInputBuilder, TaskCrudOperations},
+        operations::{GetTasksInput, GetTasksWhere, TaskCrudOperations},
         task::Task,
     },
 };
 
 #[async_trait]
 pub trait CognitionCapabilities {
-    async fn chat_completion(&self, system_message: String, user_message: String) -> String;
-    async fn acquire_tasks_fingerprints(&self, number_of_tasks: u32, project_id: Option<Uuid>) -> Vec<String>;
+    async fn chat_completion(
+        &self,
+        system_message: String,
+        user_message: String,
+        temperature: Option<f32>,
+    ) -> Result<ChatCompletionResult, SDKError>;
+    async fn chat_completion_json<T: DeserializeOwned>(
+        &self,
+        system_message: String,
+        user_message: String,
+        temperature: Option<f32>,
+    ) -> Result<(T, TokenUsage), SDKError>;
+    async fn acquire_tasks_fingerprints(
+        &self,
+        number_of_tasks: u32,
+        project_id: Option<Uuid>,
+        owner_id: Option<Uuid>,
+    ) -> Result<Vec<String>, SDKError>;
 
     fn calculate_task_fingerprint(task: Task) -> String;
     fn calculate_task_suggestion_fingerprint(task_suggestion: TaskSuggestionInput) -> String;
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ChatCompletionResult {
+    pub content: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// Pulls the JSON payload out of a model answer, dropping Markdown fences and any surrounding prose.
+pub fn extract_json(raw: &str) -> &str {
+    let raw = raw.trim();
+
+    let raw = match raw.find("```") {
+        Some(fence_start) => {
+            let fenced = &raw[fence_start + 3..];
+            // skip the info string (e.g. `json`) that follows the opening fence
+            let fenced = fenced
+                .find('\n')
+                .map(|line_end| &fenced[line_end + 1..])
+                .unwrap_or(fenced);
+
+            fenced
+                .find("```")
+                .map(|fence_end| &fenced[..fence_end])
+                .unwrap_or(fenced)
+        }
+        None => raw,
+    };
+
+    let start = raw.find(['{', '[']);
+    let end = raw.rfind(['}', ']']);
+
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &raw[start..=end],
+        _ => raw.trim(),
+    }
+}
+
+/// Prompt context for an existing task: its JSON serialization, fields in [`Task`] declaration
+/// order with unset optionals as `null`, e.g. `{"id":"…",…,"description":"","due_date":null,…}`.
+pub fn task_fingerprint(task: &Task) -> String {
+    serde_json::to_string(task).unwrap()
+}
+
+/// Prompt block for a task being drafted, one line per field:
+///
+/// ```text
+/// Task Title: <title>
+/// Task Description: <description>
+/// Task Status: <status>
+/// Task Priority: <priority>
+/// Task Due Date: <RFC 3339 due date>
+/// ```
+///
+/// Fields left unset read `<suggest>`, asking the model to fill them in.
+pub fn task_suggestion_fingerprint(task_suggestion: &TaskSuggestionInput) -> String {
+    let suggest = || "<suggest>".to_string();
+
+    format!(
+        "Task Title: {}\nTask Description: {}\nTask Status: {}\nTask Priority: {}\nTask Due Date: {}",
+        task_suggestion.title.clone().unwrap_or_else(suggest),
+        task_suggestion.description.clone().unwrap_or_else(suggest),
+        task_suggestion.status.map(|s| s.to_string()).unwrap_or_else(suggest),
+        task_suggestion.priority.map(|p| p.to_string()).unwrap_or_else(suggest),
+        task_suggestion.due_date.map(|d| d.to_rfc3339()).unwrap_or_else(suggest),
+    )
+}
+
+fn prompt_cache_key(system_message: &str, user_message: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    system_message.hash(&mut hasher);
+    user_message.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+const ANTHROPIC_MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// How long a cognition call waits for a free slot when `cognition_max_concurrency` is set.
+pub const COGNITION_PERMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const MIN_TEMPERATURE: f32 = 0.0;
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+async fn anthropic_chat_completion(
+    engine: &SDKEngine,
+    system_message: String,
+    user_message: String,
+    temperature: Option<f32>,
+) -> Result<ChatCompletionResult, SDKError> {
+    let mut body = json!({
+        "model": engine.config.llm_model_name,
+        "max_tokens": 1024,
+        "system": system_message,
+        "messages": [{ "role": "user", "content": user_message }],
+    });
+
+    // Anthropic only accepts temperatures up to 1.0
+    if let Some(temperature) = temperature {
+        body["temperature"] = json!(temperature.min(1.0));
+    }
+
+    let response: Value = engine
+        .http_client
+        .post(ANTHROPIC_MESSAGES_URL)
+        .header("x-api-key", engine.config.llm_api_key.clone())
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let content = response["content"][0]["text"]
+        .as_str()
+        .ok_or_else(|| SDKError::LLMResponseError("response has no text content".to_string()))?;
+
+    Ok(ChatCompletionResult {
+        content: content.to_string(),
+        prompt_tokens: response["usage"]["input_tokens"].as_u64().unwrap_or_default() as u32,
+        completion_tokens: response["usage"]["output_tokens"].as_u64().unwrap_or_default() as u32,
+    })
+}
+
+async fn openai_chat_completion(
+    engine: &SDKEngine,
+    system_message: String,
+    user_message: String,
+    temperature: Option<f32>,
+) -> Result<ChatCompletionResult, SDKError> {
+    let mut request = CreateChatCompletionRequestArgs::default();
+
+    if let Some(temperature) = temperature {
+        request.temperature(temperature);
+    }
+
+    let request = request
+        .max_tokens(1024u16)
+        .model(engine.config.llm_model_name.clone())
+        .messages([
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(system_message)
+                .build()
+                .unwrap()
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(user_message)
+                .build()
+                .unwrap()
+                .into(),
+        ])
+        .build()
+        .unwrap();
+
+    let response = engine
+        .llm_client
+        .chat()
+        .create(request)
+        .await
+        .map_err(|err| SDKError::LLMResponseError(err.to_string()))?;
+
+    let content = response
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.clone())
+        .ok_or_else(|| SDKError::LLMResponseError("response has no message content".to_string()))?;
+
+    Ok(ChatCompletionResult {
+        content,
+        prompt_tokens: response.usage.as_ref().map(|u| u.prompt_tokens).unwrap_or_default(),
+        completion_tokens: response.usage.as_ref().map(|u| u.completion_tokens).unwrap_or_default(),
+    })
+}
+
 #[async_trait]
 impl CognitionCapabilities for SDKEngine {
-    async fn chat_completion(&self, system_message: String, user_message: String) -> String {
-        let request = CreateChatCompletionRequestArgs::default()
-            .max_tokens(1024u16)
-            .model(self.config.llm_model_name.clone())
-            .messages([
-                ChatCompletionRequestSystemMessageArgs::default()
-                    .content(system_message)
-                    .build()
-                    .unwrap()
-                    .into(),
-                ChatCompletionRequestUserMessageArgs::default()
-                    .content(user_message)
-                    .build()
-                    .unwrap()
-                    .into(),
-            ])
-            .build()
-            .unwrap();
-
-        let response = self.llm_client.chat().create(request).await.unwrap();
-
-        response.choices.first().unwrap().message.content.clone().unwrap()
+    #[instrument(
+        skip_all,
+        fields(
+            provider = %self.config.llm_provider,
+            model = %self.config.llm_model_name,
+            latency_ms,
+            prompt_tokens,
+            completion_tokens,
+        )
+    )]
+    async fn chat_completion(
+        &self,
+        system_message: String,
+        user_message: String,
+        temperature: Option<f32>,
+    ) -> Result<ChatCompletionResult, SDKError> {
+        let temperature = temperature.map(|t| t.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE));
+
+        // held until the provider answers, so at most `cognition_max_concurrency` calls are in flight
+        let _permit = match &self.cognition_permits {
+            Some(permits) => Some(
+                tokio::time::timeout(COGNITION_PERMIT_TIMEOUT, permits.acquire())
+                    .await
+                    .map_err(|_| SDKError::unavailable("timed out waiting for a cognition slot"))?
+                    .map_err(|err| SDKError::unavailable(err.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let started_at = Instant::now();
+
+        let request = async {
+            match self.config.llm_provider {
+                LlmProvider::OpenAI => openai_chat_completion(self, system_message, user_message, temperature).await,
+                LlmProvider::Anthropic => {
+                    anthropic_chat_completion(self, system_message, user_message, temperature).await
+                }
+            }
+        };
+
+        // dropping the request on expiry cancels the in-flight HTTP call
+        let result = tokio::time::timeout(Duration::from_secs(self.config.cognition_timeout_secs), request)
+            .await
+            .map_err(|_| {
+                SDKError::Timeout(format!("LLM request exceeded {}s", self.config.cognition_timeout_secs))
+            })??;
+
+        let span = Span::current();
+        span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+        span.record("prompt_tokens", result.prompt_tokens);
+        span.record("completion_tokens", result.completion_tokens);
+
+        Ok(result)
+    }
+
+    async fn chat_completion_json<T: DeserializeOwned>(
+        &self,
+        system_message: String,
+        user_message: String,
+        temperature: Option<f32>,
+    ) -> Result<(T, TokenUsage), SDKError> {
+        let cache_key = prompt_cache_key(&system_message, &user_message);
+
+        if let Some(cache) = &self.llm_cache {
+            let cached = cache.lock().unwrap().get(&cache_key).cloned();
+
+            // a cached answer costs no tokens
+            if let Some(value) = cached.and_then(|content| serde_json::from_str::<T>(&content).ok()) {
+                return Ok((value, TokenUsage::default()));
+            }
+        }
+
+        let mut prompt = user_message.clone();
+        let mut attempt = 1;
+        // usage is accumulated over every attempt, failed parses included
+        let mut usage = TokenUsage::default();
+
+        loop {
+            let result = self
+                .chat_completion(system_message.clone(), prompt, temperature)
+                .await?;
+
+            usage.prompt_tokens += result.prompt_tokens;
+            usage.completion_tokens += result.completion_tokens;
+
+            let content = extract_json(&result.content);
+
+            match serde_json::from_str::<T>(content) {
+                Ok(value) => {
+                    if let Some(cache) = &self.llm_cache {
+                        cache.lock().unwrap().insert(cache_key, content.to_string());
+                    }
+
+                    return Ok((value, usage));
+                }
+                Err(err) if attempt < self.config.llm_max_attempts => {
+                    prompt = format!(
+                        "{}
+
+                        Your previous answer could not be parsed ({}). Reply again with only the JSON value.",
+                        user_message, err,
+                    );
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
     }
 
     fn calculate_task_fingerprint(task: Task) -> String {
-        serde_json::to_string(&task).unwrap()
+        task_fingerprint(&task)
     }
 
     fn calculate_task_suggestion_fingerprint(task_suggestion: TaskSuggestionInput) -> String {
-        format!(
-            "Task Title: {}
-        Task Description: {}
-        Task Status: {}
-        Task Priority: {}
-        Task Due Date: {}",
-            task_suggestion.title.unwrap_or("<suggest>".to_string()),
-            task_suggestion.description.unwrap_or("<suggest>".to_string()),
-            task_suggestion
-                .status
-                .map(|s| s.to_string())
-                .unwrap_or("<suggest>".to_string()),
-            task_suggestion
-                .priority
-                .map(|p| p.to_string())
-                .unwrap_or("<suggest>".to_string()),
-            task_suggestion
-                .due_date
-                .map(|d| d.to_rfc3339())
-                .unwrap_or("<suggest>".to_string()),
-        )
+        task_suggestion_fingerprint(&task_suggestion)
     }
 
-    async fn acquire_tasks_fingerprints(&self, number_of_tasks: u32, _project_id: Option<Uuid>) -> Vec<String> {
-        let filter = GetTasksInputBuilder::default()
-            .limit(number_of_tasks as i32)
-            .build()
-            .ok();
-
-        let tasks = self.get_tasks(filter).await.unwrap();
-
-        tasks
-            .iter()
-            .map(|r| Task {
-                id: r.id,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-                title: r.title.clone(),
-                description: r.description.clone(),
-                status: r.status,
-                priority: r.priority,
-                due_date: r.due_date,
-                project_id: r.project_id,
-                lead_id: r.lead_id,
-                owner_id: r.owner_id,
-                count: r.count,
-                parent_id: r.parent_id,
-            })
-            .map(Self::calculate_task_fingerprint)
-            .collect::<Vec<String>>()
+    /// Context tasks scoped to `project_id` and/or `owner_id`; either left `None` doesn't narrow the scope.
+    async fn acquire_tasks_fingerprints(
+        &self,
+        number_of_tasks: u32,
+        project_id: Option<Uuid>,
+        owner_id: Option<Uuid>,
+    ) -> Result<Vec<String>, SDKError> {
+        let tasks = self
+            .get_tasks(Some(context_tasks_input(number_of_tasks, project_id, owner_id)))
+            .await?;
+
+        Ok(tasks.iter().map(task_fingerprint).collect())
+    }
+}
+
+fn context_tasks_input(number_of_tasks: u32, project_id: Option<Uuid>, owner_id: Option<Uuid>) -> GetTasksInput {
+    GetTasksInput {
+        filter: Some(GetTasksWhere {
+            project_id: project_id.map(FilterOp::from),
+            owner_id: owner_id.map(FilterOp::from),
+            ..Default::default()
+        }),
+        limit: Some(number_of_tasks as i32),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{
+        common::commons::SQLArgument,
+        resources::tasks::task::{TaskPriority, TaskStatus},
+    };
+
+    #[test]
+    fn context_tasks_are_scoped_to_owner_and_project() {
+        let owner_id = Uuid::new_v4();
+        let project_id = Uuid::new_v4();
+
+        let input = context_tasks_input(10, Some(project_id), Some(owner_id));
+        let (where_clause, args) = input.filter.unwrap().compile_sql();
+
+        assert_eq!(where_clause, "owner_id = $1 AND project_id = $2");
+        assert_eq!(args, [SQLArgument::Uuid(owner_id), SQLArgument::Uuid(project_id)]);
+        assert_eq!(input.limit, Some(10));
+    }
+
+    #[test]
+    fn context_tasks_without_scope_are_unfiltered() {
+        let (where_clause, args) = context_tasks_input(10, None, None).filter.unwrap().compile_sql();
+
+        assert_eq!(where_clause, "");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn extract_json_strips_fences_and_prose() {
+        assert_eq!(extract_json("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(extract_json("Sure:\n```\n[1, 2]\n```\nAnything else?"), "[1, 2]");
+        assert_eq!(
+            extract_json("The answer is {\"a\": {\"b\": 2}} as requested."),
+            "{\"a\": {\"b\": 2}}"
+        );
+        assert_eq!(extract_json("  {\"a\": 1}  "), "{\"a\": 1}");
+        assert_eq!(extract_json("no JSON here"), "no JSON here");
+    }
+
+    #[test]
+    fn task_fingerprint_layout_is_stable() {
+        let at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+
+        let task = Task {
+            id: Uuid::from_u128(1),
+            created_at: at,
+            updated_at: at,
+            title: "Write docs".to_string(),
+            owner_id: Uuid::from_u128(2),
+            status: TaskStatus::ToDo,
+            priority: TaskPriority::High,
+            count: 7,
+            description: Some(String::new()),
+            due_date: None,
+            project_id: None,
+            lead_id: None,
+            parent_id: None,
+            archived_at: None,
+            recurrence: None,
+            position: 0,
+        };
+
+        assert_eq!(
+            task_fingerprint(&task),
+            concat!(
+                r#"{"id":"00000000-0000-0000-0000-000000000001","created_at":"2024-01-02T03:04:05Z","#,
+                r#""updated_at":"2024-01-02T03:04:05Z","title":"Write docs","#,
+                r#""owner_id":"00000000-0000-0000-0000-000000000002","status":"ToDo","priority":"High","#,
+                r#""count":7,"description":"","due_date":null,"project_id":null,"lead_id":null,"#,
+                r#""parent_id":null,"archived_at":null,"recurrence":null,"position":0}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn task_suggestion_fingerprint_layout_is_stable() {
+        let draft = TaskSuggestionInput {
+            title: Some("Write docs".to_string()),
+            description: Some(String::new()),
+            priority: Some(TaskPriority::Low),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            task_suggestion_fingerprint(&draft),
+            "Task Title: Write docs\nTask Description: \nTask Status: <suggest>\nTask Priority: Low\nTask Due Date: <suggest>"
+        );
+
+        let dated = TaskSuggestionInput {
+            status: Some(TaskStatus::InProgress),
+            due_date: Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            task_suggestion_fingerprint(&dated),
+            "Task Title: <suggest>\nTask Description: <suggest>\nTask Status: InProgress\nTask Priority: <suggest>\nTask Due Date: 2024-01-02T03:04:05+00:00"
+        );
     }
 }
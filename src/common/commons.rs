@@ -1,19 +1,99 @@
-use async_graphql::{Enum, InputObject};
+use std::{borrow::Cow, ops::Range, str::FromStr};
+
+use async_graphql::{Enum, InputObject, OneofObject};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
-use poem_openapi::{Enum as OpenApiEnum, Object};
+use poem_openapi::{
+    registry::{MetaSchema, MetaSchemaRef, Registry},
+    types::{ParseError, ParseFromJSON, ParseResult, ToJSON},
+    Enum as OpenApiEnum, Object,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgArguments, query::Query, Postgres};
 use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
-#[derive(
-    Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumString, Deserialize, Serialize, Eq, PartialEq,
-)]
+use crate::{
+    errors::sdk::SDKError,
+    resources::tasks::task::{TaskPriority, TaskStatus},
+};
+
+#[derive(Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(try_from = "String")]
 pub enum SortOrder {
     #[default]
     Asc,
     Desc,
 }
 
+/// Case-insensitive, so query strings like `?order=desc` parse as well as `"Desc"`.
+impl FromStr for SortOrder {
+    type Err = SDKError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(SDKError::InvalidSortOrder(value.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for SortOrder {
+    type Error = SDKError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        SortOrder::from_str(&value)
+    }
+}
+
+impl SortOrder {
+    pub fn to_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Renders `ORDER BY a ASC, b DESC `, rejecting any field outside `allowed`. Empty when `sort` is.
+pub fn compile_order_by(sort: &[(String, SortOrder)], allowed: &[&str]) -> Result<String, SDKError> {
+    if sort.is_empty() {
+        return Ok(String::new());
+    }
+
+    let columns = sort
+        .iter()
+        .map(|(field, order)| {
+            if !allowed.contains(&field.as_str()) {
+                return Err(SDKError::InvalidSortField(field.clone()));
+            }
+
+            Ok(format!("{} {}", field, order.to_sql()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(format!("ORDER BY {} ", columns.join(", ")))
+}
+
+/// Caps `limit` at `max_page_size` so one call can't load an unbounded result set; a `None` limit
+/// reads a full page of `max_page_size`. A negative limit or offset is rejected.
+pub fn clamp_page(limit: Option<i32>, offset: Option<i32>, max_page_size: u32) -> Result<i32, SDKError> {
+    if let Some(limit) = limit.filter(|limit| *limit < 0) {
+        return Err(SDKError::InvalidPagination(format!("negative limit: {}", limit)));
+    }
+
+    if let Some(offset) = offset.filter(|offset| *offset < 0) {
+        return Err(SDKError::InvalidPagination(format!("negative offset: {}", offset)));
+    }
+
+    let max_page_size = i32::try_from(max_page_size).unwrap_or(i32::MAX);
+
+    Ok(limit.map_or(max_page_size, |limit| limit.min(max_page_size)))
+}
+
 #[derive(Debug, Clone, Display, EnumString)]
 pub enum SQLComparison<T>
 where
@@ -39,3 +119,582 @@ pub struct UpdateListInput {
     pub add: Vec<Uuid>,
     pub remove: Vec<Uuid>,
 }
+
+/// A value bound to a `$n` placeholder of a compiled WHERE clause.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SQLArgument {
+    Text(String),
+    TextArray(Vec<String>),
+    Uuid(Uuid),
+    UuidArray(Vec<Uuid>),
+    Timestamp(DateTime<Utc>),
+    TimestampArray(Vec<DateTime<Utc>>),
+}
+
+impl SQLArgument {
+    pub fn bind<'q>(self, query: Query<'q, Postgres, PgArguments>) -> Query<'q, Postgres, PgArguments> {
+        match self {
+            SQLArgument::Text(value) => query.bind(value),
+            SQLArgument::TextArray(value) => query.bind(value),
+            SQLArgument::Uuid(value) => query.bind(value),
+            SQLArgument::UuidArray(value) => query.bind(value),
+            SQLArgument::Timestamp(value) => query.bind(value),
+            SQLArgument::TimestampArray(value) => query.bind(value),
+        }
+    }
+
+    /// The value as a SQL literal, for logs and debugging only; queries always bind it instead.
+    pub fn to_sql_literal(&self) -> String {
+        let quote = |value: String| format!("'{}'", value.replace('\'', "''"));
+        let array = |values: Vec<String>| format!("ARRAY[{}]", values.join(", "));
+
+        match self {
+            SQLArgument::Text(value) => quote(value.clone()),
+            SQLArgument::TextArray(values) => array(values.iter().cloned().map(quote).collect()),
+            SQLArgument::Uuid(value) => quote(value.to_string()),
+            SQLArgument::UuidArray(values) => array(values.iter().map(|value| quote(value.to_string())).collect()),
+            SQLArgument::Timestamp(value) => quote(value.to_rfc3339()),
+            SQLArgument::TimestampArray(values) => {
+                array(values.iter().map(|value| quote(value.to_rfc3339())).collect())
+            }
+        }
+    }
+}
+
+/// `query` followed by one `-- $n = ...` line per bound value. Those values come from callers and may be
+/// sensitive, so each line says so.
+pub fn sql_debug(query: &str, args: &[SQLArgument]) -> String {
+    let mut debug = query.trim_end().to_string();
+
+    for (index, arg) in args.iter().enumerate() {
+        debug.push_str(&format!(
+            "\n-- ${} = {} (bound value, may be sensitive)",
+            index + 1,
+            arg.to_sql_literal()
+        ));
+    }
+
+    debug
+}
+
+/// A comparison of one column of a `Get*Where` filter. A bare value converts into `Eq`, so
+/// `.name("Bug".to_string())` on a where-builder still means equality.
+#[derive(Debug, Clone, PartialEq, OneofObject)]
+#[graphql(concrete(name = "StringFilter", params(String)))]
+#[graphql(concrete(name = "UuidFilter", params(Uuid)))]
+#[graphql(concrete(name = "DateTimeFilter", params("DateTime<Utc>")))]
+#[graphql(concrete(name = "TaskStatusFilter", params(TaskStatus)))]
+#[graphql(concrete(name = "TaskPriorityFilter", params(TaskPriority)))]
+pub enum FilterOp<T: async_graphql::InputType> {
+    Eq(T),
+    Neq(T),
+    Gt(T),
+    Gte(T),
+    Lt(T),
+    Lte(T),
+    In(Vec<T>),
+    /// A raw LIKE pattern; `%` and `_` are wildcards. Matches against the column's text form.
+    Like(String),
+    /// `true` for `IS NULL`, `false` for `IS NOT NULL`.
+    IsNull(bool),
+}
+
+impl<T: async_graphql::InputType> From<T> for FilterOp<T> {
+    fn from(value: T) -> Self {
+        FilterOp::Eq(value)
+    }
+}
+
+/// A value a [`FilterOp`] can compare a column against.
+pub trait FilterValue: async_graphql::InputType + Clone {
+    fn to_argument(&self) -> SQLArgument;
+    fn to_array_argument(values: &[Self]) -> SQLArgument;
+}
+
+impl FilterValue for String {
+    fn to_argument(&self) -> SQLArgument {
+        SQLArgument::Text(self.clone())
+    }
+
+    fn to_array_argument(values: &[Self]) -> SQLArgument {
+        SQLArgument::TextArray(values.to_vec())
+    }
+}
+
+impl FilterValue for Uuid {
+    fn to_argument(&self) -> SQLArgument {
+        SQLArgument::Uuid(*self)
+    }
+
+    fn to_array_argument(values: &[Self]) -> SQLArgument {
+        SQLArgument::UuidArray(values.to_vec())
+    }
+}
+
+impl FilterValue for DateTime<Utc> {
+    fn to_argument(&self) -> SQLArgument {
+        SQLArgument::Timestamp(*self)
+    }
+
+    fn to_array_argument(values: &[Self]) -> SQLArgument {
+        SQLArgument::TimestampArray(values.to_vec())
+    }
+}
+
+impl FilterValue for TaskStatus {
+    fn to_argument(&self) -> SQLArgument {
+        SQLArgument::Text(self.to_string())
+    }
+
+    fn to_array_argument(values: &[Self]) -> SQLArgument {
+        SQLArgument::TextArray(values.iter().map(|value| value.to_string()).collect())
+    }
+}
+
+impl FilterValue for TaskPriority {
+    fn to_argument(&self) -> SQLArgument {
+        SQLArgument::Text(self.to_string())
+    }
+
+    fn to_array_argument(values: &[Self]) -> SQLArgument {
+        SQLArgument::TextArray(values.iter().map(|value| value.to_string()).collect())
+    }
+}
+
+impl<T: FilterValue> FilterOp<T> {
+    /// Renders the condition on `column`, pushing its value onto `args` so the placeholder is `$args.len()`.
+    pub fn compile_sql(&self, column: &str, args: &mut Vec<SQLArgument>) -> String {
+        let (operator, argument) = match self {
+            FilterOp::Eq(value) => ("=", value.to_argument()),
+            FilterOp::Neq(value) => ("<>", value.to_argument()),
+            FilterOp::Gt(value) => (">", value.to_argument()),
+            FilterOp::Gte(value) => (">=", value.to_argument()),
+            FilterOp::Lt(value) => ("<", value.to_argument()),
+            FilterOp::Lte(value) => ("<=", value.to_argument()),
+            FilterOp::In(values) => {
+                args.push(T::to_array_argument(values));
+                return format!("{} = ANY(${})", column, args.len());
+            }
+            FilterOp::Like(pattern) => {
+                args.push(SQLArgument::Text(pattern.clone()));
+                return format!("{}::text LIKE ${}", column, args.len());
+            }
+            FilterOp::IsNull(true) => return format!("{} IS NULL", column),
+            FilterOp::IsNull(false) => return format!("{} IS NOT NULL", column),
+        };
+
+        args.push(argument);
+        format!("{} {} ${}", column, operator, args.len())
+    }
+}
+
+const FILTER_OPS: [&str; 9] = ["eq", "neq", "gt", "gte", "lt", "lte", "in", "like", "is_null"];
+
+/// OpenAPI accepts either a bare value, meaning `eq`, or an object with exactly one operator key.
+impl<T: async_graphql::InputType + poem_openapi::types::Type> poem_openapi::types::Type for FilterOp<T> {
+    const IS_REQUIRED: bool = true;
+
+    type RawValueType = Self;
+
+    type RawElementValueType = Self;
+
+    fn name() -> Cow<'static, str> {
+        format!("FilterOp<{}>", T::name()).into()
+    }
+
+    fn schema_ref() -> MetaSchemaRef {
+        let operators = MetaSchema {
+            properties: FILTER_OPS
+                .iter()
+                .map(|operator| match *operator {
+                    "in" => (*operator, Vec::<T>::schema_ref()),
+                    "like" => (*operator, String::schema_ref()),
+                    "is_null" => (*operator, bool::schema_ref()),
+                    _ => (*operator, T::schema_ref()),
+                })
+                .collect(),
+            ..MetaSchema::new("object")
+        };
+
+        MetaSchemaRef::Inline(Box::new(MetaSchema {
+            any_of: vec![T::schema_ref(), MetaSchemaRef::Inline(Box::new(operators))],
+            ..MetaSchema::ANY
+        }))
+    }
+
+    fn register(registry: &mut Registry) {
+        T::register(registry);
+    }
+
+    fn as_raw_value(&self) -> Option<&Self::RawValueType> {
+        Some(self)
+    }
+
+    fn raw_element_iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::RawElementValueType> + 'a> {
+        Box::new(self.as_raw_value().into_iter())
+    }
+}
+
+impl<T: async_graphql::InputType + ParseFromJSON> ParseFromJSON for FilterOp<T> {
+    fn parse_from_json(value: Option<Value>) -> ParseResult<Self> {
+        let operator = match &value {
+            Some(Value::Object(object)) if object.len() == 1 => object.keys().next().cloned(),
+            _ => None,
+        };
+
+        let Some(operator) = operator.filter(|operator| FILTER_OPS.contains(&operator.as_str())) else {
+            return T::parse_from_json(value)
+                .map(FilterOp::Eq)
+                .map_err(ParseError::propagate);
+        };
+
+        let operand = match value {
+            Some(Value::Object(mut object)) => object.remove(&operator),
+            _ => None,
+        };
+
+        Ok(match operator.as_str() {
+            "eq" => FilterOp::Eq(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "neq" => FilterOp::Neq(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "gt" => FilterOp::Gt(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "gte" => FilterOp::Gte(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "lt" => FilterOp::Lt(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "lte" => FilterOp::Lte(T::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "in" => FilterOp::In(Vec::<T>::parse_from_json(operand).map_err(ParseError::propagate)?),
+            "like" => FilterOp::Like(String::parse_from_json(operand).map_err(ParseError::propagate)?),
+            _ => FilterOp::IsNull(bool::parse_from_json(operand).map_err(ParseError::propagate)?),
+        })
+    }
+}
+
+impl<T: async_graphql::InputType + ToJSON> ToJSON for FilterOp<T> {
+    fn to_json(&self) -> Option<Value> {
+        let (operator, operand) = match self {
+            FilterOp::Eq(value) => ("eq", value.to_json()),
+            FilterOp::Neq(value) => ("neq", value.to_json()),
+            FilterOp::Gt(value) => ("gt", value.to_json()),
+            FilterOp::Gte(value) => ("gte", value.to_json()),
+            FilterOp::Lt(value) => ("lt", value.to_json()),
+            FilterOp::Lte(value) => ("lte", value.to_json()),
+            FilterOp::In(values) => ("in", values.to_json()),
+            FilterOp::Like(pattern) => ("like", pattern.to_json()),
+            FilterOp::IsNull(is_null) => ("is_null", is_null.to_json()),
+        };
+
+        Some(Value::Object(
+            [(operator.to_string(), operand.unwrap_or_default())]
+                .into_iter()
+                .collect(),
+        ))
+    }
+}
+
+/// Wraps `value` in `%` wildcards for a substring match, escaping any LIKE metacharacters it contains.
+pub fn like_pattern(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+
+    format!("%{}%", escaped)
+}
+
+/// A hand-written SQL condition for what the where-builders can't express. Values are never spliced into
+/// the SQL: the fragment refers to `params` as `$1..$n`, renumbered to follow the rest of the query.
+#[derive(Debug, Clone)]
+pub struct RawFilter {
+    fragment: String,
+    params: Vec<SQLArgument>,
+}
+
+impl RawFilter {
+    /// Fails when the fragment uses a placeholder with no matching entry in `params`.
+    pub fn new(fragment: impl Into<String>, params: Vec<SQLArgument>) -> Result<Self, SDKError> {
+        let fragment = fragment.into();
+
+        if let Some((_, n)) = placeholders(&fragment)
+            .into_iter()
+            .find(|(_, n)| *n == 0 || *n > params.len())
+        {
+            return Err(SDKError::InvalidFilter(format!(
+                "${} has no matching parameter ({} given)",
+                n,
+                params.len()
+            )));
+        }
+
+        Ok(RawFilter { fragment, params })
+    }
+
+    /// Renders the fragment in parentheses, pushing its values onto `args` and shifting its placeholders past them.
+    pub fn compile_sql(&self, args: &mut Vec<SQLArgument>) -> String {
+        let offset = args.len();
+        args.extend(self.params.iter().cloned());
+
+        let mut sql = String::with_capacity(self.fragment.len() + 2);
+        let mut last = 0;
+
+        sql.push('(');
+
+        for (range, n) in placeholders(&self.fragment) {
+            sql.push_str(&self.fragment[last..range.start]);
+            sql.push_str(&format!("${}", offset + n));
+            last = range.end;
+        }
+
+        sql.push_str(&self.fragment[last..]);
+        sql.push(')');
+
+        sql
+    }
+}
+
+/// Byte ranges and numbers of the `$n` placeholders in `fragment`, skipping quoted literals and identifiers.
+fn placeholders(fragment: &str) -> Vec<(Range<usize>, usize)> {
+    let bytes = fragment.as_bytes();
+    let mut found = Vec::new();
+    let mut quote = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (quote, bytes[i]) {
+            (None, b'\'' | b'"') => quote = Some(bytes[i]),
+            (Some(open), byte) if byte == open => quote = None,
+            (None, b'$') => {
+                let end = bytes[i + 1..]
+                    .iter()
+                    .position(|byte| !byte.is_ascii_digit())
+                    .map_or(bytes.len(), |len| i + 1 + len);
+
+                if end > i + 1 {
+                    // an absurdly long number can't match a parameter, so it's reported as $0
+                    found.push((i..end, fragment[i + 1..end].parse().unwrap_or(0)));
+                    i = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    found
+}
+
+/// Keyset position of the last row of a page, ordered by `(created_at, id)`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.created_at.to_rfc3339(), self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Cursor, SDKError> {
+        let invalid = || SDKError::InvalidCursor(cursor.to_string());
+
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (created_at, id) = raw.split_once('|').ok_or_else(invalid)?;
+
+        Ok(Cursor {
+            created_at: DateTime::parse_from_rfc3339(created_at)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A page of results and the cursor to fetch the next one, `None` once the last page is reached.
+#[derive(Debug, Clone)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// An offset page of results. `total` is only filled in when the caller asks for it, as it costs a count query.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: Option<i64>,
+    pub limit: i32,
+    pub offset: i32,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from a fetch of up to `limit + 1` rows, the extra row only signalling that more exist.
+    pub fn from_overfetch(mut items: Vec<T>, total: Option<i64>, limit: i32, offset: i32) -> Page<T> {
+        let has_more = items.len() > limit as usize;
+        items.truncate(limit as usize);
+
+        Page {
+            items,
+            total,
+            limit,
+            offset,
+            has_more,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_orders_parse_case_insensitively() {
+        assert_eq!(SortOrder::from_str("ASC").unwrap(), SortOrder::Asc);
+        assert_eq!(SortOrder::from_str("desc").unwrap(), SortOrder::Desc);
+        assert!(matches!(
+            SortOrder::from_str("sideways"),
+            Err(SDKError::InvalidSortOrder(value)) if value == "sideways"
+        ));
+
+        assert_eq!(serde_json::from_str::<SortOrder>("\"Desc\"").unwrap(), SortOrder::Desc);
+        assert_eq!(serde_json::to_string(&SortOrder::Asc).unwrap(), "\"Asc\"");
+        assert!(serde_json::from_str::<SortOrder>("\"sideways\"").is_err());
+    }
+
+    #[test]
+    fn overfetched_rows_only_flag_more_pages() {
+        let full = Page::from_overfetch(vec![1, 2, 3], None, 2, 0);
+
+        assert_eq!(full.items, [1, 2]);
+        assert!(full.has_more);
+
+        let last = Page::from_overfetch(vec![1, 2], Some(4), 2, 2);
+
+        assert_eq!(last.items, [1, 2]);
+        assert!(!last.has_more);
+        assert_eq!((last.total, last.limit, last.offset), (Some(4), 2, 2));
+    }
+
+    #[test]
+    fn clamp_page_caps_limits_above_the_max() {
+        assert_eq!(clamp_page(Some(1_000_000), None, 1000).unwrap(), 1000);
+        assert_eq!(clamp_page(Some(50), Some(10), 1000).unwrap(), 50);
+    }
+
+    #[test]
+    fn clamp_page_defaults_a_missing_limit_to_the_max() {
+        assert_eq!(clamp_page(None, None, 1000).unwrap(), 1000);
+    }
+
+    #[test]
+    fn clamp_page_rejects_negative_values() {
+        assert!(matches!(
+            clamp_page(Some(-1), None, 1000),
+            Err(SDKError::InvalidPagination(_))
+        ));
+        assert!(matches!(
+            clamp_page(Some(10), Some(-1), 1000),
+            Err(SDKError::InvalidPagination(_))
+        ));
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("ug"), "%ug%");
+        assert_eq!(like_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    #[test]
+    fn filter_ops_compile_to_numbered_placeholders() {
+        let compile = |op: FilterOp<String>| {
+            // an earlier condition already took $1
+            let mut args = vec![SQLArgument::Text("earlier".to_string())];
+            let sql = op.compile_sql("name", &mut args);
+
+            (sql, args.split_off(1))
+        };
+        let text = |value: &str| SQLArgument::Text(value.to_string());
+
+        assert_eq!(
+            compile("a".to_string().into()),
+            ("name = $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::Neq("a".to_string())),
+            ("name <> $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::Gt("a".to_string())),
+            ("name > $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::Gte("a".to_string())),
+            ("name >= $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::Lt("a".to_string())),
+            ("name < $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::Lte("a".to_string())),
+            ("name <= $2".to_string(), vec![text("a")])
+        );
+        assert_eq!(
+            compile(FilterOp::In(vec!["a".to_string(), "b".to_string()])),
+            (
+                "name = ANY($2)".to_string(),
+                vec![SQLArgument::TextArray(vec!["a".to_string(), "b".to_string()])]
+            )
+        );
+        assert_eq!(
+            compile(FilterOp::Like("a%".to_string())),
+            ("name::text LIKE $2".to_string(), vec![text("a%")])
+        );
+        assert_eq!(compile(FilterOp::IsNull(true)), ("name IS NULL".to_string(), vec![]));
+        assert_eq!(
+            compile(FilterOp::IsNull(false)),
+            ("name IS NOT NULL".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn raw_filters_renumber_their_placeholders_past_the_existing_args() {
+        let owner_id = Uuid::new_v4();
+        let filter = RawFilter::new(
+            "owner_id = $2 AND (title = $1 OR description = $1) AND title <> '$1' AND \"$2\" IS NULL",
+            vec![SQLArgument::Text("a".to_string()), SQLArgument::Uuid(owner_id)],
+        )
+        .unwrap();
+
+        let mut args = vec![SQLArgument::Text("before".to_string())];
+
+        assert_eq!(
+            filter.compile_sql(&mut args),
+            "(owner_id = $3 AND (title = $2 OR description = $2) AND title <> '$1' AND \"$2\" IS NULL)"
+        );
+        assert_eq!(
+            args,
+            vec![
+                SQLArgument::Text("before".to_string()),
+                SQLArgument::Text("a".to_string()),
+                SQLArgument::Uuid(owner_id),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_filters_reject_placeholders_without_a_parameter() {
+        let param = || vec![SQLArgument::Text("a".to_string())];
+
+        assert!(matches!(
+            RawFilter::new("title = $2", param()),
+            Err(SDKError::InvalidFilter(_))
+        ));
+        assert!(matches!(
+            RawFilter::new("title = $0", param()),
+            Err(SDKError::InvalidFilter(_))
+        ));
+        assert!(matches!(
+            RawFilter::new("title = $99999999999999999999999", param()),
+            Err(SDKError::InvalidFilter(_))
+        ));
+        assert!(RawFilter::new("title = '$2'", param()).is_ok());
+    }
+}
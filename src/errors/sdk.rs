@@ -1,4 +1,14 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::postgres::PgDatabaseError;
 use thiserror::Error;
+use uuid::Uuid;
+
+use crate::resources::tasks::task::TaskStatus;
+
+const ENGINE_CLOSED: &str = "the engine has been closed";
 
 #[derive(Error, Debug)]
 pub enum SDKError {
@@ -18,8 +28,16 @@ pub enum SDKError {
     // PoemError(#[from] poem::error::NotFoundError),
     #[error("Resource not found")]
     ResourceNotFound,
-    #[error("SQLX Error")]
-    SQLXError(#[from] sqlx::Error),
+    #[error("{resource} not found: {id}")]
+    NotFound { resource: &'static str, id: Uuid },
+    #[error("No member with email {0}")]
+    MemberNotFound(String),
+    #[error("{resource} was modified by someone else: {id}")]
+    Conflict { resource: &'static str, id: Uuid },
+    #[error("More than one {resource} matches {value}")]
+    AmbiguousMatch { resource: &'static str, value: String },
+    #[error("Database error: {0}")]
+    Database(#[source] sqlx::Error),
     // #[error("FromStr error")]
     // FromStrError(#[from] core::str::FromStr::Err),
     #[error("Database Migration Error")]
@@ -27,4 +45,239 @@ pub enum SDKError {
 
     #[error("Serde JSON Error")]
     SerdeJSONError(#[from] serde_json::Error),
+
+    #[error("Invalid sort field: {0}")]
+    InvalidSortField(String),
+    #[error("Invalid sort order: {0} (expected \"asc\" or \"desc\")")]
+    InvalidSortOrder(String),
+    #[error("A label named {name} already exists")]
+    DuplicateLabel { name: String },
+    #[error("Invalid task status: {0}")]
+    InvalidTaskStatus(String),
+    #[error("Invalid task priority: {0}")]
+    InvalidTaskPriority(String),
+    #[error("Invalid color: {0}")]
+    InvalidColor(String),
+    #[error("Invalid status transition from {from} to {to}")]
+    InvalidStatusTransition { from: TaskStatus, to: TaskStatus },
+    #[error("Due date is in the past: {0}")]
+    DueDateInPast(DateTime<Utc>),
+    #[error("Task dependency would create a cycle")]
+    DependencyCycle,
+    #[error("Project not found")]
+    ProjectNotFound,
+    #[error("Invalid recurrence rule: {0}")]
+    InvalidRecurrence(String),
+    #[error("Validation failed: {}", FieldError::join(.0))]
+    Validation(Vec<FieldError>),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(String),
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("Unsupported export version: {0}")]
+    UnsupportedExportVersion(u32),
+    #[error("Suggestion is missing the {field} field")]
+    IncompleteSuggestion { field: &'static str },
+    #[error("Invalid subtask count: {0}")]
+    InvalidSubtaskCount(u8),
+    #[error("Invalid subdivision depth: {0}")]
+    InvalidSubdivisionDepth(u8),
+    #[error("Subdivision would generate {0} suggestions, more than the limit")]
+    SubdivisionTooLarge(usize),
+    /// `closed` is set once the engine was shut down with [`SDKEngine::close`](crate::backend::engine::SDKEngine::close);
+    /// unlike other unavailability it is permanent.
+    #[error("Service unavailable: {reason}")]
+    Unavailable { reason: String, closed: bool },
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("LLM request error")]
+    LLMRequestError(#[from] reqwest::Error),
+    #[error("LLM response error: {0}")]
+    LLMResponseError(String),
+}
+
+/// One problem found while validating a batch; `field` is a path such as `subtasks[1].due_date`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub index: usize,
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(index: usize, field: impl Into<String>, err: impl fmt::Display) -> Self {
+        FieldError {
+            index,
+            field: field.into(),
+            message: err.to_string(),
+        }
+    }
+
+    fn join(errors: &[FieldError]) -> String {
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.index, self.field, self.message)
+    }
+}
+
+impl SDKError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            SDKError::NotFound { .. }
+                | SDKError::ResourceNotFound
+                | SDKError::ProjectNotFound
+                | SDKError::MemberNotFound(_)
+        )
+    }
+
+    /// SQLSTATE code reported by Postgres, e.g. `40001` for a serialization failure.
+    pub fn sql_state(&self) -> Option<String> {
+        match self {
+            SDKError::Database(sqlx::Error::Database(err)) => err.code().map(|code| code.into_owned()),
+            _ => None,
+        }
+    }
+
+    /// A transient `Unavailable`, such as a saturated pool or an unreachable database.
+    pub fn unavailable(reason: impl Into<String>) -> SDKError {
+        SDKError::Unavailable {
+            reason: reason.into(),
+            closed: false,
+        }
+    }
+
+    /// Whether the engine was shut down with [`SDKEngine::close`](crate::backend::engine::SDKEngine::close).
+    pub fn is_closed(&self) -> bool {
+        matches!(self, SDKError::Unavailable { closed: true, .. })
+    }
+
+    /// Whether the same call may succeed if retried: lost connections, pool timeouts,
+    /// serialization failures and deadlocks. Logic errors and a closed engine are never retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SDKError::Database(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut) => true,
+            SDKError::Database(sqlx::Error::Database(_)) => self.sql_state().is_some_and(|code| {
+                // connection exceptions, too many connections, serialization failure, deadlock
+                code.starts_with("08") || matches!(code.as_str(), "53300" | "40001" | "40P01")
+            }),
+            SDKError::Unavailable { closed, .. } => !closed,
+            SDKError::Timeout(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Error mapper for single-row queries: a missing row becomes `NotFound`, anything else stays a database error.
+    pub fn or_not_found(resource: &'static str, id: Uuid) -> impl FnOnce(sqlx::Error) -> SDKError {
+        move |err| match err {
+            sqlx::Error::RowNotFound => SDKError::NotFound { resource, id },
+            err => err.into(),
+        }
+    }
+
+    /// Error mapper for label writes: a violation of the unique label name becomes `DuplicateLabel`.
+    pub fn or_duplicate_label(name: &str) -> impl FnOnce(sqlx::Error) -> SDKError + '_ {
+        move |err| match err {
+            sqlx::Error::Database(db_err) if db_err.constraint() == Some("labels_name_key") => {
+                SDKError::DuplicateLabel { name: name.to_string() }
+            }
+            err => err.into(),
+        }
+    }
+
+    /// Batch variant of [`SDKError::or_duplicate_label`]. Postgres names the conflicting value in the
+    /// violation's detail, e.g. `Key (name)=(bug) already exists.`; without one, every name of the batch is reported.
+    pub fn or_duplicate_labels(names: &[String]) -> impl FnOnce(sqlx::Error) -> SDKError + '_ {
+        move |err| match err {
+            sqlx::Error::Database(db_err) if db_err.constraint() == Some("labels_name_key") => {
+                let name = db_err
+                    .try_downcast_ref::<PgDatabaseError>()
+                    .and_then(|pg_err| pg_err.detail())
+                    .and_then(|detail| detail.strip_prefix("Key (name)=("))
+                    .and_then(|detail| detail.strip_suffix(") already exists."))
+                    .map_or_else(|| names.join(", "), ToString::to_string);
+
+                SDKError::DuplicateLabel { name }
+            }
+            err => err.into(),
+        }
+    }
+}
+
+/// A closed pool means the engine was shut down with [`SDKEngine::close`](crate::backend::engine::SDKEngine::close),
+/// which callers see as `Unavailable` rather than a database failure.
+impl From<sqlx::Error> for SDKError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::PoolClosed => SDKError::Unavailable {
+                reason: ENGINE_CLOSED.to_string(),
+                closed: true,
+            },
+            err => SDKError::Database(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_sqlx_errors_are_retryable() {
+        assert!(SDKError::from(sqlx::Error::PoolTimedOut).is_retryable());
+        assert!(SDKError::from(sqlx::Error::Io(std::io::ErrorKind::ConnectionReset.into())).is_retryable());
+        assert!(SDKError::unavailable("too many connections").is_retryable());
+        assert!(SDKError::Timeout("acquire".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn logic_errors_are_not_retryable() {
+        assert!(!SDKError::from(sqlx::Error::RowNotFound).is_retryable());
+        assert!(!SDKError::from(sqlx::Error::ColumnNotFound("title".to_string())).is_retryable());
+        assert!(!SDKError::NotFound {
+            resource: "task",
+            id: Uuid::nil()
+        }
+        .is_retryable());
+    }
+
+    #[test]
+    fn a_closed_pool_is_unavailable_but_not_retryable() {
+        let err = SDKError::from(sqlx::Error::PoolClosed);
+
+        assert!(matches!(err, SDKError::Unavailable { closed: true, .. }));
+        assert!(err.is_closed());
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn only_a_closed_pool_counts_as_closed() {
+        // the message alone says nothing about whether the engine is gone
+        let err = SDKError::unavailable(ENGINE_CLOSED);
+
+        assert!(!err.is_closed());
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn row_not_found_maps_to_not_found() {
+        let id = Uuid::new_v4();
+
+        assert!(matches!(
+            SDKError::or_not_found("task", id)(sqlx::Error::RowNotFound),
+            SDKError::NotFound { resource: "task", id: found } if found == id
+        ));
+        assert!(matches!(
+            SDKError::or_not_found("task", id)(sqlx::Error::PoolClosed),
+            SDKError::Unavailable { closed: true, .. }
+        ));
+    }
 }
@@ -4,3 +4,5 @@ pub mod common;
 pub mod errors;
 pub mod organization;
 pub mod resources;
+#[cfg(feature = "test-util")]
+pub mod testing;
@@ -1,6 +1,6 @@
 use std::{error::Error, str::FromStr, sync::Arc};
 
-use chrono::Local;
+use chrono::{Duration, Utc};
 use dotenv::dotenv;
 
 use plexo_sdk::{
@@ -28,7 +28,7 @@ use uuid::Uuid;
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
 
-    let engine = SDKEngine::new(SDKConfig::from_env()).await?;
+    let engine = SDKEngine::new(SDKConfig::from_env()?).await?;
     let engine = Arc::new(engine);
 
     let loaders = SDKLoaders::new(engine.clone());
@@ -87,7 +87,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     CreateTaskInputBuilder::default()
                         .title("Real Task".to_string())
                         .status(TaskStatus::Done)
-                        .due_date(Local::now().into())
+                        .due_date(Utc::now() + Duration::days(7))
                         .owner_id(task_owner.id)
                         .build()?,
                 ])
@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 trait AssetImplementation {
     fn alt_text(&self) -> String;
     fn icon_base64(&self) -> String;
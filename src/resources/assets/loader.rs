@@ -28,7 +28,7 @@ impl Loader<Uuid> for AssetLoader {
             "#,
             &keys
         )
-        .fetch_all(&*self.0.db_pool)
+        .fetch_all(&*self.0.read_pool)
         .await
         .unwrap();
 
@@ -8,7 +8,7 @@ use sqlx::Row;
 use uuid::Uuid;
 
 use crate::backend::engine::SDKEngine;
-use crate::common::commons::SortOrder;
+use crate::common::commons::{clamp_page, SortOrder};
 use crate::errors::sdk::SDKError;
 use crate::resources::assets::asset::{Asset, AssetKind};
 
@@ -153,7 +153,7 @@ impl AssetCrudOperations for SDKEngine {
             input.kind.map(|k| k.to_string()),
             input.project_id,
         )
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(Asset {
@@ -174,8 +174,9 @@ impl AssetCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("asset", id))?;
 
         Ok(Asset {
             id: asset_info.id,
@@ -203,15 +204,16 @@ impl AssetCrudOperations for SDKEngine {
             query.push_str(&format!("{} ", sort_order));
         }
 
-        if let Some(limit) = input.limit {
-            query.push_str(&format!("LIMIT {} ", limit));
-        }
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        query.push_str(&format!("LIMIT {} ", limit));
 
         if let Some(offset) = input.offset {
             query.push_str(&format!("OFFSET {} ", offset));
         }
 
-        let assets_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        let assets_info = sqlx::query(query.as_str())
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
 
         let assets = assets_info
             .into_iter()
@@ -248,8 +250,9 @@ impl AssetCrudOperations for SDKEngine {
             input.project_id,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("asset", id))?;
 
         Ok(Asset {
             id: asset_final_info.id,
@@ -270,8 +273,9 @@ impl AssetCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("asset", id))?;
 
         Ok(Asset {
             id: asset_info.id,
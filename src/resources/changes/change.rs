@@ -8,7 +8,7 @@ use uuid::Uuid;
 use poem_openapi::Enum as OpenApiEnum;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, SimpleObject, Object, Clone, Serialize)]
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
 #[graphql(name = "SDKChange")]
 pub struct Change {
     pub id: Uuid,
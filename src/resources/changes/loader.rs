@@ -28,7 +28,7 @@ impl Loader<Uuid> for ChangeLoader {
             "#,
             &keys
         )
-        .fetch_all(&*self.0.db_pool)
+        .fetch_all(&*self.0.read_pool)
         .await
         .unwrap();
 
@@ -7,7 +7,11 @@ use poem_openapi::Object;
 use sqlx::Row;
 use uuid::Uuid;
 
-use crate::{backend::engine::SDKEngine, common::commons::SortOrder, errors::sdk::SDKError};
+use crate::{
+    backend::engine::SDKEngine,
+    common::commons::{clamp_page, SortOrder},
+    errors::sdk::SDKError,
+};
 
 use super::change::{Change, ChangeOperation, ChangeResourceType};
 
@@ -158,7 +162,7 @@ impl ChangeCrudOperations for SDKEngine {
             input.resource_type.to_string(),
             input.diff_json,
         )
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(Change {
@@ -181,8 +185,9 @@ impl ChangeCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("change", id))?;
 
         Ok(Change {
             id: change_info.id,
@@ -211,15 +216,16 @@ impl ChangeCrudOperations for SDKEngine {
             query.push_str(format!("{} ", sort_order).as_str());
         }
 
-        if let Some(limit) = input.limit {
-            query.push_str(format!("LIMIT {} ", limit).as_str());
-        }
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        query.push_str(format!("LIMIT {} ", limit).as_str());
 
         if let Some(offset) = input.offset {
             query.push_str(format!("OFFSET {} ", offset).as_str());
         }
 
-        let changes_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        let changes_info = sqlx::query(query.as_str())
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
 
         let changes = changes_info
             .into_iter()
@@ -259,8 +265,9 @@ impl ChangeCrudOperations for SDKEngine {
             input.diff_json,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("change", id))?;
 
         Ok(Change {
             id: change_info.id,
@@ -282,8 +289,9 @@ impl ChangeCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("change", id))?;
 
         Ok(Change {
             id: change_info.id,
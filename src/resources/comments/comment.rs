@@ -0,0 +1,20 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+
+use poem_openapi::Object;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
+#[graphql(name = "SDKTaskComment")]
+pub struct Comment {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    pub task_id: Uuid,
+    pub author_id: Uuid,
+
+    pub content: String,
+}
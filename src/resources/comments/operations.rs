@@ -0,0 +1,136 @@
+use async_graphql::InputObject;
+use async_trait::async_trait;
+use derive_builder::Builder;
+use poem_openapi::Object;
+use uuid::Uuid;
+
+use crate::{backend::engine::SDKEngine, errors::sdk::SDKError};
+
+use super::comment::Comment;
+
+#[async_trait]
+pub trait TaskCommentOperations {
+    async fn create_comment(&self, input: CreateCommentInput) -> Result<Comment, SDKError>;
+    async fn get_comments(&self, task_id: Uuid) -> Result<Vec<Comment>, SDKError>;
+    async fn update_comment(&self, id: Uuid, input: UpdateCommentInput) -> Result<Comment, SDKError>;
+    async fn delete_comment(&self, task_id: Uuid, id: Uuid) -> Result<Comment, SDKError>;
+}
+
+#[derive(Default, Builder, Object, InputObject)]
+#[builder(pattern = "owned")]
+pub struct CreateCommentInput {
+    pub task_id: Uuid,
+
+    #[graphql(skip)]
+    pub author_id: Uuid,
+
+    pub content: String,
+}
+
+#[derive(Default, Builder, Object, InputObject)]
+#[builder(pattern = "owned")]
+pub struct UpdateCommentInput {
+    #[builder(setter(strip_option), default)]
+    pub content: Option<String>,
+}
+
+#[async_trait]
+impl TaskCommentOperations for SDKEngine {
+    async fn create_comment(&self, input: CreateCommentInput) -> Result<Comment, SDKError> {
+        let comment_info = sqlx::query!(
+            r#"
+            INSERT INTO task_comments (task_id, author_id, content)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+            input.task_id,
+            input.author_id,
+            input.content,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(Comment {
+            id: comment_info.id,
+            created_at: comment_info.created_at,
+            updated_at: comment_info.updated_at,
+            task_id: comment_info.task_id,
+            author_id: comment_info.author_id,
+            content: comment_info.content,
+        })
+    }
+
+    async fn get_comments(&self, task_id: Uuid) -> Result<Vec<Comment>, SDKError> {
+        let comments_info = sqlx::query!(
+            r#"
+            SELECT * FROM task_comments
+            WHERE task_id = $1
+            ORDER BY created_at ASC
+            "#,
+            task_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Ok(comments_info
+            .into_iter()
+            .map(|comment_info| Comment {
+                id: comment_info.id,
+                created_at: comment_info.created_at,
+                updated_at: comment_info.updated_at,
+                task_id: comment_info.task_id,
+                author_id: comment_info.author_id,
+                content: comment_info.content,
+            })
+            .collect())
+    }
+
+    async fn update_comment(&self, id: Uuid, input: UpdateCommentInput) -> Result<Comment, SDKError> {
+        let comment_info = sqlx::query!(
+            r#"
+            UPDATE task_comments
+            SET content = COALESCE($1, content)
+            WHERE id = $2
+            RETURNING *
+            "#,
+            input.content,
+            id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("comment", id))?;
+
+        Ok(Comment {
+            id: comment_info.id,
+            created_at: comment_info.created_at,
+            updated_at: comment_info.updated_at,
+            task_id: comment_info.task_id,
+            author_id: comment_info.author_id,
+            content: comment_info.content,
+        })
+    }
+
+    async fn delete_comment(&self, task_id: Uuid, id: Uuid) -> Result<Comment, SDKError> {
+        let comment_info = sqlx::query!(
+            r#"
+            DELETE FROM task_comments
+            WHERE id = $1 AND task_id = $2
+            RETURNING *
+            "#,
+            id,
+            task_id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("comment", id))?;
+
+        Ok(Comment {
+            id: comment_info.id,
+            created_at: comment_info.created_at,
+            updated_at: comment_info.updated_at,
+            task_id: comment_info.task_id,
+            author_id: comment_info.author_id,
+            content: comment_info.content,
+        })
+    }
+}
@@ -3,10 +3,10 @@ use chrono::{DateTime, Utc};
 
 use poem_openapi::Object;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, SimpleObject, Object, Clone, Serialize)]
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
 #[graphql(name = "SDKLabel")]
 pub struct Label {
     pub id: Uuid,
@@ -19,3 +19,36 @@ pub struct Label {
     pub description: Option<String>,
     pub color: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_round_trip_through_json() {
+        let label = Label {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            name: "bug, \"urgent\"".to_string(),
+            owner_id: Uuid::new_v4(),
+            description: None,
+            color: Some("#d73a4a".to_string()),
+        };
+
+        let json = serde_json::to_value(&label).unwrap();
+
+        assert_eq!(json["owner_id"], label.owner_id.to_string());
+        assert!(json["description"].is_null());
+
+        let decoded: Label = serde_json::from_value(json).unwrap();
+
+        assert_eq!(decoded.id, label.id);
+        assert_eq!(decoded.created_at, label.created_at);
+        assert_eq!(decoded.updated_at, label.updated_at);
+        assert_eq!(decoded.name, label.name);
+        assert_eq!(decoded.owner_id, label.owner_id);
+        assert_eq!(decoded.description, None);
+        assert_eq!(decoded.color, label.color);
+    }
+}
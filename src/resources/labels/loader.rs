@@ -28,7 +28,7 @@ impl Loader<Uuid> for LabelLoader {
             "#,
             &keys
         )
-        .fetch_all(&*self.0.db_pool)
+        .fetch_all(&*self.0.read_pool)
         .await
         .unwrap();
 
@@ -55,3 +55,36 @@ impl Loader<Uuid> for LabelLoader {
         Ok(labels_map)
     }
 }
+
+/// Label ids attached to each task, so resolving labels for many tasks costs a single query.
+pub struct TaskLabelsLoader(Arc<SDKEngine>);
+
+impl TaskLabelsLoader {
+    pub fn new(e: Arc<SDKEngine>) -> Self {
+        Self(e)
+    }
+}
+
+impl Loader<Uuid> for TaskLabelsLoader {
+    type Value = Vec<Uuid>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &'_ [Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT task_id, label_id FROM labels_by_tasks WHERE task_id = ANY($1)
+            "#,
+            &keys
+        )
+        .fetch_all(&*self.0.read_pool)
+        .await?;
+
+        let mut labels_by_task: HashMap<Uuid, Vec<Uuid>> = keys.iter().map(|key| (*key, vec![])).collect();
+
+        for row in rows {
+            labels_by_task.entry(row.task_id).or_default().push(row.label_id);
+        }
+
+        Ok(labels_by_task)
+    }
+}
@@ -1,23 +1,56 @@
+use std::collections::{HashMap, HashSet};
+
 use async_graphql::InputObject;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use poem_openapi::Object;
-use sqlx::Row;
+use sqlx::{Connection, PgConnection, PgExecutor, Row};
+use tracing::{instrument, Span};
 use uuid::Uuid;
 
-use crate::{backend::engine::SDKEngine, common::commons::SortOrder, errors::sdk::SDKError};
+use crate::{
+    backend::{engine::SDKEngine, events::SDKEvent},
+    common::commons::{
+        clamp_page, compile_order_by, like_pattern, sql_debug, Cursor, CursorPage, FilterOp, Page, SQLArgument,
+        SortOrder,
+    },
+    errors::sdk::SDKError,
+};
 
 use super::label::Label;
 
 #[async_trait]
 pub trait LabelCrudOperations {
     async fn create_label(&self, input: CreateLabelInput) -> Result<Label, SDKError>;
+    async fn create_labels(&self, inputs: Vec<CreateLabelInput>) -> Result<Vec<Label>, SDKError>;
+    async fn upsert_label(&self, input: CreateLabelInput) -> Result<Label, SDKError>;
     async fn get_label(&self, id: Uuid) -> Result<Label, SDKError>;
     async fn get_labels(&self, input: GetLabelsInput) -> Result<Vec<Label>, SDKError>;
+    async fn get_labels_by_cursor(&self, input: GetLabelsInput) -> Result<CursorPage<Label>, SDKError>;
+    async fn get_labels_page(&self, input: GetLabelsInput, with_total: bool) -> Result<Page<Label>, SDKError>;
+    async fn get_labels_count(&self, filter: Option<GetLabelsWhere>) -> Result<i64, SDKError>;
+    async fn get_labels_with_total(&self, input: GetLabelsInput) -> Result<(Vec<Label>, i64), SDKError>;
+    /// Number of tasks the label is attached to; 0 for an unused or unknown label.
+    async fn get_label_usage(&self, label_id: Uuid) -> Result<i64, SDKError>;
+    /// Every label with the number of tasks it is attached to, ordered by name.
+    async fn get_labels_with_usage(&self) -> Result<Vec<(Label, i64)>, SDKError>;
     async fn update_label(&self, id: Uuid, input: UpdateLabelInput) -> Result<Label, SDKError>;
+    /// Deletes the label and its task links.
     async fn delete_label(&self, id: Uuid) -> Result<Label, SDKError>;
+    async fn delete_labels(&self, ids: Vec<Uuid>) -> Result<u64, SDKError>;
 }
 
+/// Label writes that run on a caller-provided connection, e.g. inside [`SDKEngine::transaction`].
+#[async_trait]
+pub trait LabelTransactionOperations {
+    async fn create_label(&mut self, input: CreateLabelInput) -> Result<Label, SDKError>;
+    /// Deletes the label and its task links in a single statement.
+    async fn delete_label(&mut self, id: Uuid) -> Result<Label, SDKError>;
+}
+
+pub const LABEL_SORT_FIELDS: [&str; 5] = ["name", "created_at", "updated_at", "color", "description"];
+
 #[derive(Default, Builder, Object, InputObject)]
 #[builder(pattern = "owned")]
 pub struct CreateLabelInput {
@@ -30,6 +63,16 @@ pub struct CreateLabelInput {
     pub description: Option<String>,
     #[builder(setter(strip_option), default)]
     pub color: Option<String>,
+
+    /// Retrying a create with the same key returns the label from the first attempt instead of inserting
+    /// a duplicate. Keys are unique among labels only; `upsert_label` ignores it.
+    #[builder(setter(into, strip_option), default)]
+    pub idempotency_key: Option<String>,
+
+    /// Member performing the write, recorded as `created_by`/`updated_by`; `None` for system operations.
+    #[graphql(skip)]
+    #[builder(setter(strip_option), default)]
+    pub actor_id: Option<Uuid>,
 }
 
 #[derive(Default, Builder, Object, InputObject)]
@@ -41,6 +84,15 @@ pub struct UpdateLabelInput {
     pub description: Option<String>,
     #[builder(setter(strip_option), default)]
     pub color: Option<String>,
+
+    /// When set, the update only applies if the label is still at this version, else `SDKError::Conflict`.
+    #[builder(setter(strip_option), default)]
+    pub expected_updated_at: Option<DateTime<Utc>>,
+
+    /// Member performing the write, recorded as `created_by`/`updated_by`; `None` for system operations.
+    #[graphql(skip)]
+    #[builder(setter(strip_option), default)]
+    pub actor_id: Option<Uuid>,
 }
 
 #[derive(Default, Builder, Object, InputObject)]
@@ -49,28 +101,60 @@ pub struct GetLabelsInput {
     #[builder(setter(strip_option), default)]
     pub filter: Option<GetLabelsWhere>,
 
+    /// Single-field shorthand for `sort`, used only when `sort` is empty.
     #[builder(setter(strip_option), default)]
     pub sort_by: Option<String>,
     #[builder(setter(strip_option), default)]
     pub sort_order: Option<SortOrder>,
+    /// `(field, order)` pairs applied in sequence, e.g. color ascending then name descending.
+    #[graphql(skip)]
+    #[oai(skip)]
+    #[builder(default)]
+    pub sort: Vec<(String, SortOrder)>,
 
     #[builder(setter(into, strip_option), default = "Some(100)")]
     pub limit: Option<i32>,
     #[builder(setter(into, strip_option), default = "Some(0)")]
     pub offset: Option<i32>,
+
+    /// Opaque `next_cursor` of a previous page; pages by `(created_at, id)` instead of `offset`.
+    #[builder(setter(into, strip_option), default)]
+    pub cursor: Option<String>,
 }
 
-#[derive(Default, Builder, Object, InputObject)]
+#[derive(Default, Builder, Object, InputObject, Clone)]
 #[builder(pattern = "owned")]
 pub struct GetLabelsWhere {
     #[builder(setter(strip_option), default)]
     pub ids: Option<Vec<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub name: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub color: Option<FilterOp<String>>,
+
+    /// Case-insensitive substring match, `ILIKE '%value%'`; `%` and `_` in the value match literally.
     #[builder(setter(strip_option), default)]
-    pub name: Option<String>,
+    pub name_contains: Option<String>,
     #[builder(setter(strip_option), default)]
-    pub description: Option<String>,
+    pub name_ilike: Option<String>,
+    /// Case-insensitive substring match, like `name_contains`.
     #[builder(setter(strip_option), default)]
-    pub color: Option<String>,
+    pub description_contains: Option<String>,
+
+    /// Inclusive lower bound.
+    #[builder(setter(strip_option), default)]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
+    #[builder(setter(strip_option), default)]
+    pub created_before: Option<DateTime<Utc>>,
+    /// Inclusive lower bound.
+    #[builder(setter(strip_option), default)]
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
+    #[builder(setter(strip_option), default)]
+    pub updated_before: Option<DateTime<Utc>>,
 
     #[oai(skip)]
     #[builder(setter(strip_option), default)]
@@ -81,41 +165,84 @@ pub struct GetLabelsWhere {
 }
 
 impl GetLabelsWhere {
-    pub fn compile_sql(&self) -> String {
+    pub fn compile_sql(&self) -> (String, Vec<SQLArgument>) {
+        let mut args = Vec::new();
+        let where_clause = self.compile_sql_with(&mut args);
+
+        (where_clause, args)
+    }
+
+    /// The compiled WHERE clause with its bound values listed after it, without running anything.
+    pub fn to_sql_debug(&self) -> String {
+        let (where_clause, args) = self.compile_sql();
+
+        sql_debug(&where_clause, &args)
+    }
+
+    fn compile_sql_with(&self, args: &mut Vec<SQLArgument>) -> String {
         let mut and_clauses = Vec::new();
         let mut or_clauses = Vec::new();
 
         if let Some(ids) = &self.ids {
-            and_clauses.push(format!(
-                "id = ANY(array[{}]::uuid[])",
-                ids.iter()
-                    .map(|x| format!("'{}'", x))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            args.push(SQLArgument::UuidArray(ids.clone()));
+            and_clauses.push(format!("id = ANY(${})", args.len()));
         }
 
         if let Some(name) = &self.name {
-            and_clauses.push(format!("name = '{}'", name));
+            and_clauses.push(name.compile_sql("name", args));
         }
         if let Some(description) = &self.description {
-            and_clauses.push(format!("description = '{}'", description));
+            and_clauses.push(description.compile_sql("description", args));
         }
         if let Some(color) = &self.color {
-            and_clauses.push(format!("color = '{}'", color));
+            and_clauses.push(color.compile_sql("color", args));
+        }
+
+        if let Some(name_contains) = &self.name_contains {
+            args.push(SQLArgument::Text(like_pattern(name_contains)));
+            and_clauses.push(format!("name ILIKE ${}", args.len()));
+        }
+        if let Some(name_ilike) = &self.name_ilike {
+            args.push(SQLArgument::Text(like_pattern(name_ilike)));
+            and_clauses.push(format!("name ILIKE ${}", args.len()));
+        }
+        if let Some(description_contains) = &self.description_contains {
+            args.push(SQLArgument::Text(like_pattern(description_contains)));
+            and_clauses.push(format!("description ILIKE ${}", args.len()));
         }
 
+        if let Some(created_after) = &self.created_after {
+            args.push(SQLArgument::Timestamp(*created_after));
+            and_clauses.push(format!("created_at >= ${}", args.len()));
+        }
+        if let Some(created_before) = &self.created_before {
+            args.push(SQLArgument::Timestamp(*created_before));
+            and_clauses.push(format!("created_at < ${}", args.len()));
+        }
+        if let Some(updated_after) = &self.updated_after {
+            args.push(SQLArgument::Timestamp(*updated_after));
+            and_clauses.push(format!("updated_at >= ${}", args.len()));
+        }
+        if let Some(updated_before) = &self.updated_before {
+            args.push(SQLArgument::Timestamp(*updated_before));
+            and_clauses.push(format!("updated_at < ${}", args.len()));
+        }
+
+        // an empty nested filter adds no clause
         if let Some(ands) = &self._and {
             for and in ands {
-                and_clauses.push(and.compile_sql());
+                and_clauses.push(and.compile_sql_with(args));
             }
         }
         if let Some(ors) = &self._or {
             for or in ors {
-                or_clauses.push(or.compile_sql());
+                or_clauses.push(or.compile_sql_with(args));
             }
         }
 
+        and_clauses.retain(|clause| !clause.is_empty());
+        or_clauses.retain(|clause| !clause.is_empty());
+
         let mut where_clause = String::new();
         if !and_clauses.is_empty() {
             where_clause.push_str(&format!("({})", and_clauses.join(" AND ")));
@@ -131,24 +258,254 @@ impl GetLabelsWhere {
     }
 }
 
+/// Offset-paginated label query, shared by `get_labels` and `get_labels_page`. The limit is used as given.
+async fn fetch_labels(
+    engine: &SDKEngine,
+    executor: impl PgExecutor<'_>,
+    input: GetLabelsInput,
+) -> Result<Vec<Label>, SDKError> {
+    let mut query = "SELECT * FROM labels ".to_string();
+    let mut args = Vec::new();
+
+    if let Some(filter) = input.filter {
+        let (where_clause, where_args) = filter.compile_sql();
+
+        if !where_clause.is_empty() {
+            query.push_str(format!("WHERE {} ", where_clause).as_str());
+            args = where_args;
+        }
+    }
+
+    let sort = match input.sort_by {
+        Some(sort_by) if input.sort.is_empty() => vec![(sort_by, input.sort_order.unwrap_or_default())],
+        _ => input.sort,
+    };
+
+    query.push_str(compile_order_by(&sort, &LABEL_SORT_FIELDS)?.as_str());
+
+    if let Some(limit) = input.limit {
+        query.push_str(format!("LIMIT {} ", limit).as_str());
+    }
+
+    if let Some(offset) = input.offset {
+        query.push_str(format!("OFFSET {} ", offset).as_str());
+    }
+
+    engine.log_sql(&query, &args);
+
+    let labels_info = args
+        .into_iter()
+        .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+        .fetch_all(executor)
+        .await?;
+
+    Span::current().record("rows", labels_info.len());
+
+    let labels = labels_info
+        .into_iter()
+        .map(|label_info| Label {
+            id: label_info.get("id"),
+            created_at: label_info.get("created_at"),
+            updated_at: label_info.get("updated_at"),
+            name: label_info.get("name"),
+            owner_id: label_info.get("owner_id"),
+            description: label_info.get("description"),
+            color: label_info.get("color"),
+        })
+        .collect();
+
+    Ok(labels)
+}
+
+/// Labels matching `filter`, shared by `get_labels_count` and `get_labels_with_total`.
+async fn count_labels(executor: impl PgExecutor<'_>, filter: Option<GetLabelsWhere>) -> Result<i64, SDKError> {
+    let mut query = "SELECT COUNT(*) FROM labels ".to_string();
+    let mut args = Vec::new();
+
+    if let Some(filter) = filter {
+        let (where_clause, where_args) = filter.compile_sql();
+
+        if !where_clause.is_empty() {
+            query.push_str(format!("WHERE {} ", where_clause).as_str());
+            args = where_args;
+        }
+    }
+
+    let count = args
+        .into_iter()
+        .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+        .fetch_one(executor)
+        .await?
+        .get::<i64, _>(0);
+
+    Ok(count)
+}
+
+/// `#rgb` or `#rrggbb`, in either case.
+pub(crate) fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .filter(|digits| matches!(digits.len(), 3 | 6))
+        .is_some_and(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn validate_color(color: &Option<String>) -> Result<(), SDKError> {
+    let Some(color) = color else {
+        return Ok(());
+    };
+
+    if !is_hex_color(color) {
+        return Err(SDKError::InvalidColor(color.clone()));
+    }
+
+    Ok(())
+}
+
 #[async_trait]
 impl LabelCrudOperations for SDKEngine {
+    #[instrument(skip_all, fields(resource = "label", operation = "create"))]
     async fn create_label(&self, input: CreateLabelInput) -> Result<Label, SDKError> {
-        let label_info = sqlx::query!(
+        let (label, created) = insert_label(&mut *self.acquire().await?, input).await?;
+
+        if created {
+            self.emit(|| SDKEvent::LabelCreated(label.clone()));
+        }
+
+        Ok(label)
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "create", rows))]
+    async fn create_labels(&self, inputs: Vec<CreateLabelInput>) -> Result<Vec<Label>, SDKError> {
+        for input in &inputs {
+            validate_color(&input.color)?;
+        }
+
+        let mut tx = self.begin().await?;
+
+        let names: Vec<String> = inputs.iter().map(|input| input.name.clone()).collect();
+        let descriptions: Vec<Option<String>> = inputs.iter().map(|input| input.description.clone()).collect();
+        let colors: Vec<Option<String>> = inputs.iter().map(|input| input.color.clone()).collect();
+        let owner_ids: Vec<Uuid> = inputs.iter().map(|input| input.owner_id).collect();
+        let actor_ids: Vec<Option<Uuid>> = inputs.iter().map(|input| input.actor_id).collect();
+        let idempotency_keys: Vec<Option<String>> = inputs.iter().map(|input| input.idempotency_key.clone()).collect();
+
+        let labels_info = sqlx::query!(
             r#"
-            INSERT INTO labels (name, description, color, owner_id)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO labels (name, description, color, owner_id, created_by, updated_by, idempotency_key)
+            SELECT name, description, color, owner_id, actor_id, actor_id, idempotency_key
+            FROM UNNEST($1::text[], $2::text[], $3::varchar[], $4::uuid[], $5::uuid[], $6::text[])
+                AS input(name, description, color, owner_id, actor_id, idempotency_key)
+            ON CONFLICT (idempotency_key) DO NOTHING
             RETURNING *
             "#,
+            &names,
+            &descriptions as &[Option<String>],
+            &colors as &[Option<String>],
+            &owner_ids,
+            &actor_ids as &[Option<Uuid>],
+            &idempotency_keys as &[Option<String>],
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(SDKError::or_duplicate_labels(&names))?;
+
+        // read after the insert, so it holds both the labels just created and those from earlier attempts
+        let keyed_info = sqlx::query!(
+            r#"
+            SELECT * FROM labels
+            WHERE idempotency_key = ANY($1)
+            "#,
+            &idempotency_keys.iter().flatten().cloned().collect::<Vec<String>>(),
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Span::current().record("rows", labels_info.len());
+
+        tx.commit().await?;
+
+        let mut created_ids: HashSet<Uuid> = labels_info.iter().map(|label_info| label_info.id).collect();
+
+        // label names are unique, so they map the returned rows back to the input order
+        let mut labels_map: HashMap<String, Label> = labels_info
+            .into_iter()
+            .map(|label_info| {
+                (
+                    label_info.name.clone(),
+                    Label {
+                        id: label_info.id,
+                        created_at: label_info.created_at,
+                        updated_at: label_info.updated_at,
+                        name: label_info.name,
+                        owner_id: label_info.owner_id,
+                        description: label_info.description,
+                        color: label_info.color,
+                    },
+                )
+            })
+            .collect();
+
+        let keyed_map: HashMap<String, Label> = keyed_info
+            .into_iter()
+            .filter_map(|label_info| {
+                Some((
+                    label_info.idempotency_key?,
+                    Label {
+                        id: label_info.id,
+                        created_at: label_info.created_at,
+                        updated_at: label_info.updated_at,
+                        name: label_info.name,
+                        owner_id: label_info.owner_id,
+                        description: label_info.description,
+                        color: label_info.color,
+                    },
+                ))
+            })
+            .collect();
+
+        let labels: Vec<Label> = inputs
+            .iter()
+            .filter_map(|input| match &input.idempotency_key {
+                Some(key) => keyed_map.get(key).cloned(),
+                None => labels_map.remove(&input.name),
+            })
+            .collect();
+
+        // a key repeated within the batch yields the same label twice, but it was created once
+        for label in labels.iter().filter(|label| created_ids.remove(&label.id)) {
+            self.emit(|| SDKEvent::LabelCreated(label.clone()));
+        }
+
+        Ok(labels)
+    }
+
+    /// Creates the label, or updates the one with the same name. An existing label keeps
+    /// its owner, and its description and color unless the input sets them.
+    #[instrument(skip_all, fields(resource = "label", operation = "upsert"))]
+    async fn upsert_label(&self, input: CreateLabelInput) -> Result<Label, SDKError> {
+        validate_color(&input.color)?;
+
+        let label_info = sqlx::query!(
+            r#"
+            INSERT INTO labels (name, description, color, owner_id, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $5)
+            ON CONFLICT (name) DO UPDATE
+            SET
+                description = COALESCE(EXCLUDED.description, labels.description),
+                color = COALESCE(EXCLUDED.color, labels.color),
+                updated_by = EXCLUDED.updated_by
+            RETURNING *, (xmax = 0) AS "inserted!"
+            "#,
             input.name,
             input.description,
             input.color,
             input.owner_id,
+            input.actor_id,
         )
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
-        Ok(Label {
+        let label = Label {
             id: label_info.id,
             created_at: label_info.created_at,
             updated_at: label_info.updated_at,
@@ -156,9 +513,17 @@ impl LabelCrudOperations for SDKEngine {
             owner_id: label_info.owner_id,
             description: label_info.description,
             color: label_info.color,
-        })
+        };
+
+        self.emit(|| match label_info.inserted {
+            true => SDKEvent::LabelCreated(label.clone()),
+            false => SDKEvent::LabelUpdated(label.clone()),
+        });
+
+        Ok(label)
     }
 
+    #[instrument(skip_all, fields(resource = "label", operation = "read"))]
     async fn get_label(&self, id: Uuid) -> Result<Label, SDKError> {
         let label_info = sqlx::query!(
             r#"
@@ -167,8 +532,9 @@ impl LabelCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("label", id))?;
 
         Ok(Label {
             id: label_info.id,
@@ -181,32 +547,126 @@ impl LabelCrudOperations for SDKEngine {
         })
     }
 
+    #[instrument(skip_all, fields(resource = "label", operation = "read"))]
+    async fn get_label_usage(&self, label_id: Uuid) -> Result<i64, SDKError> {
+        let usage = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "usage!" FROM labels_by_tasks
+            WHERE label_id = $1
+            "#,
+            label_id,
+        )
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await?
+        .usage;
+
+        Ok(usage)
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "read", rows))]
+    async fn get_labels_with_usage(&self) -> Result<Vec<(Label, i64)>, SDKError> {
+        let labels_info = sqlx::query!(
+            r#"
+            SELECT labels.*, COUNT(labels_by_tasks.task_id) AS "usage!"
+            FROM labels
+            LEFT JOIN labels_by_tasks ON labels_by_tasks.label_id = labels.id
+            GROUP BY labels.id
+            ORDER BY labels.name ASC
+            "#,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Span::current().record("rows", labels_info.len());
+
+        Ok(labels_info
+            .into_iter()
+            .map(|label_info| {
+                (
+                    Label {
+                        id: label_info.id,
+                        created_at: label_info.created_at,
+                        updated_at: label_info.updated_at,
+                        name: label_info.name,
+                        owner_id: label_info.owner_id,
+                        description: label_info.description,
+                        color: label_info.color,
+                    },
+                    label_info.usage,
+                )
+            })
+            .collect())
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "read", rows))]
     async fn get_labels(&self, input: GetLabelsInput) -> Result<Vec<Label>, SDKError> {
+        if input.cursor.is_some() {
+            return self.get_labels_by_cursor(input).await.map(|page| page.items);
+        }
+
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+
+        fetch_labels(
+            self,
+            &mut *self.acquire_read().await?,
+            GetLabelsInput {
+                limit: Some(limit),
+                ..input
+            },
+        )
+        .await
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "read", rows))]
+    async fn get_labels_by_cursor(&self, input: GetLabelsInput) -> Result<CursorPage<Label>, SDKError> {
+        if input.sort_by.is_some() || !input.sort.is_empty() {
+            return Err(SDKError::InvalidCursor(
+                "cursor pages are always sorted by created_at and id".to_string(),
+            ));
+        }
+
+        let limit = clamp_page(input.limit, None, self.config.max_page_size)?;
+
         let mut query = "SELECT * FROM labels ".to_string();
+        let mut args = Vec::new();
+        let mut conditions = Vec::new();
 
         if let Some(filter) = input.filter {
-            query.push_str(format!("WHERE {} ", filter.compile_sql()).as_str());
-        }
+            let (where_clause, where_args) = filter.compile_sql();
 
-        if let Some(sort_by) = input.sort_by {
-            query.push_str(format!("ORDER BY {} ", sort_by).as_str());
+            if !where_clause.is_empty() {
+                conditions.push(format!("({})", where_clause));
+                args = where_args;
+            }
         }
 
-        if let Some(sort_order) = input.sort_order {
-            query.push_str(format!("{} ", sort_order).as_str());
-        }
+        if let Some(cursor) = input.cursor {
+            let cursor = Cursor::decode(&cursor)?;
 
-        if let Some(limit) = input.limit {
-            query.push_str(format!("LIMIT {} ", limit).as_str());
+            args.push(SQLArgument::Timestamp(cursor.created_at));
+            args.push(SQLArgument::Uuid(cursor.id));
+            conditions.push(format!("(created_at, id) > (${}, ${})", args.len() - 1, args.len()));
         }
 
-        if let Some(offset) = input.offset {
-            query.push_str(format!("OFFSET {} ", offset).as_str());
+        if !conditions.is_empty() {
+            query.push_str(format!("WHERE {} ", conditions.join(" AND ")).as_str());
         }
 
-        let labels_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        query.push_str("ORDER BY created_at ASC, id ASC ");
+
+        query.push_str(format!("LIMIT {} ", limit).as_str());
+
+        self.log_sql(&query, &args);
 
-        let labels = labels_info
+        let labels_info = args
+            .into_iter()
+            .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
+
+        Span::current().record("rows", labels_info.len());
+
+        let labels: Vec<Label> = labels_info
             .into_iter()
             .map(|label_info| Label {
                 id: label_info.get("id"),
@@ -219,29 +679,139 @@ impl LabelCrudOperations for SDKEngine {
             })
             .collect();
 
-        Ok(labels)
+        let next_cursor = match labels.last() {
+            Some(last) if labels.len() as i32 >= limit => Some(
+                Cursor {
+                    created_at: last.created_at,
+                    id: last.id,
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+
+        Ok(CursorPage {
+            items: labels,
+            next_cursor,
+        })
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "read", rows))]
+    async fn get_labels_page(&self, input: GetLabelsInput, with_total: bool) -> Result<Page<Label>, SDKError> {
+        if input.cursor.is_some() {
+            return Err(SDKError::InvalidPagination(
+                "get_labels_page pages by offset, not by cursor".to_string(),
+            ));
+        }
+
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        let offset = input.offset.unwrap_or(0);
+
+        let total = match with_total {
+            true => Some(self.get_labels_count(input.filter.clone()).await?),
+            false => None,
+        };
+
+        // the extra row only tells whether another page exists, so it may exceed `max_page_size`
+        let labels = fetch_labels(
+            self,
+            &mut *self.acquire_read().await?,
+            GetLabelsInput {
+                limit: Some(limit + 1),
+                offset: Some(offset),
+                ..input
+            },
+        )
+        .await?;
+
+        Ok(Page::from_overfetch(labels, total, limit, offset))
     }
 
+    #[instrument(skip_all, fields(resource = "label", operation = "read"))]
+    async fn get_labels_count(&self, filter: Option<GetLabelsWhere>) -> Result<i64, SDKError> {
+        count_labels(&mut *self.acquire_read().await?, filter).await
+    }
+
+    /// The offset page `input` selects, plus how many labels match its filter regardless of `limit` and
+    /// `offset`. Both are read from one snapshot, so the total always agrees with the page.
+    #[instrument(skip_all, fields(resource = "label", operation = "read", rows))]
+    async fn get_labels_with_total(&self, input: GetLabelsInput) -> Result<(Vec<Label>, i64), SDKError> {
+        if input.cursor.is_some() {
+            return Err(SDKError::InvalidPagination(
+                "get_labels_with_total pages by offset, not by cursor".to_string(),
+            ));
+        }
+
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+
+        let mut conn = self.acquire_read().await?;
+        let mut tx = conn.begin().await?;
+
+        // without a repeatable read each statement would get its own snapshot
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+
+        let total = count_labels(&mut *tx, input.filter.clone()).await?;
+        let labels = fetch_labels(
+            self,
+            &mut *tx,
+            GetLabelsInput {
+                limit: Some(limit),
+                ..input
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((labels, total))
+    }
+
+    #[instrument(skip_all, fields(resource = "label", operation = "update"))]
     async fn update_label(&self, id: Uuid, input: UpdateLabelInput) -> Result<Label, SDKError> {
+        validate_color(&input.color)?;
+
         let label_info = sqlx::query!(
             r#"
             UPDATE labels
             SET
                 name = COALESCE($1, name),
                 description = COALESCE($2, description),
-                color = COALESCE($3, color)
-            WHERE id = $4
+                color = COALESCE($3, color),
+                updated_by = $6
+            WHERE id = $4 AND ($5::timestamptz IS NULL OR updated_at = $5)
             RETURNING *
             "#,
             input.name,
             input.description,
             input.color,
             id,
+            input.expected_updated_at,
+            input.actor_id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_optional(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_duplicate_label(input.name.as_deref().unwrap_or_default()))?;
 
-        Ok(Label {
+        let Some(label_info) = label_info else {
+            let exists = sqlx::query!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM labels WHERE id = $1) AS "exists!"
+                "#,
+                id,
+            )
+            .fetch_one(&mut *self.acquire().await?)
+            .await?
+            .exists;
+
+            return Err(match exists {
+                true => SDKError::Conflict { resource: "label", id },
+                false => SDKError::NotFound { resource: "label", id },
+            });
+        };
+
+        let label = Label {
             id: label_info.id,
             created_at: label_info.created_at,
             updated_at: label_info.updated_at,
@@ -249,19 +819,78 @@ impl LabelCrudOperations for SDKEngine {
             owner_id: label_info.owner_id,
             description: label_info.description,
             color: label_info.color,
-        })
+        };
+
+        self.emit(|| SDKEvent::LabelUpdated(label.clone()));
+
+        Ok(label)
     }
 
+    #[instrument(skip_all, fields(resource = "label", operation = "delete"))]
     async fn delete_label(&self, id: Uuid) -> Result<Label, SDKError> {
+        let label = self.acquire().await?.delete_label(id).await?;
+
+        self.emit(|| SDKEvent::LabelDeleted { id: label.id });
+
+        Ok(label)
+    }
+
+    /// Deletes the labels and their task links; returns how many labels existed and were removed.
+    #[instrument(skip_all, fields(resource = "label", operation = "delete", rows))]
+    async fn delete_labels(&self, ids: Vec<Uuid>) -> Result<u64, SDKError> {
+        let mut tx = self.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM labels_by_tasks WHERE label_id = ANY($1)
+            "#,
+            &ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM labels WHERE id = ANY($1)
+            RETURNING id
+            "#,
+            &ids,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Span::current().record("rows", deleted.len());
+
+        tx.commit().await?;
+
+        for label in &deleted {
+            self.emit(|| SDKEvent::LabelDeleted { id: label.id });
+        }
+
+        Ok(deleted.len() as u64)
+    }
+}
+
+#[async_trait]
+impl LabelTransactionOperations for PgConnection {
+    async fn create_label(&mut self, input: CreateLabelInput) -> Result<Label, SDKError> {
+        insert_label(self, input).await.map(|(label, _)| label)
+    }
+
+    async fn delete_label(&mut self, id: Uuid) -> Result<Label, SDKError> {
         let label_info = sqlx::query!(
             r#"
+            WITH links AS (
+                DELETE FROM labels_by_tasks WHERE label_id = $1
+            )
             DELETE FROM labels WHERE id = $1
             RETURNING *
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self)
+        .await
+        .map_err(SDKError::or_not_found("label", id))?;
 
         Ok(Label {
             id: label_info.id,
@@ -274,3 +903,221 @@ impl LabelCrudOperations for SDKEngine {
         })
     }
 }
+
+/// Inserts the label and reports whether it was created. A label whose `idempotency_key` is already
+/// taken is returned as stored.
+async fn insert_label(conn: &mut PgConnection, input: CreateLabelInput) -> Result<(Label, bool), SDKError> {
+    validate_color(&input.color)?;
+
+    let label_info = sqlx::query!(
+        r#"
+        INSERT INTO labels (name, description, color, owner_id, created_by, updated_by, idempotency_key)
+        VALUES ($1, $2, $3, $4, $5, $5, $6)
+        ON CONFLICT (idempotency_key) DO NOTHING
+        RETURNING *
+        "#,
+        input.name,
+        input.description,
+        input.color,
+        input.owner_id,
+        input.actor_id,
+        input.idempotency_key,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(SDKError::or_duplicate_label(&input.name))?;
+
+    if let Some(label_info) = label_info {
+        return Ok((
+            Label {
+                id: label_info.id,
+                created_at: label_info.created_at,
+                updated_at: label_info.updated_at,
+                name: label_info.name,
+                owner_id: label_info.owner_id,
+                description: label_info.description,
+                color: label_info.color,
+            },
+            true,
+        ));
+    }
+
+    let label_info = sqlx::query!(
+        r#"
+        SELECT * FROM labels
+        WHERE idempotency_key = $1
+        "#,
+        input.idempotency_key,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok((
+        Label {
+            id: label_info.id,
+            created_at: label_info.created_at,
+            updated_at: label_info.updated_at,
+            name: label_info.name,
+            owner_id: label_info.owner_id,
+            description: label_info.description,
+            color: label_info.color,
+        },
+        false,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_parse_from_graphql_values() {
+        use async_graphql::{value, InputType};
+
+        let id = Uuid::new_v4();
+        let value = value!({
+            "name": { "neq": "wontfix" },
+            "nameContains": "bug",
+            // `_and` and `_or` are camel-cased to `and` and `or` in the schema
+            "or": [
+                { "color": { "isNull": true } },
+                { "ids": [id.to_string()], "and": [{ "description": { "eq": "triage" } }] },
+            ],
+        });
+
+        let filter = GetLabelsWhere::parse(Some(value))
+            .map_err(|err| err.into_server_error(Default::default()).message)
+            .unwrap();
+
+        assert_eq!(filter.name, Some(FilterOp::Neq("wontfix".to_string())));
+        assert_eq!(filter.name_contains.as_deref(), Some("bug"));
+
+        let nested = filter._or.as_ref().unwrap();
+
+        assert_eq!(nested.len(), 2);
+        assert_eq!(nested[0].color, Some(FilterOp::IsNull(true)));
+        assert_eq!(nested[1].ids, Some(vec![id]));
+        assert_eq!(
+            nested[1]._and.as_ref().unwrap()[0].description,
+            Some(FilterOp::Eq("triage".to_string()))
+        );
+    }
+
+    #[test]
+    fn empty_nested_filters_add_no_clause() {
+        let filter = GetLabelsWhere {
+            _and: Some(vec![GetLabelsWhere::default()]),
+            _or: Some(vec![]),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.compile_sql().0, "");
+    }
+
+    #[test]
+    fn placeholders_are_numbered_across_nested_groups() {
+        let filter = GetLabelsWhere {
+            name: Some("O'Brien".to_string().into()),
+            _and: Some(vec![GetLabelsWhere {
+                color: Some("#fff".to_string().into()),
+                ..Default::default()
+            }]),
+            _or: Some(vec![
+                GetLabelsWhere {
+                    description: Some("infra".to_string().into()),
+                    ..Default::default()
+                },
+                GetLabelsWhere {
+                    name_contains: Some("ug".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let (where_clause, args) = filter.compile_sql();
+
+        assert_eq!(
+            where_clause,
+            "(name = $1 AND (color = $2)) OR ((description = $3) OR (name ILIKE $4))"
+        );
+        assert_eq!(
+            args,
+            vec![
+                SQLArgument::Text("O'Brien".to_string()),
+                SQLArgument::Text("#fff".to_string()),
+                SQLArgument::Text("infra".to_string()),
+                SQLArgument::Text("%ug%".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_fields_outside_the_allowlist_are_rejected() {
+        let sort = vec![("name; DROP TABLE labels".to_string(), SortOrder::Asc)];
+
+        assert!(matches!(
+            compile_order_by(&sort, &LABEL_SORT_FIELDS),
+            Err(SDKError::InvalidSortField(field)) if field == "name; DROP TABLE labels"
+        ));
+
+        let sort = vec![
+            ("color".to_string(), SortOrder::Asc),
+            ("name".to_string(), SortOrder::Desc),
+        ];
+
+        assert_eq!(
+            compile_order_by(&sort, &LABEL_SORT_FIELDS).unwrap(),
+            "ORDER BY color ASC, name DESC "
+        );
+    }
+
+    #[test]
+    fn colors_must_be_short_or_long_hex_codes() {
+        assert!(is_hex_color("#fff"));
+        assert!(is_hex_color("#AABBCC"));
+
+        assert!(!is_hex_color("red"));
+        assert!(!is_hex_color("fff"));
+        assert!(!is_hex_color("#ffff"));
+        assert!(!is_hex_color("#GGHHII"));
+
+        assert!(validate_color(&None).is_ok());
+        assert!(matches!(
+            validate_color(&Some("red".to_string())),
+            Err(SDKError::InvalidColor(color)) if color == "red"
+        ));
+    }
+
+    #[test]
+    fn to_sql_debug_lists_the_bound_values_of_a_nested_filter() {
+        let filter = GetLabelsWhere {
+            name: Some(FilterOp::Neq("wontfix".to_string())),
+            name_contains: Some("bug".to_string()),
+            _or: Some(vec![
+                GetLabelsWhere {
+                    color: Some(FilterOp::IsNull(true)),
+                    ..Default::default()
+                },
+                GetLabelsWhere {
+                    ids: Some(vec![Uuid::from_u128(1)]),
+                    description: Some(FilterOp::Eq("O'Brien's".to_string())),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            filter.to_sql_debug(),
+            [
+                "(name <> $1 AND name ILIKE $2) OR ((color IS NULL) OR (id = ANY($3) AND description = $4))",
+                "-- $1 = 'wontfix' (bound value, may be sensitive)",
+                "-- $2 = '%bug%' (bound value, may be sensitive)",
+                "-- $3 = ARRAY['00000000-0000-0000-0000-000000000001'] (bound value, may be sensitive)",
+                "-- $4 = 'O''Brien''s' (bound value, may be sensitive)",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -31,7 +31,7 @@ impl LabelRelations for Label {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
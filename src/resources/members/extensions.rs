@@ -3,6 +3,8 @@ use std::str::FromStr;
 use async_graphql::InputObject;
 use async_trait::async_trait;
 use derive_builder::Builder;
+use sqlx::PgConnection;
+use uuid::Uuid;
 
 use crate::{backend::engine::SDKEngine, errors::sdk::SDKError};
 
@@ -14,6 +16,8 @@ pub trait MembersExtensionOperations {
     async fn create_member_from_email(&self, input: CreateMemberFromEmailInput) -> Result<Member, SDKError>;
     async fn get_member_by_github_id(&self, github_id: String) -> Result<Option<Member>, SDKError>;
     async fn get_member_by_email(&self, email: String) -> Result<Option<Member>, SDKError>;
+    /// Id of the member with this email, compared case-insensitively, else `SDKError::MemberNotFound`.
+    async fn resolve_member_by_email(&self, email: String) -> Result<Uuid, SDKError>;
 }
 
 #[derive(Default, Builder, InputObject)]
@@ -51,7 +55,7 @@ impl MembersExtensionOperations for SDKEngine {
             input.github_id,
             input.photo_url,
         )
-        .fetch_one(&*self.db_pool)
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(Member {
@@ -84,7 +88,7 @@ impl MembersExtensionOperations for SDKEngine {
             input.photo_url,
             input.role.map(|role| role.to_string()),
         )
-        .fetch_one(&*self.db_pool)
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         Ok(Member {
@@ -112,7 +116,7 @@ impl MembersExtensionOperations for SDKEngine {
             ",
             github_id,
         )
-        .fetch_one(&*self.db_pool)
+        .fetch_one(&mut *self.acquire_read().await?)
         .await?;
 
         Ok(Some(Member {
@@ -140,7 +144,7 @@ impl MembersExtensionOperations for SDKEngine {
             ",
             email,
         )
-        .fetch_one(&*self.db_pool)
+        .fetch_one(&mut *self.acquire_read().await?)
         .await?;
 
         Ok(Some(Member {
@@ -159,4 +163,59 @@ impl MembersExtensionOperations for SDKEngine {
             password_hash: member_info.password_hash,
         }))
     }
+
+    async fn resolve_member_by_email(&self, email: String) -> Result<Uuid, SDKError> {
+        find_or_create_member_by_email(&mut *self.acquire_read().await?, &email, false).await
+    }
+}
+
+/// Resolves `email` to a member id like [`MembersExtensionOperations::resolve_member_by_email`]. With `create`
+/// set, a missing member is created with the address's local part as name; `members.email` isn't unique, so
+/// the lookup and insert are serialized per email and `conn` should be inside a transaction.
+pub(crate) async fn find_or_create_member_by_email(
+    conn: &mut PgConnection,
+    email: &str,
+    create: bool,
+) -> Result<Uuid, SDKError> {
+    if create {
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtext(lower($1)))", email)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let member_id = sqlx::query!(
+        "
+        SELECT id FROM members
+        WHERE lower(email) = lower($1)
+        ORDER BY created_at ASC
+        LIMIT 1
+        ",
+        email,
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .map(|member_info| member_info.id);
+
+    match member_id {
+        Some(member_id) => Ok(member_id),
+        None if create => {
+            let name = email.split('@').next().unwrap_or(email);
+
+            let member_info = sqlx::query!(
+                "
+                INSERT INTO members (email, name, role)
+                VALUES ($1, $2, $3)
+                RETURNING id
+                ",
+                email,
+                name,
+                MemberRole::default().to_string(),
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+
+            Ok(member_info.id)
+        }
+        None => Err(SDKError::MemberNotFound(email.to_string())),
+    }
 }
@@ -8,7 +8,11 @@ use poem_openapi::Object;
 use sqlx::Row;
 use uuid::Uuid;
 
-use crate::{backend::engine::SDKEngine, common::commons::SortOrder, errors::sdk::SDKError};
+use crate::{
+    backend::engine::SDKEngine,
+    common::commons::{clamp_page, SortOrder},
+    errors::sdk::SDKError,
+};
 
 use super::member::{Member, MemberRole};
 
@@ -178,7 +182,7 @@ impl MemberCrudOperations for SDKEngine {
             input.photo_url,
             input.password_hash
         )
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_one(&mut *self.acquire().await?)
         .await?;
 
         let member = Member {
@@ -209,8 +213,9 @@ impl MemberCrudOperations for SDKEngine {
             "#,
             id
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("member", id))?;
 
         let member = Member {
             id: member_info.id,
@@ -246,15 +251,16 @@ impl MemberCrudOperations for SDKEngine {
             query.push_str(format!("{} ", sort_order).as_str());
         }
 
-        if let Some(limit) = input.limit {
-            query.push_str(format!("LIMIT {} ", limit).as_str());
-        }
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        query.push_str(format!("LIMIT {} ", limit).as_str());
 
         if let Some(offset) = input.offset {
             query.push_str(format!("OFFSET {} ", offset).as_str());
         }
 
-        let members_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        let members_info = sqlx::query(query.as_str())
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
 
         let members = members_info
             .iter()
@@ -302,8 +308,9 @@ impl MemberCrudOperations for SDKEngine {
             input.password_hash,
             id
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("member", id))?;
 
         let member = Member {
             id: member_final_info.id,
@@ -332,8 +339,9 @@ impl MemberCrudOperations for SDKEngine {
             "#,
             id
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("member", id))?;
 
         let member = Member {
             id: member_info.id,
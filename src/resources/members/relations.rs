@@ -26,7 +26,7 @@ impl MemberRelations for Member {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -51,7 +51,7 @@ impl MemberRelations for Member {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -73,7 +73,7 @@ impl MemberRelations for Member {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await?
         .into_iter()
         .map(|id| id.team_id)
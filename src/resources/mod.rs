@@ -1,5 +1,6 @@
 pub mod assets;
 pub mod changes;
+pub mod comments;
 pub mod labels;
 pub mod members;
 pub mod projects;
@@ -0,0 +1,276 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    backend::{engine::SDKEngine, events::SDKEvent},
+    errors::sdk::SDKError,
+    resources::{
+        labels::label::Label,
+        tasks::{operations::TaskRecord, task::Task},
+    },
+};
+
+use super::{
+    operations::ProjectCrudOperations,
+    project::{Project, ProjectStatus, ProjectVisibility},
+};
+
+pub const PROJECT_EXPORT_VERSION: u32 = 1;
+
+/// Self-contained snapshot of a project, its tasks and the labels attached to them.
+/// Ids are only meaningful within the document; imports assign fresh ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectExport {
+    pub version: u32,
+    pub project: Project,
+    pub tasks: Vec<Task>,
+    pub labels: Vec<Label>,
+    pub task_labels: Vec<TaskLabelLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLabelLink {
+    pub task_id: Uuid,
+    pub label_id: Uuid,
+}
+
+#[async_trait]
+pub trait ProjectExportOperations {
+    async fn export_project(&self, project_id: Uuid) -> Result<ProjectExport, SDKError>;
+    /// Recreates the project under new ids. Labels are matched by name, so existing ones are reused.
+    /// Owners that aren't members here become `owner_id`, which must exist, and unknown leads are dropped.
+    async fn import_project(&self, export: ProjectExport, owner_id: Uuid) -> Result<Project, SDKError>;
+}
+
+#[async_trait]
+impl ProjectExportOperations for SDKEngine {
+    async fn export_project(&self, project_id: Uuid) -> Result<ProjectExport, SDKError> {
+        let project = self.get_project(project_id).await?;
+
+        let tasks: Vec<Task> = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT * FROM tasks
+            WHERE project_id = $1
+            ORDER BY position
+            "#,
+            project_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?
+        .into_iter()
+        .map(Task::try_from)
+        .collect::<Result<_, _>>()?;
+
+        let task_ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+
+        let task_labels: Vec<TaskLabelLink> = sqlx::query!(
+            r#"
+            SELECT task_id, label_id FROM labels_by_tasks
+            WHERE task_id = ANY($1)
+            "#,
+            &task_ids,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?
+        .into_iter()
+        .map(|link| TaskLabelLink {
+            task_id: link.task_id,
+            label_id: link.label_id,
+        })
+        .collect();
+
+        let labels = sqlx::query!(
+            r#"
+            SELECT * FROM labels
+            WHERE id IN (SELECT label_id FROM labels_by_tasks WHERE task_id = ANY($1))
+            ORDER BY name
+            "#,
+            &task_ids,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?
+        .into_iter()
+        .map(|label_info| Label {
+            id: label_info.id,
+            created_at: label_info.created_at,
+            updated_at: label_info.updated_at,
+            name: label_info.name,
+            owner_id: label_info.owner_id,
+            description: label_info.description,
+            color: label_info.color,
+        })
+        .collect();
+
+        Ok(ProjectExport {
+            version: PROJECT_EXPORT_VERSION,
+            project,
+            tasks,
+            labels,
+            task_labels,
+        })
+    }
+
+    async fn import_project(&self, export: ProjectExport, owner_id: Uuid) -> Result<Project, SDKError> {
+        if export.version != PROJECT_EXPORT_VERSION {
+            return Err(SDKError::UnsupportedExportVersion(export.version));
+        }
+
+        let mut tx = self.begin().await?;
+
+        // the document may come from another database, whose members don't exist here
+        let referenced: Vec<Uuid> = [owner_id, export.project.owner_id]
+            .into_iter()
+            .chain(export.project.lead_id)
+            .chain(
+                export
+                    .tasks
+                    .iter()
+                    .flat_map(|task| [Some(task.owner_id), task.lead_id])
+                    .flatten(),
+            )
+            .chain(export.labels.iter().map(|label| label.owner_id))
+            .collect();
+
+        let members: HashSet<Uuid> = sqlx::query!(
+            r#"
+            SELECT id FROM members WHERE id = ANY($1)
+            "#,
+            &referenced,
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|member| member.id)
+        .collect();
+
+        if !members.contains(&owner_id) {
+            return Err(SDKError::NotFound {
+                resource: "member",
+                id: owner_id,
+            });
+        }
+
+        let owner = |id: Uuid| if members.contains(&id) { id } else { owner_id };
+        let lead = |id: Option<Uuid>| id.filter(|id| members.contains(id));
+
+        let project_info = sqlx::query!(
+            r#"
+            INSERT INTO projects (name, prefix, owner_id, description, lead_id, start_date, due_date, status, visibility)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+            export.project.name,
+            export.project.prefix,
+            owner(export.project.owner_id),
+            export.project.description,
+            lead(export.project.lead_id),
+            export.project.start_date,
+            export.project.due_date,
+            export.project.status.to_string(),
+            export.project.visibility.to_string(),
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut label_ids = HashMap::new();
+
+        for label in export.labels {
+            // label names are unique, so a label that already exists here is reused as is
+            let label_id = sqlx::query!(
+                r#"
+                INSERT INTO labels (name, description, color, owner_id)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (name) DO UPDATE SET name = EXCLUDED.name
+                RETURNING id
+                "#,
+                label.name,
+                label.description,
+                label.color,
+                owner(label.owner_id),
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .id;
+
+            label_ids.insert(label.id, label_id);
+        }
+
+        let task_ids: HashMap<Uuid, Uuid> = export.tasks.iter().map(|task| (task.id, Uuid::new_v4())).collect();
+
+        for task in &export.tasks {
+            sqlx::query!(
+                r#"
+                INSERT INTO tasks (id, title, description, owner_id, status, priority, due_date, project_id, lead_id, parent_id, archived_at, recurrence, position)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                "#,
+                task_ids[&task.id],
+                task.title,
+                task.description,
+                owner(task.owner_id),
+                task.status.to_string(),
+                task.priority.to_string(),
+                task.due_date,
+                project_info.id,
+                lead(task.lead_id),
+                task.parent_id.and_then(|parent_id| task_ids.get(&parent_id).copied()),
+                task.archived_at,
+                task.recurrence,
+                task.position,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for link in export.task_labels {
+            let (Some(task_id), Some(label_id)) = (task_ids.get(&link.task_id), label_ids.get(&link.label_id)) else {
+                continue;
+            };
+
+            sqlx::query!(
+                r#"
+                INSERT INTO labels_by_tasks (task_id, label_id)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "#,
+                task_id,
+                label_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        let project = Project {
+            id: project_info.id,
+            created_at: project_info.created_at,
+            updated_at: project_info.updated_at,
+            name: project_info.name,
+            prefix: project_info.prefix,
+            owner_id: project_info.owner_id,
+            description: project_info.description,
+            lead_id: project_info.lead_id,
+            start_date: project_info.start_date,
+            due_date: project_info.due_date,
+            status: project_info
+                .status
+                .and_then(|a| ProjectStatus::from_str(&a).ok())
+                .unwrap_or_default(),
+            visibility: project_info
+                .visibility
+                .and_then(|a| ProjectVisibility::from_str(&a).ok())
+                .unwrap_or_default(),
+        };
+
+        self.emit(|| SDKEvent::ProjectCreated(project.clone()));
+
+        Ok(project)
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::HashMap, str::FromStr};
+
+use async_trait::async_trait;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::{
+    backend::{engine::SDKEngine, events::SDKEvent},
+    errors::sdk::SDKError,
+    resources::tasks::task::TaskStatus,
+};
+
+use super::project::{Project, ProjectStats, ProjectStatus, ProjectVisibility, TaskStatusCount};
+
+#[async_trait]
+pub trait ProjectsExtensionOperations {
+    async fn get_task_counts_by_status(&self, project_id: Uuid) -> Result<HashMap<TaskStatus, i64>, SDKError>;
+    async fn get_project_stats(&self, project_id: Uuid) -> Result<ProjectStats, SDKError>;
+    /// Copies the project and its non-archived tasks as a fresh skeleton: statuses reset to `ToDo`,
+    /// due dates cleared and subtasks re-linked to the copies of their parents.
+    async fn clone_project(&self, source_id: Uuid, new_name: String) -> Result<Project, SDKError>;
+}
+
+#[async_trait]
+impl ProjectsExtensionOperations for SDKEngine {
+    async fn get_task_counts_by_status(&self, project_id: Uuid) -> Result<HashMap<TaskStatus, i64>, SDKError> {
+        let counts_info = sqlx::query!(
+            r#"
+            SELECT status, COUNT(*) AS "count!" FROM tasks
+            WHERE project_id = $1 AND archived_at IS NULL
+            GROUP BY status
+            "#,
+            project_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        let mut counts: HashMap<TaskStatus, i64> = TaskStatus::iter().map(|status| (status, 0)).collect();
+
+        for count_info in counts_info {
+            let status = count_info
+                .status
+                .as_deref()
+                .map(TaskStatus::from_str)
+                .transpose()?
+                .unwrap_or_default();
+
+            *counts.entry(status).or_default() += count_info.count;
+        }
+
+        Ok(counts)
+    }
+
+    async fn get_project_stats(&self, project_id: Uuid) -> Result<ProjectStats, SDKError> {
+        let counts_info = sqlx::query!(
+            r#"
+            SELECT
+                status,
+                COUNT(*) AS "count!",
+                COUNT(*) FILTER (
+                    WHERE due_date < now() AND status IS DISTINCT FROM 'Done' AND status IS DISTINCT FROM 'Canceled'
+                ) AS "overdue!"
+            FROM tasks
+            WHERE project_id = $1 AND archived_at IS NULL
+            GROUP BY status
+            "#,
+            project_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        let members = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM members_by_projects
+            WHERE project_id = $1
+            "#,
+            project_id,
+        )
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await?
+        .count;
+
+        let mut counts: HashMap<TaskStatus, i64> = TaskStatus::iter().map(|status| (status, 0)).collect();
+        let mut overdue_tasks = 0;
+
+        for count_info in counts_info {
+            let status = count_info
+                .status
+                .as_deref()
+                .map(TaskStatus::from_str)
+                .transpose()?
+                .unwrap_or_default();
+
+            *counts.entry(status).or_default() += count_info.count;
+            overdue_tasks += count_info.overdue;
+        }
+
+        Ok(ProjectStats {
+            project_id,
+            total_tasks: counts.values().sum(),
+            tasks_by_status: TaskStatus::iter()
+                .map(|status| TaskStatusCount {
+                    status,
+                    count: counts[&status],
+                })
+                .collect(),
+            members,
+            overdue_tasks,
+        })
+    }
+
+    async fn clone_project(&self, source_id: Uuid, new_name: String) -> Result<Project, SDKError> {
+        let mut tx = self.begin().await?;
+
+        let project_info = sqlx::query!(
+            r#"
+            INSERT INTO projects (name, prefix, owner_id, description, lead_id, start_date, due_date, status, visibility)
+            SELECT $1, prefix, owner_id, description, lead_id, start_date, due_date, $2, visibility
+            FROM projects
+            WHERE id = $3
+            RETURNING *
+            "#,
+            new_name,
+            ProjectStatus::Active.to_string(),
+            source_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(SDKError::or_not_found("project", source_id))?;
+
+        let source_ids: Vec<Uuid> = sqlx::query!(
+            r#"
+            SELECT id FROM tasks
+            WHERE project_id = $1 AND archived_at IS NULL
+            "#,
+            source_id,
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|task| task.id)
+        .collect();
+
+        let clone_ids: Vec<Uuid> = source_ids.iter().map(|_| Uuid::new_v4()).collect();
+
+        // Parents outside the copied set (archived or in another project) leave the copy at the top level.
+        sqlx::query!(
+            r#"
+            INSERT INTO tasks (id, title, description, owner_id, status, priority, project_id, lead_id, parent_id, recurrence, position)
+            SELECT clone.new_id, t.title, t.description, t.owner_id, $3, t.priority, $4, t.lead_id, parent.new_id, t.recurrence, t.position
+            FROM tasks t
+            JOIN UNNEST($1::uuid[], $2::uuid[]) AS clone(old_id, new_id) ON clone.old_id = t.id
+            LEFT JOIN UNNEST($1::uuid[], $2::uuid[]) AS parent(old_id, new_id) ON parent.old_id = t.parent_id
+            "#,
+            &source_ids,
+            &clone_ids,
+            TaskStatus::ToDo.to_string(),
+            project_info.id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let project = Project {
+            id: project_info.id,
+            created_at: project_info.created_at,
+            updated_at: project_info.updated_at,
+            name: project_info.name,
+            prefix: project_info.prefix,
+            owner_id: project_info.owner_id,
+            description: project_info.description,
+            lead_id: project_info.lead_id,
+            start_date: project_info.start_date,
+            due_date: project_info.due_date,
+            status: project_info
+                .status
+                .and_then(|a| ProjectStatus::from_str(&a).ok())
+                .unwrap_or_default(),
+            visibility: project_info
+                .visibility
+                .and_then(|a| ProjectVisibility::from_str(&a).ok())
+                .unwrap_or_default(),
+        };
+
+        self.emit(|| SDKEvent::ProjectCreated(project.clone()));
+
+        Ok(project)
+    }
+}
@@ -27,7 +27,7 @@ impl Loader<Uuid> for ProjectLoader {
             "#,
             &keys
         )
-        .fetch_all(&*self.0.db_pool)
+        .fetch_all(&*self.0.read_pool)
         .await
         .unwrap();
 
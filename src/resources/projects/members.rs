@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{backend::engine::SDKEngine, errors::sdk::SDKError};
+
+use super::project::{ProjectMember, ProjectMemberRole};
+
+#[async_trait]
+pub trait ProjectMemberOperations {
+    /// Adds `member_id` to the project, or changes their role if they already belong to it.
+    async fn add_project_member(
+        &self,
+        project_id: Uuid,
+        member_id: Uuid,
+        role: ProjectMemberRole,
+    ) -> Result<ProjectMember, SDKError>;
+    async fn remove_project_member(&self, project_id: Uuid, member_id: Uuid) -> Result<ProjectMember, SDKError>;
+    async fn get_project_members(&self, project_id: Uuid) -> Result<Vec<ProjectMember>, SDKError>;
+}
+
+#[async_trait]
+impl ProjectMemberOperations for SDKEngine {
+    async fn add_project_member(
+        &self,
+        project_id: Uuid,
+        member_id: Uuid,
+        role: ProjectMemberRole,
+    ) -> Result<ProjectMember, SDKError> {
+        let member_info = sqlx::query!(
+            r#"
+            INSERT INTO members_by_projects (project_id, member_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (member_id, project_id) DO UPDATE SET role = EXCLUDED.role
+            RETURNING *
+            "#,
+            project_id,
+            member_id,
+            role.to_string(),
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(ProjectMember {
+            project_id: member_info.project_id,
+            member_id: member_info.member_id,
+            role: ProjectMemberRole::from_str(&member_info.role).unwrap_or_default(),
+        })
+    }
+
+    async fn remove_project_member(&self, project_id: Uuid, member_id: Uuid) -> Result<ProjectMember, SDKError> {
+        let member_info = sqlx::query!(
+            r#"
+            DELETE FROM members_by_projects
+            WHERE project_id = $1 AND member_id = $2
+            RETURNING *
+            "#,
+            project_id,
+            member_id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("project member", member_id))?;
+
+        Ok(ProjectMember {
+            project_id: member_info.project_id,
+            member_id: member_info.member_id,
+            role: ProjectMemberRole::from_str(&member_info.role).unwrap_or_default(),
+        })
+    }
+
+    async fn get_project_members(&self, project_id: Uuid) -> Result<Vec<ProjectMember>, SDKError> {
+        let members_info = sqlx::query!(
+            r#"
+            SELECT * FROM members_by_projects
+            WHERE project_id = $1
+            "#,
+            project_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Ok(members_info
+            .into_iter()
+            .map(|member_info| ProjectMember {
+                project_id: member_info.project_id,
+                member_id: member_info.member_id,
+                role: ProjectMemberRole::from_str(&member_info.role).unwrap_or_default(),
+            })
+            .collect())
+    }
+}
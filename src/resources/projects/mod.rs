@@ -1,4 +1,7 @@
+pub mod export;
+pub mod extensions;
 pub mod loader;
+pub mod members;
 pub mod operations;
 pub mod project;
 pub mod relations;
@@ -6,11 +6,12 @@ use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use poem_openapi::Object;
 use sqlx::Row;
+use tracing::{instrument, Span};
 use uuid::Uuid;
 
 use crate::{
-    backend::engine::SDKEngine,
-    common::commons::{SortOrder, UpdateListInput},
+    backend::{engine::SDKEngine, events::SDKEvent},
+    common::commons::{clamp_page, like_pattern, FilterOp, SQLArgument, SortOrder, UpdateListInput},
     errors::sdk::SDKError,
 };
 
@@ -20,8 +21,10 @@ use super::project::{Project, ProjectStatus, ProjectVisibility};
 pub trait ProjectCrudOperations {
     async fn create_project(&self, input: CreateProjectInput) -> Result<Project, SDKError>;
     async fn get_project(&self, id: Uuid) -> Result<Project, SDKError>;
+    async fn get_project_by_name(&self, name: String) -> Result<Option<Project>, SDKError>;
     async fn get_projects(&self, input: GetProjectsInput) -> Result<Vec<Project>, SDKError>;
     async fn update_project(&self, id: Uuid, input: UpdateProjectInput) -> Result<Project, SDKError>;
+    async fn update_project_status(&self, id: Uuid, status: ProjectStatus) -> Result<Project, SDKError>;
     async fn delete_project(&self, id: Uuid) -> Result<Project, SDKError>;
 }
 
@@ -97,6 +100,9 @@ pub struct GetProjectsInput {
     pub limit: Option<i32>,
     #[builder(setter(into, strip_option), default = "Some(0)")]
     pub offset: Option<i32>,
+
+    #[builder(setter(strip_option), default)]
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Default, Builder, Object, InputObject)]
@@ -104,20 +110,27 @@ pub struct GetProjectsInput {
 pub struct GetProjectsWhere {
     #[builder(setter(strip_option), default)]
     pub ids: Option<Vec<Uuid>>,
-    #[builder(setter(strip_option), default)]
-    pub name: Option<String>,
-    #[builder(setter(strip_option), default)]
-    pub prefix: Option<String>,
-    #[builder(setter(strip_option), default)]
-    pub owner_id: Option<Uuid>,
-    #[builder(setter(strip_option), default)]
-    pub description: Option<String>,
-    #[builder(setter(strip_option), default)]
-    pub lead_id: Option<Uuid>,
-    #[builder(setter(strip_option), default)]
-    pub start_date: Option<DateTime<Utc>>,
-    #[builder(setter(strip_option), default)]
-    pub due_date: Option<DateTime<Utc>>,
+    #[builder(setter(into, strip_option), default)]
+    pub name: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub prefix: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub owner_id: Option<FilterOp<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub lead_id: Option<FilterOp<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub start_date: Option<FilterOp<DateTime<Utc>>>,
+    #[builder(setter(into, strip_option), default)]
+    pub due_date: Option<FilterOp<DateTime<Utc>>>,
+
+    /// Case-insensitive substring match, `ILIKE '%value%'`; `%` and `_` in the value match literally.
+    #[builder(setter(strip_option), default)]
+    pub name_contains: Option<String>,
+    /// Case-insensitive substring match, like `name_contains`.
+    #[builder(setter(strip_option), default)]
+    pub description_contains: Option<String>,
 
     #[oai(skip)]
     #[builder(setter(strip_option), default)]
@@ -129,67 +142,86 @@ pub struct GetProjectsWhere {
 }
 
 impl GetProjectsWhere {
-    pub fn compile_sql(&self) -> String {
-        let mut where_clause = Vec::new();
+    pub fn compile_sql(&self) -> (String, Vec<SQLArgument>) {
+        let mut args = Vec::new();
+        let where_clause = self.compile_sql_with(&mut args);
+
+        (where_clause, args)
+    }
+
+    fn compile_sql_with(&self, args: &mut Vec<SQLArgument>) -> String {
+        let mut and_clauses = Vec::new();
 
         if let Some(ids) = &self.ids {
-            where_clause.push(format!(
-                "id = ANY(array[{}]::uuid[])",
-                ids.iter()
-                    .map(|x| format!("'{}'", x))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            args.push(SQLArgument::UuidArray(ids.clone()));
+            and_clauses.push(format!("id = ANY(${})", args.len()));
         }
 
         if let Some(name) = &self.name {
-            where_clause.push(format!("name = '{}'", name));
+            and_clauses.push(name.compile_sql("name", args));
         }
         if let Some(prefix) = &self.prefix {
-            where_clause.push(format!("prefix = '{}'", prefix));
+            and_clauses.push(prefix.compile_sql("prefix", args));
         }
         if let Some(owner_id) = &self.owner_id {
-            where_clause.push(format!("owner_id = '{}'", owner_id));
+            and_clauses.push(owner_id.compile_sql("owner_id", args));
         }
         if let Some(description) = &self.description {
-            where_clause.push(format!("description = '{}'", description));
+            and_clauses.push(description.compile_sql("description", args));
         }
         if let Some(lead_id) = &self.lead_id {
-            where_clause.push(format!("lead_id = '{}'", lead_id));
+            and_clauses.push(lead_id.compile_sql("lead_id", args));
         }
         if let Some(start_date) = &self.start_date {
-            where_clause.push(format!("start_date = '{}'", start_date));
+            and_clauses.push(start_date.compile_sql("start_date", args));
         }
         if let Some(due_date) = &self.due_date {
-            where_clause.push(format!("due_date = '{}'", due_date));
+            and_clauses.push(due_date.compile_sql("due_date", args));
         }
-        if let Some(_and) = &self._and {
-            where_clause.push(format!(
-                "({})",
-                _and.iter()
-                    .map(|x| x.compile_sql())
-                    .collect::<Vec<String>>()
-                    .join(" AND ")
-            ));
+
+        if let Some(name_contains) = &self.name_contains {
+            args.push(SQLArgument::Text(like_pattern(name_contains)));
+            and_clauses.push(format!("name ILIKE ${}", args.len()));
         }
-        if let Some(_or) = &self._or {
-            where_clause.push(format!(
-                "({})",
-                _or.iter()
-                    .map(|x| x.compile_sql())
-                    .collect::<Vec<String>>()
-                    .join(" OR ")
-            ));
+        if let Some(description_contains) = &self.description_contains {
+            args.push(SQLArgument::Text(like_pattern(description_contains)));
+            and_clauses.push(format!("description ILIKE ${}", args.len()));
         }
 
-        where_clause.join(" AND ")
+        // an empty nested filter adds no clause, and a group left with none is dropped
+        if let Some(ands) = &self._and {
+            for and in ands {
+                and_clauses.push(and.compile_sql_with(args));
+            }
+        }
+        if let Some(ors) = &self._or {
+            let or_clauses: Vec<String> = ors
+                .iter()
+                .map(|or| or.compile_sql_with(args))
+                .filter(|clause| !clause.is_empty())
+                .collect();
+
+            if !or_clauses.is_empty() {
+                and_clauses.push(format!("({})", or_clauses.join(" OR ")));
+            }
+        }
+
+        and_clauses.retain(|clause| !clause.is_empty());
+
+        let mut where_clause = String::new();
+        if !and_clauses.is_empty() {
+            where_clause.push_str(&format!("({})", and_clauses.join(" AND ")));
+        }
+
+        where_clause
     }
 }
 
 #[async_trait]
 impl ProjectCrudOperations for SDKEngine {
+    #[instrument(skip_all, fields(resource = "project", operation = "create"))]
     async fn create_project(&self, input: CreateProjectInput) -> Result<Project, SDKError> {
-        let mut tx = self.db_pool.as_ref().begin().await?;
+        let mut tx = self.begin().await?;
 
         let project = sqlx::query!(
             r#"
@@ -239,7 +271,7 @@ impl ProjectCrudOperations for SDKEngine {
 
         tx.commit().await?;
 
-        Ok(Project {
+        let project = Project {
             id: project.id,
             created_at: project.created_at,
             updated_at: project.updated_at,
@@ -258,9 +290,14 @@ impl ProjectCrudOperations for SDKEngine {
                 .visibility
                 .and_then(|a| ProjectVisibility::from_str(&a).ok())
                 .unwrap_or_default(),
-        })
+        };
+
+        self.emit(|| SDKEvent::ProjectCreated(project.clone()));
+
+        Ok(project)
     }
 
+    #[instrument(skip_all, fields(resource = "project", operation = "read"))]
     async fn get_project(&self, id: Uuid) -> Result<Project, SDKError> {
         let project_info = sqlx::query!(
             r#"
@@ -268,8 +305,9 @@ impl ProjectCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("project", id))?;
 
         Ok(Project {
             id: project_info.id,
@@ -293,8 +331,51 @@ impl ProjectCrudOperations for SDKEngine {
         })
     }
 
+    /// Exact, case-sensitive match; `AmbiguousMatch` when several projects share the name.
+    #[instrument(skip_all, fields(resource = "project", operation = "read"))]
+    async fn get_project_by_name(&self, name: String) -> Result<Option<Project>, SDKError> {
+        let mut projects_info = sqlx::query!(
+            r#"
+            SELECT * FROM projects WHERE name = $1
+            LIMIT 2
+            "#,
+            name,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        if projects_info.len() > 1 {
+            return Err(SDKError::AmbiguousMatch {
+                resource: "project",
+                value: name,
+            });
+        }
+
+        Ok(projects_info.pop().map(|project_info| Project {
+            id: project_info.id,
+            created_at: project_info.created_at,
+            updated_at: project_info.updated_at,
+            name: project_info.name,
+            prefix: project_info.prefix,
+            owner_id: project_info.owner_id,
+            description: project_info.description,
+            lead_id: project_info.lead_id,
+            start_date: project_info.start_date,
+            due_date: project_info.due_date,
+            status: project_info
+                .status
+                .and_then(|a| ProjectStatus::from_str(&a).ok())
+                .unwrap_or_default(),
+            visibility: project_info
+                .visibility
+                .and_then(|a| ProjectVisibility::from_str(&a).ok())
+                .unwrap_or_default(),
+        }))
+    }
+
+    #[instrument(skip_all, fields(resource = "project", operation = "update"))]
     async fn update_project(&self, id: Uuid, input: UpdateProjectInput) -> Result<Project, SDKError> {
-        let mut tx = self.db_pool.as_ref().begin().await?;
+        let mut tx = self.begin().await?;
 
         let project_final_info = sqlx::query!(
             r#"
@@ -322,7 +403,8 @@ impl ProjectCrudOperations for SDKEngine {
             id,
         )
         .fetch_one(&mut *tx)
-        .await?;
+        .await
+        .map_err(SDKError::or_not_found("project", id))?;
 
         if let Some(members) = input.members {
             for member in members.add {
@@ -386,7 +468,7 @@ impl ProjectCrudOperations for SDKEngine {
 
         tx.commit().await?;
 
-        Ok(Project {
+        let project = Project {
             id: project_final_info.id,
             created_at: project_final_info.created_at,
             updated_at: project_final_info.updated_at,
@@ -405,9 +487,56 @@ impl ProjectCrudOperations for SDKEngine {
                 .visibility
                 .and_then(|a| ProjectVisibility::from_str(&a).ok())
                 .unwrap_or_default(),
-        })
+        };
+
+        self.emit(|| SDKEvent::ProjectUpdated(project.clone()));
+
+        Ok(project)
+    }
+
+    #[instrument(skip_all, fields(resource = "project", operation = "update"))]
+    async fn update_project_status(&self, id: Uuid, status: ProjectStatus) -> Result<Project, SDKError> {
+        let project_info = sqlx::query!(
+            r#"
+            UPDATE projects
+            SET status = $1
+            WHERE id = $2
+            RETURNING *
+            "#,
+            status.to_string(),
+            id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("project", id))?;
+
+        let project = Project {
+            id: project_info.id,
+            created_at: project_info.created_at,
+            updated_at: project_info.updated_at,
+            name: project_info.name,
+            prefix: project_info.prefix,
+            owner_id: project_info.owner_id,
+            description: project_info.description,
+            lead_id: project_info.lead_id,
+            start_date: project_info.start_date,
+            due_date: project_info.due_date,
+            status: project_info
+                .status
+                .and_then(|a| ProjectStatus::from_str(&a).ok())
+                .unwrap_or_default(),
+            visibility: project_info
+                .visibility
+                .and_then(|a| ProjectVisibility::from_str(&a).ok())
+                .unwrap_or_default(),
+        };
+
+        self.emit(|| SDKEvent::ProjectUpdated(project.clone()));
+
+        Ok(project)
     }
 
+    #[instrument(skip_all, fields(resource = "project", operation = "delete"))]
     async fn delete_project(&self, id: Uuid) -> Result<Project, SDKError> {
         let project_info = sqlx::query!(
             r#"
@@ -416,10 +545,11 @@ impl ProjectCrudOperations for SDKEngine {
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("project", id))?;
 
-        Ok(Project {
+        let project = Project {
             id: project_info.id,
             created_at: project_info.created_at,
             updated_at: project_info.updated_at,
@@ -438,14 +568,34 @@ impl ProjectCrudOperations for SDKEngine {
                 .visibility
                 .and_then(|a| ProjectVisibility::from_str(&a).ok())
                 .unwrap_or_default(),
-        })
+        };
+
+        self.emit(|| SDKEvent::ProjectDeleted { id: project.id });
+
+        Ok(project)
     }
 
+    #[instrument(skip_all, fields(resource = "project", operation = "read", rows))]
     async fn get_projects(&self, input: GetProjectsInput) -> Result<Vec<Project>, SDKError> {
         let mut query = "SELECT * FROM projects ".to_string();
+        let mut args = Vec::new();
+        let mut conditions = Vec::new();
 
         if let Some(filter) = input.filter {
-            query.push_str(format!("WHERE {} ", filter.compile_sql()).as_str());
+            let (where_clause, where_args) = filter.compile_sql();
+
+            if !where_clause.is_empty() {
+                conditions.push(format!("({})", where_clause));
+                args = where_args;
+            }
+        }
+
+        if !input.include_archived.unwrap_or_default() {
+            conditions.push(format!("status IS DISTINCT FROM '{}'", ProjectStatus::Archived));
+        }
+
+        if !conditions.is_empty() {
+            query.push_str(format!("WHERE {} ", conditions.join(" AND ")).as_str());
         }
 
         if let Some(sort_by) = input.sort_by {
@@ -456,15 +606,22 @@ impl ProjectCrudOperations for SDKEngine {
             query.push_str(format!("{} ", sort_order).as_str());
         }
 
-        if let Some(limit) = input.limit {
-            query.push_str(format!("LIMIT {} ", limit).as_str());
-        }
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        query.push_str(format!("LIMIT {} ", limit).as_str());
 
         if let Some(offset) = input.offset {
             query.push_str(format!("OFFSET {} ", offset).as_str());
         }
 
-        let projects_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        self.log_sql(&query, &args);
+
+        let projects_info = args
+            .into_iter()
+            .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
+
+        Span::current().record("rows", projects_info.len());
 
         let projects = projects_info
             .iter()
@@ -493,3 +650,62 @@ impl ProjectCrudOperations for SDKEngine {
         Ok(projects)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_nested_filters_add_no_clause() {
+        let filter = GetProjectsWhere {
+            _and: Some(vec![GetProjectsWhere::default()]),
+            _or: Some(vec![GetProjectsWhere::default()]),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.compile_sql().0, "");
+    }
+
+    #[test]
+    fn empty_nested_filters_are_dropped_next_to_others() {
+        let filter = GetProjectsWhere {
+            name: Some(FilterOp::Eq("roadmap".to_string())),
+            _and: Some(vec![GetProjectsWhere::default()]),
+            _or: Some(vec![
+                GetProjectsWhere::default(),
+                GetProjectsWhere {
+                    prefix: Some(FilterOp::Eq("RM".to_string())),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.compile_sql().0, "(name = $1 AND ((prefix = $2)))");
+    }
+
+    #[test]
+    fn or_groups_are_anded_with_the_other_fields() {
+        let owner_id = Uuid::new_v4();
+
+        let filter = GetProjectsWhere {
+            owner_id: Some(FilterOp::Eq(owner_id)),
+            _or: Some(vec![
+                GetProjectsWhere {
+                    name: Some(FilterOp::Eq("roadmap".to_string())),
+                    ..Default::default()
+                },
+                GetProjectsWhere {
+                    prefix: Some(FilterOp::Eq("RM".to_string())),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let (where_clause, args) = filter.compile_sql();
+
+        assert_eq!(where_clause, "(owner_id = $1 AND ((name = $2) OR (prefix = $3)))");
+        assert_eq!(args.len(), 3);
+    }
+}
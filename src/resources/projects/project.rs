@@ -9,7 +9,10 @@ use strum_macros::{Display, EnumString};
 
 use poem_openapi::Enum as OpenApiEnum;
 use serde::{Deserialize, Serialize};
-#[derive(Debug, SimpleObject, Object, Clone, Serialize)]
+
+use crate::resources::tasks::task::TaskStatus;
+
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
 #[graphql(name = "SDKProject")]
 pub struct Project {
     pub id: Uuid,
@@ -35,12 +38,10 @@ pub struct Project {
 
 pub enum ProjectStatus {
     #[default]
-    None,
-    Backlog,
-    ToDo,
-    InProgress,
-    Done,
-    Canceled,
+    Active,
+    Paused,
+    Completed,
+    Archived,
 }
 
 #[derive(
@@ -54,3 +55,40 @@ pub enum ProjectVisibility {
     Public,
     // Shared,
 }
+
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
+#[graphql(name = "SDKProjectMember")]
+pub struct ProjectMember {
+    pub project_id: Uuid,
+    pub member_id: Uuid,
+    pub role: ProjectMemberRole,
+}
+
+#[derive(
+    Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumString, Deserialize, Serialize, Eq, PartialEq,
+)]
+pub enum ProjectMemberRole {
+    Owner,
+    Admin,
+    #[default]
+    Member,
+}
+
+/// Overview numbers for a single project; archived tasks are not counted.
+#[derive(Debug, SimpleObject, Clone, Serialize)]
+#[graphql(name = "SDKProjectStats")]
+pub struct ProjectStats {
+    pub project_id: Uuid,
+    pub total_tasks: i64,
+    pub tasks_by_status: Vec<TaskStatusCount>,
+    pub members: i64,
+    /// Tasks past their due date that are neither done nor canceled.
+    pub overdue_tasks: i64,
+}
+
+#[derive(Debug, SimpleObject, Clone, Serialize)]
+#[graphql(name = "SDKTaskStatusCount")]
+pub struct TaskStatusCount {
+    pub status: TaskStatus,
+    pub count: i64,
+}
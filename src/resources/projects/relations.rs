@@ -10,7 +10,7 @@ use crate::{
         assets::asset::{Asset, AssetKind},
         changes::change::{Change, ChangeOperation, ChangeResourceType},
         members::member::Member,
-        tasks::task::{Task, TaskPriority, TaskStatus},
+        tasks::{operations::TaskRecord, task::Task},
         teams::team::Team,
     },
 };
@@ -49,42 +49,18 @@ impl ProjectRelations for Project {
     }
 
     async fn tasks(&self, loaders: &SDKLoaders) -> Result<Vec<Task>, SDKError> {
-        let tasks = sqlx::query!(
+        let tasks = sqlx::query_as!(
+            TaskRecord,
             r#"
         SELECT * FROM tasks
         WHERE project_id = $1"#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap();
 
-        Ok(tasks
-            .iter()
-            .map(|task| Task {
-                id: task.id,
-                created_at: task.created_at,
-                updated_at: task.updated_at,
-                title: task.title.clone(),
-                description: task.description.clone(),
-                status: task
-                    .status
-                    .clone()
-                    .and_then(|a| TaskStatus::from_str(&a).ok())
-                    .unwrap_or_default(),
-                priority: task
-                    .priority
-                    .clone()
-                    .and_then(|a| TaskPriority::from_str(&a).ok())
-                    .unwrap_or_default(),
-                due_date: task.due_date,
-                project_id: task.project_id,
-                lead_id: task.lead_id,
-                owner_id: task.owner_id,
-                count: task.count,
-                parent_id: task.parent_id,
-            })
-            .collect())
+        tasks.into_iter().map(Task::try_from).collect()
     }
 
     async fn members(&self, loaders: &SDKLoaders) -> Result<Vec<Member>, SDKError> {
@@ -95,7 +71,7 @@ impl ProjectRelations for Project {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -119,7 +95,7 @@ impl ProjectRelations for Project {
         WHERE project_id = $1"#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap();
 
@@ -149,7 +125,7 @@ impl ProjectRelations for Project {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -170,7 +146,7 @@ impl ProjectRelations for Project {
         WHERE resource_id = $1"#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap();
 
@@ -1,176 +1,770 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use async_graphql::InputObject;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use serde::Serialize;
 use sqlx::Row;
+use tracing::{instrument, Span};
 use uuid::Uuid;
 
-use crate::{backend::engine::SDKEngine, errors::sdk::SDKError};
+use crate::{
+    backend::{engine::SDKEngine, events::SDKEvent},
+    common::commons::{clamp_page, FilterOp},
+    errors::sdk::{FieldError, SDKError},
+    resources::{
+        labels::label::Label,
+        members::member::{Member, MemberRole},
+    },
+};
 
 use super::{
-    operations::{CreateTaskInput, TaskCrudOperations},
-    task::{Task, TaskPriority, TaskStatus},
+    operations::{
+        compile_tasks_query, insert_task, task_from_row, validate_due_date, validate_titles, CreateTaskInput,
+        GetTasksInput, GetTasksWhere, TaskCrudOperations, TaskRecord,
+    },
+    recurrence::Recurrence,
+    task::{Task, TaskNode, TaskStatus},
 };
 
 #[derive(Default, Builder, InputObject, Clone, Serialize)]
 #[builder(pattern = "owned")]
 pub struct CreateTasksInput {
     pub tasks: Vec<CreateTaskInput>,
+
+    /// Run the whole batch and return the tasks it would create, then roll back. Tasks without an `id` get one
+    /// assigned up front; submitting the same tasks with those ids commits them exactly as previewed.
+    #[builder(setter(strip_option), default)]
+    pub dry_run: Option<bool>,
 }
 
 #[async_trait]
 pub trait TasksExtensionOperations {
     async fn create_tasks(&self, input: CreateTasksInput) -> Result<Vec<Task>, SDKError>;
+    async fn materialize_recurring_tasks(&self, until: DateTime<Utc>) -> Result<Vec<Task>, SDKError>;
+    async fn reorder_task(&self, task_id: Uuid, new_position: i32) -> Result<Task, SDKError>;
+    async fn get_task_tree(&self, root: Uuid) -> Result<TaskNode, SDKError>;
+    /// RFC 4180 CSV of the matching tasks: a header row, then `id,title,status,priority,owner_id,due_date`.
+    /// Reads one page like `get_tasks`, so at most `max_page_size` rows; page with `offset` for more.
+    async fn export_tasks_csv(&self, input: GetTasksInput) -> Result<String, SDKError>;
+    async fn get_project_tasks(&self, project_id: Uuid, input: Option<GetTasksInput>) -> Result<Vec<Task>, SDKError>;
+    async fn get_project_tasks_with_owners(
+        &self,
+        project_id: Uuid,
+        input: Option<GetTasksInput>,
+    ) -> Result<Vec<(Task, Member)>, SDKError>;
+}
+
+#[async_trait]
+pub trait TaskLabelOperations {
+    async fn add_label_to_task(&self, task_id: Uuid, label_id: Uuid) -> Result<(), SDKError>;
+    async fn remove_label_from_task(&self, task_id: Uuid, label_id: Uuid) -> Result<(), SDKError>;
+    async fn get_task_labels(&self, task_id: Uuid) -> Result<Vec<Label>, SDKError>;
+}
+
+#[async_trait]
+pub trait TaskAssigneeOperations {
+    async fn assign_task(&self, task_id: Uuid, member_id: Uuid) -> Result<(), SDKError>;
+    async fn unassign_task(&self, task_id: Uuid, member_id: Uuid) -> Result<(), SDKError>;
+    async fn get_task_assignees(&self, task_id: Uuid) -> Result<Vec<Member>, SDKError>;
+}
+
+#[async_trait]
+pub trait TaskDependencyOperations {
+    async fn add_dependency(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), SDKError>;
+    async fn remove_dependency(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), SDKError>;
+    /// Tasks that must be finished before `task_id` can move forward.
+    async fn get_blockers(&self, task_id: Uuid) -> Result<Vec<Task>, SDKError>;
+    /// Tasks that are waiting on `task_id`.
+    async fn get_blocked_by(&self, task_id: Uuid) -> Result<Vec<Task>, SDKError>;
+}
+
+/// Subtasks without an explicit owner take their parent's.
+fn inherit_owner(task: &mut CreateTaskInput) {
+    let owner_id = task.owner_id;
+
+    for subtask in task.subtasks.iter_mut().flatten() {
+        if subtask.owner_id.is_nil() {
+            subtask.owner_id = owner_id;
+        }
+
+        inherit_owner(subtask);
+    }
+}
+
+/// `input` with `project_id` ANDed onto its filter; `None` reads like `get_tasks(None)`, one page of `max_page_size`.
+fn in_project(project_id: Uuid, input: Option<GetTasksInput>) -> GetTasksInput {
+    let input = input.unwrap_or_default();
+
+    let filter = GetTasksWhere {
+        project_id: Some(FilterOp::Eq(project_id)),
+        _and: input.filter.map(|filter| vec![filter]),
+        ..Default::default()
+    };
+
+    GetTasksInput {
+        filter: Some(filter),
+        ..input
+    }
+}
+
+fn assign_ids(task: &mut CreateTaskInput) {
+    task.id.get_or_insert_with(Uuid::new_v4);
+
+    task.subtasks.iter_mut().flatten().for_each(assign_ids);
+}
+
+/// Checks the whole tree before anything is written, collecting every problem under the batch `index`.
+fn validate_task_tree(task: &CreateTaskInput, index: usize, path: &str, errors: &mut Vec<FieldError>) {
+    if let Some(recurrence) = &task.recurrence {
+        if let Err(err) = Recurrence::from_str(recurrence) {
+            errors.push(FieldError::new(index, format!("{path}recurrence"), err));
+        }
+    }
+
+    if let Err(err) = validate_due_date(task.due_date, task.allow_past_due) {
+        errors.push(FieldError::new(index, format!("{path}due_date"), err));
+    }
+
+    for (i, subtask) in task.subtasks.iter().flatten().enumerate() {
+        validate_task_tree(subtask, index, &format!("{path}subtasks[{i}]."), errors);
+    }
+}
+
+const TASKS_CSV_HEADER: [&str; 6] = ["id", "title", "status", "priority", "owner_id", "due_date"];
+
+/// Quotes the field when it holds a comma, quote or line break, doubling any quotes inside.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn build_task_tree(task: Task, children_by_parent: &mut HashMap<Uuid, Vec<Task>>) -> TaskNode {
+    let children = children_by_parent
+        .remove(&task.id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| build_task_tree(child, children_by_parent))
+        .collect();
+
+    TaskNode { task, children }
 }
 
 #[async_trait]
 impl TasksExtensionOperations for SDKEngine {
+    #[instrument(
+        skip_all,
+        fields(resource = "task", operation = "create", rows, invalid, failed_title)
+    )]
     async fn create_tasks(&self, input: CreateTasksInput) -> Result<Vec<Task>, SDKError> {
-        let mut tx = self.db_pool.begin().await?;
-        // let saved_input = input.clone();
-
-        let values = input
-            .tasks
-            .iter()
-            .map(|task| {
-                format!(
-                    "('{}', '{}', {}, '{}', '{}', {}, {}, {}, {})",
-                    task.title,
-                    task.owner_id,
-                    task.description
-                        .clone()
-                        .map(|d| format!("'{}'", d))
-                        .unwrap_or("null".to_string()),
-                    task.status.unwrap_or_default(),
-                    task.priority.unwrap_or_default(),
-                    task.due_date
-                        .map(|dd| format!("'{}'", dd))
-                        .unwrap_or("null".to_string()),
-                    task.project_id
-                        .map(|p| format!("'{}'", p))
-                        .unwrap_or("null".to_string()),
-                    task.lead_id.map(|l| format!("'{}'", l)).unwrap_or("null".to_string()),
-                    task.parent_id.map(|p| format!("'{}'", p)).unwrap_or("null".to_string()),
-                )
-            })
-            .collect::<Vec<String>>();
+        let mut inputs = input.tasks;
+        let dry_run = input.dry_run.unwrap_or_default();
 
-        let query = format!(
-            "INSERT INTO tasks (title, owner_id, description, status, priority, due_date, project_id, lead_id, parent_id) VALUES {} RETURNING *",
-            values.join(", ")
-        );
+        let mut errors = Vec::new();
 
-        let tasks = sqlx::query(query.as_str()).fetch_all(&mut *tx).await?;
+        for (index, task) in inputs.iter_mut().enumerate() {
+            inherit_owner(task);
 
-        for (i, input_task) in input.tasks.iter().enumerate() {
-            let task = &tasks[i];
+            if dry_run {
+                assign_ids(task);
+            }
 
-            let task_id = task.get::<Uuid, _>("id");
-            let task_owner_id = task.get::<Uuid, _>("owner_id");
+            validate_titles(task, index, "", self.config.max_title_length, &mut errors);
+            validate_task_tree(task, index, "", &mut errors);
+        }
 
-            if let Some(labels) = input_task.labels.clone() {
-                for label in labels {
-                    sqlx::query!(
-                        r#"
-                    INSERT INTO labels_by_tasks (task_id, label_id)
-                    VALUES ($1, $2)
-                    "#,
-                        task_id,
-                        label,
-                    )
-                    .execute(&mut *tx)
-                    .await?;
-                }
-            }
+        if !errors.is_empty() {
+            Span::current().record("invalid", errors.len());
+            return Err(SDKError::Validation(errors));
+        }
+
+        let mut tx = self.begin().await?;
+        let mut tasks = Vec::with_capacity(inputs.len());
+        let mut created = Vec::with_capacity(inputs.len());
 
-            if let Some(assignees) = input_task.assignees.clone() {
-                for assignee in assignees {
-                    sqlx::query!(
-                        r#"
-                        INSERT INTO tasks_by_assignees (task_id, assignee_id)
-                        VALUES ($1, $2)
-                        "#,
-                        task_id,
-                        assignee,
-                    )
-                    .execute(&mut *tx)
-                    .await?;
+        // every task and its subtasks go through the same transaction, so any failure rolls back the whole batch
+        for task in inputs {
+            let title = task.title.clone();
+
+            match insert_task(&mut tx, task).await {
+                Ok((task, inserted)) => {
+                    tasks.push(task);
+                    created.push(inserted);
+                }
+                Err(err) => {
+                    Span::current().record("failed_title", title);
+                    return Err(err);
                 }
             }
+        }
 
-            if let Some(subtasks) = input_task.subtasks.clone() {
-                for mut subtask in subtasks {
-                    subtask.owner_id = task_owner_id;
+        if dry_run {
+            tx.rollback().await?;
 
-                    if subtask.parent_id.is_none() {
-                        subtask.parent_id = Some(task_id);
-                    }
+            return Ok(tasks);
+        }
 
-                    self.create_task(subtask).await?;
-                }
+        tx.commit().await?;
+
+        Span::current().record("rows", tasks.len());
+
+        // tasks replayed by their idempotency key were announced by the create that inserted them
+        for (task, _) in tasks.iter().zip(&created).filter(|(_, created)| **created) {
+            self.emit(|| SDKEvent::TaskCreated(task.clone()));
+        }
+
+        Ok(tasks)
+    }
+
+    async fn materialize_recurring_tasks(&self, until: DateTime<Utc>) -> Result<Vec<Task>, SDKError> {
+        let mut tx = self.begin().await?;
+        let mut tasks = Vec::new();
+
+        let templates = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT * FROM tasks
+            WHERE recurrence IS NOT NULL
+                AND due_date IS NOT NULL
+                AND recurrence_source_id IS NULL
+                AND archived_at IS NULL
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for template in templates {
+            let (Some(recurrence), Some(anchor)) = (template.recurrence, template.due_date) else {
+                continue;
+            };
+
+            let recurrence = Recurrence::from_str(&recurrence)?;
+
+            for due_date in recurrence.occurrences(anchor, until) {
+                // an occurrence created earlier, or by a concurrent run, is skipped by the unique index
+                let Some(task_info) = sqlx::query_as!(
+                    TaskRecord,
+                    r#"
+                    INSERT INTO tasks (title, description, owner_id, status, priority, due_date, project_id, lead_id, parent_id, recurrence_source_id)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    ON CONFLICT (recurrence_source_id, due_date) DO NOTHING
+                    RETURNING *
+                    "#,
+                    template.title,
+                    template.description,
+                    template.owner_id,
+                    TaskStatus::default().to_string(),
+                    template.priority,
+                    due_date,
+                    template.project_id,
+                    template.lead_id,
+                    template.parent_id,
+                    template.id,
+                )
+                .fetch_optional(&mut *tx)
+                .await?
+                else {
+                    continue;
+                };
+
+                tasks.push(Task::try_from(task_info)?);
             }
         }
 
         tx.commit().await?;
 
-        let tasks: Vec<Task> = tasks
-            .iter()
-            .map(|task_info| Task {
-                id: task_info.get("id"),
-                created_at: task_info.get("created_at"),
-                updated_at: task_info.get("updated_at"),
-                title: task_info.get("title"),
-                description: task_info.get("description"),
-                status: task_info
-                    .get::<'_, Option<String>, _>("status")
-                    .and_then(|a| TaskStatus::from_str(&a).ok())
-                    .unwrap_or_default(),
-                priority: task_info
-                    .get::<'_, Option<String>, _>("priority")
-                    .and_then(|a| TaskPriority::from_str(&a).ok())
+        for task in &tasks {
+            self.emit(|| SDKEvent::TaskCreated(task.clone()));
+        }
+
+        Ok(tasks)
+    }
+
+    async fn reorder_task(&self, task_id: Uuid, new_position: i32) -> Result<Task, SDKError> {
+        let mut tx = self.begin().await?;
+
+        let task = sqlx::query!(
+            r#"
+            SELECT project_id FROM tasks WHERE id = $1 FOR UPDATE
+            "#,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(SDKError::or_not_found("task", task_id))?;
+
+        // lock the whole column so concurrent reorders can't interleave their shifts
+        let siblings = sqlx::query!(
+            r#"
+            SELECT id FROM tasks WHERE project_id IS NOT DISTINCT FROM $1 FOR UPDATE
+            "#,
+            task.project_id,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // deletes and moves leave gaps, so renumber the column densely before treating positions as indexes
+        sqlx::query!(
+            r#"
+            UPDATE tasks
+            SET position = ordered.position
+            FROM (
+                SELECT id, row_number() OVER (ORDER BY position, created_at, id)::int - 1 AS position
+                FROM tasks
+                WHERE project_id IS NOT DISTINCT FROM $1
+            ) AS ordered
+            WHERE tasks.id = ordered.id AND tasks.position <> ordered.position
+            "#,
+            task.project_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let task = sqlx::query!(
+            r#"
+            SELECT project_id, position FROM tasks WHERE id = $1
+            "#,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let new_position = new_position.clamp(0, siblings.len() as i32 - 1);
+
+        if new_position < task.position {
+            sqlx::query!(
+                r#"
+                UPDATE tasks
+                SET position = position + 1
+                WHERE project_id IS NOT DISTINCT FROM $1 AND position >= $2 AND position < $3
+                "#,
+                task.project_id,
+                new_position,
+                task.position,
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else if new_position > task.position {
+            sqlx::query!(
+                r#"
+                UPDATE tasks
+                SET position = position - 1
+                WHERE project_id IS NOT DISTINCT FROM $1 AND position > $2 AND position <= $3
+                "#,
+                task.project_id,
+                task.position,
+                new_position,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let task_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            UPDATE tasks
+            SET position = $1
+            WHERE id = $2
+            RETURNING *
+            "#,
+            new_position,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let task = Task::try_from(task_info)?;
+
+        self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+
+        Ok(task)
+    }
+
+    async fn get_task_tree(&self, root: Uuid) -> Result<TaskNode, SDKError> {
+        // `path` tracks the ancestors of each row, so a parent loop stops the recursion instead of spinning forever
+        let tasks_info = sqlx::query(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT tasks.*, ARRAY[tasks.id] AS path, false AS is_cycle
+                FROM tasks
+                WHERE id = $1
+                UNION ALL
+                SELECT tasks.*, tree.path || tasks.id, tasks.id = ANY(tree.path)
+                FROM tasks
+                JOIN tree ON tasks.parent_id = tree.id
+                WHERE NOT tree.is_cycle
+            )
+            SELECT * FROM tree
+            ORDER BY position
+            "#,
+        )
+        .bind(root)
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        if tasks_info.iter().any(|task_info| task_info.get::<bool, _>("is_cycle")) {
+            return Err(SDKError::DependencyCycle);
+        }
+
+        let mut root_task = None;
+        let mut children_by_parent: HashMap<Uuid, Vec<Task>> = HashMap::new();
+
+        for task_info in tasks_info {
+            let task = task_from_row(&task_info)?;
+
+            match task.parent_id {
+                Some(parent_id) if task.id != root => children_by_parent.entry(parent_id).or_default().push(task),
+                _ => root_task = Some(task),
+            }
+        }
+
+        let root_task = root_task.ok_or(SDKError::NotFound {
+            resource: "task",
+            id: root,
+        })?;
+
+        Ok(build_task_tree(root_task, &mut children_by_parent))
+    }
+
+    async fn export_tasks_csv(&self, input: GetTasksInput) -> Result<String, SDKError> {
+        let tasks = self.get_tasks(Some(input)).await?;
+
+        let mut csv = TASKS_CSV_HEADER.join(",");
+        csv.push_str("\r\n");
+
+        for task in tasks {
+            let row = [
+                task.id.to_string(),
+                task.title,
+                task.status.to_string(),
+                task.priority.to_string(),
+                task.owner_id.to_string(),
+                task.due_date.map(|due_date| due_date.to_rfc3339()).unwrap_or_default(),
+            ];
+
+            csv.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+            csv.push_str("\r\n");
+        }
+
+        Ok(csv)
+    }
+
+    /// `get_tasks` narrowed to `project_id`, on top of whatever filter `input` has.
+    async fn get_project_tasks(&self, project_id: Uuid, input: Option<GetTasksInput>) -> Result<Vec<Task>, SDKError> {
+        self.get_tasks(Some(in_project(project_id, input))).await
+    }
+
+    /// Like `get_project_tasks`, with each task's owner loaded by the same query. Tasks whose owner no
+    /// longer exists are left out. Pages by offset only.
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_project_tasks_with_owners(
+        &self,
+        project_id: Uuid,
+        input: Option<GetTasksInput>,
+    ) -> Result<Vec<(Task, Member)>, SDKError> {
+        let input = in_project(project_id, input);
+
+        if input.cursor.is_some() {
+            return Err(SDKError::InvalidPagination(
+                "get_project_tasks_with_owners pages by offset, not by cursor".to_string(),
+            ));
+        }
+
+        let input = GetTasksInput {
+            limit: Some(clamp_page(input.limit, input.offset, self.config.max_page_size)?),
+            ..input
+        };
+
+        // the owner columns are prefixed so the filter and sort columns stay unambiguous
+        let (query, args) = compile_tasks_query(
+            "SELECT * FROM tasks
+            JOIN LATERAL (
+                SELECT id AS owner__id, created_at AS owner__created_at, updated_at AS owner__updated_at,
+                    name AS owner__name, email AS owner__email, role AS owner__role, github_id AS owner__github_id,
+                    google_id AS owner__google_id, photo_url AS owner__photo_url, password_hash AS owner__password_hash
+                FROM members WHERE members.id = tasks.owner_id
+            ) owner ON TRUE ",
+            Some(input),
+        )?;
+
+        self.log_sql(&query, &args);
+
+        let rows = args
+            .into_iter()
+            .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
+
+        Span::current().record("rows", rows.len());
+
+        rows.iter()
+            .map(|row| {
+                let owner = Member {
+                    id: row.get("owner__id"),
+                    created_at: row.get("owner__created_at"),
+                    updated_at: row.get("owner__updated_at"),
+                    name: row.get("owner__name"),
+                    email: row.get("owner__email"),
+                    role: row
+                        .get::<'_, Option<String>, _>("owner__role")
+                        .and_then(|a| MemberRole::from_str(&a).ok())
+                        .unwrap_or_default(),
+                    github_id: row.get("owner__github_id"),
+                    google_id: row.get("owner__google_id"),
+                    photo_url: row.get("owner__photo_url"),
+                    password_hash: row.get("owner__password_hash"),
+                };
+
+                Ok((task_from_row(row)?, owner))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TaskLabelOperations for SDKEngine {
+    async fn add_label_to_task(&self, task_id: Uuid, label_id: Uuid) -> Result<(), SDKError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO labels_by_tasks (task_id, label_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            task_id,
+            label_id,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_label_from_task(&self, task_id: Uuid, label_id: Uuid) -> Result<(), SDKError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM labels_by_tasks WHERE task_id = $1 AND label_id = $2
+            "#,
+            task_id,
+            label_id,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_task_labels(&self, task_id: Uuid) -> Result<Vec<Label>, SDKError> {
+        let labels_info = sqlx::query!(
+            r#"
+            SELECT labels.* FROM labels
+            JOIN labels_by_tasks ON labels_by_tasks.label_id = labels.id
+            WHERE labels_by_tasks.task_id = $1
+            ORDER BY labels.name
+            "#,
+            task_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Ok(labels_info
+            .into_iter()
+            .map(|label_info| Label {
+                id: label_info.id,
+                created_at: label_info.created_at,
+                updated_at: label_info.updated_at,
+                name: label_info.name,
+                owner_id: label_info.owner_id,
+                description: label_info.description,
+                color: label_info.color,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TaskAssigneeOperations for SDKEngine {
+    async fn assign_task(&self, task_id: Uuid, member_id: Uuid) -> Result<(), SDKError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO tasks_by_assignees (task_id, assignee_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            task_id,
+            member_id,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn unassign_task(&self, task_id: Uuid, member_id: Uuid) -> Result<(), SDKError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM tasks_by_assignees WHERE task_id = $1 AND assignee_id = $2
+            "#,
+            task_id,
+            member_id,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_task_assignees(&self, task_id: Uuid) -> Result<Vec<Member>, SDKError> {
+        let members_info = sqlx::query!(
+            r#"
+            SELECT members.* FROM members
+            JOIN tasks_by_assignees ON tasks_by_assignees.assignee_id = members.id
+            WHERE tasks_by_assignees.task_id = $1
+            ORDER BY members.name
+            "#,
+            task_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Ok(members_info
+            .into_iter()
+            .map(|member_info| Member {
+                id: member_info.id,
+                created_at: member_info.created_at,
+                updated_at: member_info.updated_at,
+                name: member_info.name,
+                email: member_info.email,
+                role: member_info
+                    .role
+                    .and_then(|a| MemberRole::from_str(&a).ok())
                     .unwrap_or_default(),
-                due_date: task_info.get("due_date"),
-                project_id: task_info.get("project_id"),
-                lead_id: task_info.get("lead_id"),
-                owner_id: task_info.get("owner_id"),
-                count: task_info.get("count"),
-                parent_id: task_info.get("parent_id"),
+                github_id: member_info.github_id,
+                google_id: member_info.google_id,
+                photo_url: member_info.photo_url,
+                password_hash: member_info.password_hash,
             })
-            .collect();
-
-        // if self.config.with_changes_registration {
-        //     let tasks = tasks.clone();
-        //     let engine = self.clone();
-
-        //     task::spawn(async move {
-        //         for task in tasks {
-        //             let change = engine
-        //                 .create_change(
-        //                     CreateChangeInputBuilder::default()
-        //                         .owner_id(task.owner_id)
-        //                         .resource_id(task.id)
-        //                         .operation(ChangeOperation::Create)
-        //                         .resource_type(ChangeResourceType::Task)
-        //                         .diff_json(
-        //                             serde_json::to_string(&json!({
-        //                                 "input": saved_input,
-        //                                 "result": task,
-        //                             }))
-        //                             .unwrap(),
-        //                         )
-        //                         .build()
-        //                         .unwrap(),
-        //                 )
-        //                 .await
-        //                 .unwrap();
-
-        //             println!("change registered 2: {} | {}", change.operation, change.resource_type);
-        //         }
-        //     });
-        // }
+            .collect())
+    }
+}
 
-        Ok(tasks)
+#[async_trait]
+impl TaskDependencyOperations for SDKEngine {
+    async fn add_dependency(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), SDKError> {
+        if blocker_id == blocked_id {
+            return Err(SDKError::DependencyCycle);
+        }
+
+        let mut tx = self.begin().await?;
+
+        // two edges that only form a cycle together must not both pass the check, so dependency writes take turns
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtext('task_dependencies'))")
+            .execute(&mut *tx)
+            .await?;
+
+        // the new edge closes a cycle if the blocker is already reachable from the blocked task
+        let creates_cycle = sqlx::query!(
+            r#"
+            WITH RECURSIVE reachable (task_id) AS (
+                SELECT blocked_id FROM task_dependencies WHERE blocker_id = $1
+                UNION
+                SELECT task_dependencies.blocked_id FROM task_dependencies
+                JOIN reachable ON task_dependencies.blocker_id = reachable.task_id
+            )
+            SELECT EXISTS (SELECT 1 FROM reachable WHERE task_id = $2) AS "creates_cycle!"
+            "#,
+            blocked_id,
+            blocker_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .creates_cycle;
+
+        if creates_cycle {
+            return Err(SDKError::DependencyCycle);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO task_dependencies (blocker_id, blocked_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            blocker_id,
+            blocked_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn remove_dependency(&self, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), SDKError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM task_dependencies WHERE blocker_id = $1 AND blocked_id = $2
+            "#,
+            blocker_id,
+            blocked_id,
+        )
+        .execute(&mut *self.acquire().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_blockers(&self, task_id: Uuid) -> Result<Vec<Task>, SDKError> {
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT tasks.* FROM tasks
+            JOIN task_dependencies ON task_dependencies.blocker_id = tasks.id
+            WHERE task_dependencies.blocked_id = $1
+            ORDER BY task_dependencies.created_at
+            "#,
+            task_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        tasks_info.into_iter().map(Task::try_from).collect()
+    }
+
+    async fn get_blocked_by(&self, task_id: Uuid) -> Result<Vec<Task>, SDKError> {
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT tasks.* FROM tasks
+            JOIN task_dependencies ON task_dependencies.blocked_id = tasks.id
+            WHERE task_dependencies.blocker_id = $1
+            ORDER BY task_dependencies.created_at
+            "#,
+            task_id,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        tasks_info.into_iter().map(Task::try_from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("docs, api"), "\"docs, api\"");
+        assert_eq!(csv_field("first\nsecond"), "\"first\nsecond\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field(""), "");
     }
 }
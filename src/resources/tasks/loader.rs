@@ -1,12 +1,12 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 use async_graphql::dataloader::Loader;
 
 use uuid::Uuid;
 
-use crate::backend::engine::SDKEngine;
+use crate::{backend::engine::SDKEngine, errors::sdk::SDKError};
 
-use super::task::{Task, TaskPriority, TaskStatus};
+use super::{operations::TaskCrudOperations, task::Task};
 
 // #[derive(Clone)]
 pub struct TaskLoader(Arc<SDKEngine>);
@@ -19,53 +19,11 @@ impl TaskLoader {
 
 impl Loader<Uuid> for TaskLoader {
     type Value = Task;
-    type Error = Arc<sqlx::Error>;
+    type Error = Arc<SDKError>;
 
     async fn load(&self, keys: &'_ [Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
-        let tasks = sqlx::query!(
-            r#"
-            SELECT * FROM tasks WHERE id  = ANY($1)
-            "#,
-            &keys
-        )
-        .fetch_all(&*self.0.db_pool)
-        .await
-        .unwrap();
+        let tasks = self.0.get_tasks_by_ids(keys.to_vec()).await.map_err(Arc::new)?;
 
-        //iterate to get the hashmap
-        let tasks_map: HashMap<Uuid, Task> = tasks
-            .iter()
-            .map(|task| {
-                (
-                    task.id,
-                    Task {
-                        id: task.id,
-                        created_at: task.created_at,
-                        updated_at: task.updated_at,
-                        title: task.title.clone(),
-                        description: task.description.clone(),
-                        status: task
-                            .status
-                            .clone()
-                            .and_then(|a| TaskStatus::from_str(&a).ok())
-                            .unwrap_or_default(),
-                        priority: task
-                            .priority
-                            .clone()
-                            .and_then(|a| TaskPriority::from_str(&a).ok())
-                            .unwrap_or_default(),
-                        due_date: task.due_date,
-                        project_id: task.project_id,
-                        lead_id: task.lead_id,
-                        owner_id: task.owner_id,
-                        count: task.count,
-                        parent_id: task.parent_id,
-                    },
-                )
-            })
-            .collect();
-
-        //println!("{:?}", tasks);
-        Ok(tasks_map)
+        Ok(tasks.into_iter().map(|task| (task.id, task)).collect())
     }
 }
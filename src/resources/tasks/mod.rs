@@ -1,5 +1,6 @@
 pub mod extensions;
 pub mod loader;
 pub mod operations;
+pub mod recurrence;
 pub mod relations;
 pub mod task;
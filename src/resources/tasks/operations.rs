@@ -1,22 +1,29 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
-use async_graphql::InputObject;
+use async_graphql::{Enum, InputObject};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
 use derive_builder::Builder;
-use poem_openapi::Object;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use poem_openapi::{Enum as OpenApiEnum, Object};
 use serde::Serialize;
+use strum_macros::Display;
 // use serde_json::json;
-use sqlx::Row;
+use sqlx::{postgres::PgRow, Connection, PgConnection, PgExecutor, Row};
 // use tokio::task;
+use tracing::{instrument, Span};
 use uuid::Uuid;
 
-use crate::backend::engine::SDKEngine;
-use crate::common::commons::{SortOrder, UpdateListInput};
-use crate::errors::sdk::SDKError;
+use crate::backend::{engine::SDKEngine, events::SDKEvent};
+use crate::common::commons::{
+    clamp_page, Cursor, CursorPage, FilterOp, Page, RawFilter, SQLArgument, SortOrder, UpdateListInput,
+};
+use crate::errors::sdk::{FieldError, SDKError};
 // use crate::resources::changes::change::{ChangeOperation, ChangeResourceType};
 // use crate::resources::changes::operations::{ChangeCrudOperations, CreateChangeInputBuilder};
+use crate::resources::members::extensions::find_or_create_member_by_email;
+use crate::resources::tasks::recurrence::Recurrence;
 use crate::resources::tasks::task::{Task, TaskPriority, TaskStatus};
 
 #[async_trait]
@@ -24,10 +31,54 @@ pub trait TaskCrudOperations {
     async fn create_task(&self, input: CreateTaskInput) -> Result<Task, SDKError>;
     async fn get_task(&self, id: Uuid) -> Result<Task, SDKError>;
     async fn get_tasks(&self, input: Option<GetTasksInput>) -> Result<Vec<Task>, SDKError>;
+    async fn get_tasks_by_ids(&self, ids: Vec<Uuid>) -> Result<Vec<Task>, SDKError>;
+    async fn get_tasks_by_cursor(&self, input: GetTasksInput) -> Result<CursorPage<Task>, SDKError>;
+    async fn get_tasks_page(&self, input: GetTasksInput, with_total: bool) -> Result<Page<Task>, SDKError>;
+    fn stream_tasks(&self, filter: Option<GetTasksWhere>) -> BoxStream<'_, Result<Task, SDKError>>;
+    async fn get_tasks_due_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        owner_id: Option<Uuid>,
+    ) -> Result<Vec<Task>, SDKError>;
+    /// Unarchived tasks due before `now` that are neither done nor canceled, most overdue first.
+    async fn get_overdue_tasks(&self, owner_id: Option<Uuid>, now: DateTime<Utc>) -> Result<Vec<Task>, SDKError>;
     async fn update_task(&self, id: Uuid, input: UpdateTaskInput) -> Result<Task, SDKError>;
+    /// `actor_id` is recorded as `updated_by` on every task, as `UpdateTaskInput::actor_id` is.
+    async fn update_tasks_status(
+        &self,
+        ids: Vec<Uuid>,
+        status: TaskStatus,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<Task>, SDKError>;
+    async fn move_task_to_project(&self, task_id: Uuid, project_id: Option<Uuid>) -> Result<Task, SDKError>;
     async fn delete_task(&self, id: Uuid) -> Result<Task, SDKError>;
+    async fn archive_task(&self, id: Uuid) -> Result<Task, SDKError>;
+    async fn unarchive_task(&self, id: Uuid) -> Result<Task, SDKError>;
 }
 
+/// Task writes that run on a caller-provided connection, e.g. inside [`SDKEngine::transaction`].
+#[async_trait]
+pub trait TaskTransactionOperations {
+    async fn create_task(&mut self, input: CreateTaskInput) -> Result<Task, SDKError>;
+    async fn delete_task(&mut self, id: Uuid) -> Result<Task, SDKError>;
+}
+
+/// Tasks fetched per round trip by [`TaskCrudOperations::stream_tasks`].
+pub const TASK_STREAM_PAGE_SIZE: i32 = 500;
+
+pub const TASK_SORT_FIELDS: [&str; 9] = [
+    "title",
+    "status",
+    "priority",
+    "due_date",
+    "created_at",
+    "updated_at",
+    "count",
+    "position",
+    "archived_at",
+];
+
 #[derive(Default, Builder, Object, InputObject)]
 #[builder(pattern = "owned")]
 pub struct GetTasksInput {
@@ -43,6 +94,13 @@ pub struct GetTasksInput {
     pub limit: Option<i32>,
     #[builder(setter(into, strip_option), default = "Some(0)")]
     pub offset: Option<i32>,
+
+    #[builder(setter(strip_option), default)]
+    pub include_archived: Option<bool>,
+
+    /// Opaque `next_cursor` of a previous page; pages by `(created_at, id)` instead of `offset`.
+    #[builder(setter(into, strip_option), default)]
+    pub cursor: Option<String>,
 }
 
 #[derive(Clone, Default, Builder, Object, InputObject, Serialize)]
@@ -50,8 +108,22 @@ pub struct GetTasksInput {
 pub struct CreateTaskInput {
     pub title: String,
 
+    /// Id for the new task, generated by the database when `None`.
+    #[builder(setter(strip_option), default)]
+    pub id: Option<Uuid>,
+
+    /// Ignored when `owner_email` is given.
     #[graphql(skip)]
+    #[builder(default)]
     pub owner_id: Uuid,
+    /// Owner looked up by email, for callers that don't know member ids; see `create_owner_if_missing`.
+    #[graphql(skip)]
+    #[builder(setter(into, strip_option), default)]
+    pub owner_email: Option<String>,
+    /// Create a member for an unknown `owner_email` instead of failing with `SDKError::MemberNotFound`.
+    #[graphql(skip)]
+    #[builder(setter(strip_option), default)]
+    pub create_owner_if_missing: Option<bool>,
 
     #[builder(setter(strip_option), default)]
     pub status: Option<TaskStatus>,
@@ -60,14 +132,20 @@ pub struct CreateTaskInput {
 
     #[builder(setter(strip_option), default)]
     pub description: Option<String>,
+    /// Given in any offset, stored as UTC.
     #[builder(setter(strip_option), default)]
     pub due_date: Option<DateTime<Utc>>,
+    /// Accept a `due_date` earlier than now, which is otherwise rejected with `SDKError::DueDateInPast`.
+    #[builder(setter(strip_option), default)]
+    pub allow_past_due: Option<bool>,
     #[builder(setter(strip_option), default)]
     pub project_id: Option<Uuid>,
     #[builder(setter(strip_option), default)]
     pub lead_id: Option<Uuid>,
     #[builder(setter(strip_option), default)]
     pub parent_id: Option<Uuid>,
+    #[builder(setter(strip_option), default)]
+    pub recurrence: Option<String>,
 
     #[builder(setter(strip_option), default)]
     pub labels: Option<Vec<Uuid>>,
@@ -79,6 +157,16 @@ pub struct CreateTaskInput {
 
     #[builder(setter(strip_option), default)]
     pub assets: Option<Vec<Uuid>>,
+
+    /// Retrying a create with the same key returns the task from the first attempt instead of inserting
+    /// a duplicate. Keys are unique among tasks only, so a label may reuse one.
+    #[builder(setter(into, strip_option), default)]
+    pub idempotency_key: Option<String>,
+
+    /// Member performing the write, recorded as `created_by`/`updated_by`; `None` for system operations.
+    #[graphql(skip)]
+    #[builder(setter(strip_option), default)]
+    pub actor_id: Option<Uuid>,
 }
 
 #[derive(Default, Builder, Object, InputObject, Serialize, Clone)]
@@ -92,14 +180,34 @@ pub struct UpdateTaskInput {
     pub title: Option<String>,
     #[builder(setter(strip_option), default)]
     pub description: Option<String>,
+    /// Given in any offset, stored as UTC.
     #[builder(setter(strip_option), default)]
     pub due_date: Option<DateTime<Utc>>,
+    /// Accept a `due_date` earlier than now, which is otherwise rejected with `SDKError::DueDateInPast`.
+    #[builder(setter(strip_option), default)]
+    pub allow_past_due: Option<bool>,
     #[builder(setter(strip_option), default)]
     pub project_id: Option<Uuid>,
     #[builder(setter(strip_option), default)]
     pub lead_id: Option<Uuid>,
     #[builder(setter(strip_option), default)]
     pub parent_id: Option<Uuid>,
+    #[builder(setter(strip_option), default)]
+    pub recurrence: Option<String>,
+
+    /// Fields to set to null. Leaving a field `None` above keeps its current value,
+    /// listing it here clears it; clearing wins if both are given.
+    #[builder(setter(strip_option), default)]
+    pub clear: Option<Vec<ClearableTaskField>>,
+
+    /// When set, the update only applies if the task is still at this version, else `SDKError::Conflict`.
+    #[builder(setter(strip_option), default)]
+    pub expected_updated_at: Option<DateTime<Utc>>,
+
+    /// Member performing the write, recorded as `created_by`/`updated_by`; `None` for system operations.
+    #[graphql(skip)]
+    #[builder(setter(strip_option), default)]
+    pub actor_id: Option<Uuid>,
 
     #[builder(setter(strip_option), default)]
     pub labels: Option<UpdateListInput>,
@@ -109,29 +217,59 @@ pub struct UpdateTaskInput {
     pub assets: Option<UpdateListInput>,
 }
 
-#[derive(Default, Builder, Object, InputObject)]
+#[derive(Debug, Enum, OpenApiEnum, Copy, Clone, Display, Serialize, Eq, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum ClearableTaskField {
+    Description,
+    DueDate,
+    ProjectId,
+    LeadId,
+    ParentId,
+    Recurrence,
+}
+
+#[derive(Default, Builder, Object, InputObject, Clone)]
 #[builder(pattern = "owned")]
 pub struct GetTasksWhere {
     #[builder(setter(strip_option), default)]
     pub ids: Option<Vec<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub owner_id: Option<FilterOp<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub status: Option<FilterOp<TaskStatus>>,
+    #[builder(setter(into, strip_option), default)]
+    pub priority: Option<FilterOp<TaskPriority>>,
+    #[builder(setter(into, strip_option), default)]
+    pub title: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<FilterOp<String>>,
+    #[builder(setter(into, strip_option), default)]
+    pub due_date: Option<FilterOp<DateTime<Utc>>>,
+    #[builder(setter(into, strip_option), default)]
+    pub project_id: Option<FilterOp<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub lead_id: Option<FilterOp<Uuid>>,
+    #[builder(setter(into, strip_option), default)]
+    pub parent_id: Option<FilterOp<Uuid>>,
+
+    /// Inclusive lower bound.
     #[builder(setter(strip_option), default)]
-    pub owner_id: Option<Uuid>,
-    #[builder(setter(strip_option), default)]
-    pub status: Option<TaskStatus>,
-    #[builder(setter(strip_option), default)]
-    pub priority: Option<TaskPriority>,
-    #[builder(setter(strip_option), default)]
-    pub title: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
     #[builder(setter(strip_option), default)]
-    pub description: Option<String>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Inclusive lower bound.
     #[builder(setter(strip_option), default)]
-    pub due_date: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
     #[builder(setter(strip_option), default)]
-    pub project_id: Option<Uuid>,
-    #[builder(setter(strip_option), default)]
-    pub lead_id: Option<Uuid>,
+    pub updated_before: Option<DateTime<Utc>>,
+
+    /// SDK-only escape hatch, ANDed with the other conditions; see [`GetTasksWhere::raw`].
+    #[oai(skip)]
+    #[graphql(skip)]
     #[builder(setter(strip_option), default)]
-    pub parent_id: Option<Uuid>,
+    pub raw: Option<RawFilter>,
 
     #[oai(skip)]
     #[builder(setter(strip_option), default)]
@@ -143,172 +281,394 @@ pub struct GetTasksWhere {
 }
 
 impl GetTasksWhere {
-    pub fn compile_sql(&self) -> String {
+    /// A filter on a parameterized SQL fragment such as `"position > $1 AND recurrence IS NOT NULL"`;
+    /// combine it with other fields through the builder's `raw` setter or `_and`.
+    pub fn raw(fragment: &str, params: Vec<SQLArgument>) -> Result<Self, SDKError> {
+        Ok(GetTasksWhere {
+            raw: Some(RawFilter::new(fragment, params)?),
+            ..Default::default()
+        })
+    }
+
+    pub fn compile_sql(&self) -> (String, Vec<SQLArgument>) {
+        let mut args = Vec::new();
+        let where_clause = self.compile_sql_with(&mut args);
+
+        (where_clause, args)
+    }
+
+    fn compile_sql_with(&self, args: &mut Vec<SQLArgument>) -> String {
         let mut conditions = Vec::new();
 
         if let Some(ids) = &self.ids {
-            conditions.push(format!(
-                "id = ANY(array[{}]::uuid[])",
-                ids.iter()
-                    .map(|x| format!("'{}'", x))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ));
+            args.push(SQLArgument::UuidArray(ids.clone()));
+            conditions.push(format!("id = ANY(${})", args.len()));
         }
 
         if let Some(owner_id) = &self.owner_id {
-            conditions.push(format!("owner_id = {}", owner_id));
+            conditions.push(owner_id.compile_sql("owner_id", args));
         }
 
         if let Some(status) = &self.status {
-            conditions.push(format!("status = '{}'", status));
+            conditions.push(status.compile_sql("status", args));
         }
 
         if let Some(priority) = &self.priority {
-            conditions.push(format!("priority = '{}'", priority));
+            conditions.push(priority.compile_sql("priority", args));
         }
 
         if let Some(title) = &self.title {
-            conditions.push(format!("title = '{}'", title));
+            conditions.push(title.compile_sql("title", args));
         }
 
         if let Some(description) = &self.description {
-            conditions.push(format!("description = '{}'", description));
+            conditions.push(description.compile_sql("description", args));
         }
 
         if let Some(due_date) = &self.due_date {
-            conditions.push(format!("due_date = '{}'", due_date));
+            conditions.push(due_date.compile_sql("due_date", args));
         }
 
         if let Some(project_id) = &self.project_id {
-            conditions.push(format!("project_id = {}", project_id));
+            conditions.push(project_id.compile_sql("project_id", args));
         }
 
         if let Some(lead_id) = &self.lead_id {
-            conditions.push(format!("lead_id = {}", lead_id));
+            conditions.push(lead_id.compile_sql("lead_id", args));
         }
 
         if let Some(parent_id) = &self.parent_id {
-            conditions.push(format!("parent_id = {}", parent_id));
+            conditions.push(parent_id.compile_sql("parent_id", args));
+        }
+
+        if let Some(created_after) = &self.created_after {
+            args.push(SQLArgument::Timestamp(*created_after));
+            conditions.push(format!("created_at >= ${}", args.len()));
+        }
+
+        if let Some(created_before) = &self.created_before {
+            args.push(SQLArgument::Timestamp(*created_before));
+            conditions.push(format!("created_at < ${}", args.len()));
         }
 
+        if let Some(updated_after) = &self.updated_after {
+            args.push(SQLArgument::Timestamp(*updated_after));
+            conditions.push(format!("updated_at >= ${}", args.len()));
+        }
+
+        if let Some(updated_before) = &self.updated_before {
+            args.push(SQLArgument::Timestamp(*updated_before));
+            conditions.push(format!("updated_at < ${}", args.len()));
+        }
+
+        if let Some(raw) = &self.raw {
+            conditions.push(raw.compile_sql(args));
+        }
+
+        // an empty nested filter adds no condition, and a group left with none is dropped
         if let Some(ands) = &self._and {
-            let and_conditions: Vec<String> = ands.iter().map(|and| and.compile_sql()).collect();
-            conditions.push(format!("({})", and_conditions.join(" AND ")));
+            let and_conditions: Vec<String> = ands
+                .iter()
+                .map(|and| and.compile_sql_with(args))
+                .filter(|condition| !condition.is_empty())
+                .collect();
+
+            if !and_conditions.is_empty() {
+                conditions.push(format!("({})", and_conditions.join(" AND ")));
+            }
         }
 
         if let Some(ors) = &self._or {
-            let or_conditions: Vec<String> = ors.iter().map(|or| or.compile_sql()).collect();
-            conditions.push(format!("({})", or_conditions.join(" OR ")));
+            let or_conditions: Vec<String> = ors
+                .iter()
+                .map(|or| or.compile_sql_with(args))
+                .filter(|condition| !condition.is_empty())
+                .collect();
+
+            if !or_conditions.is_empty() {
+                conditions.push(format!("({})", or_conditions.join(" OR ")));
+            }
         }
 
         conditions.join(" AND ")
     }
 }
 
-#[async_trait]
-impl TaskCrudOperations for SDKEngine {
-    async fn create_task(&self, input: CreateTaskInput) -> Result<Task, SDKError> {
-        let mut tx = self.db_pool.begin().await?;
-        // let saved_input = input.clone();
+/// Rejects a due date earlier than now unless the caller opted into past due dates.
+pub(crate) fn validate_due_date(due_date: Option<DateTime<Utc>>, allow_past_due: Option<bool>) -> Result<(), SDKError> {
+    match due_date {
+        Some(due_date) if !allow_past_due.unwrap_or_default() && due_date < Utc::now() => {
+            Err(SDKError::DueDateInPast(due_date))
+        }
+        _ => Ok(()),
+    }
+}
 
-        let task = sqlx::query!(
-            r#"
-            INSERT INTO tasks (title, description, owner_id, status, priority, due_date, project_id, lead_id, parent_id)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-            RETURNING *
-            "#,
-            input.title,
-            input.description,
-            input.owner_id,
-            input.status.unwrap_or_default().to_string(),
-            input.priority.unwrap_or_default().to_string(),
-            input.due_date,
-            input.project_id,
-            input.lead_id,
-            input.parent_id,
-        )
-        .fetch_one(&mut *tx)
-        .await?;
+/// Trims `title` in place; what remains must be non-empty and at most `max_length` characters.
+pub(crate) fn validate_title(title: &mut String, max_length: u32) -> Result<(), String> {
+    let trimmed = title.trim();
 
-        if let Some(labels) = input.labels {
-            for label in labels {
-                sqlx::query!(
-                    r#"
-                    INSERT INTO labels_by_tasks (task_id, label_id)
-                    VALUES ($1, $2)
-                    "#,
-                    task.id,
-                    label,
-                )
-                .execute(&mut *tx)
-                .await?;
+    if trimmed.is_empty() {
+        return Err("title must not be empty".to_string());
+    }
+
+    let length = trimmed.chars().count();
+
+    if length > max_length as usize {
+        return Err(format!(
+            "title is {length} characters long, the maximum is {max_length}"
+        ));
+    }
+
+    if trimmed.len() != title.len() {
+        *title = trimmed.to_string();
+    }
+
+    Ok(())
+}
+
+/// Runs [`validate_title`] over a task and its subtasks, collecting failures under the batch `index`.
+pub(crate) fn validate_titles(
+    task: &mut CreateTaskInput,
+    index: usize,
+    path: &str,
+    max_length: u32,
+    errors: &mut Vec<FieldError>,
+) {
+    if let Err(message) = validate_title(&mut task.title, max_length) {
+        errors.push(FieldError::new(index, format!("{path}title"), message));
+    }
+
+    for (i, subtask) in task.subtasks.iter_mut().flatten().enumerate() {
+        validate_titles(subtask, index, &format!("{path}subtasks[{i}]."), max_length, errors);
+    }
+}
+
+/// Renders the offset-paginated query behind `get_tasks` after `select`, which must expose the task columns
+/// unqualified, e.g. `SELECT * FROM tasks `. The limit is used as given.
+pub(crate) fn compile_tasks_query(
+    select: &str,
+    input: Option<GetTasksInput>,
+) -> Result<(String, Vec<SQLArgument>), SDKError> {
+    let mut query = select.to_string();
+    let mut args = Vec::new();
+
+    let query = match input {
+        Some(input) => {
+            let mut conditions = Vec::new();
+
+            if let Some(filter) = input.filter {
+                let (where_clause, where_args) = filter.compile_sql();
+
+                if !where_clause.is_empty() {
+                    conditions.push(format!("({})", where_clause));
+                    args = where_args;
+                }
             }
-        }
 
-        if let Some(assignees) = input.assignees {
-            for assignee in assignees {
-                sqlx::query!(
-                    r#"
-                    INSERT INTO tasks_by_assignees (task_id, assignee_id)
-                    VALUES ($1, $2)
-                    "#,
-                    task.id,
-                    assignee,
-                )
-                .execute(&mut *tx)
-                .await?;
+            if !input.include_archived.unwrap_or_default() {
+                conditions.push("archived_at IS NULL".to_string());
+            }
+
+            if !conditions.is_empty() {
+                query.push_str(format!("WHERE {} ", conditions.join(" AND ")).as_str());
             }
-        }
 
-        if let Some(subtasks) = input.subtasks {
-            for mut subtask in subtasks {
-                if subtask.parent_id.is_none() {
-                    subtask.parent_id = Some(task.id);
+            if let Some(sort_by) = input.sort_by {
+                if !TASK_SORT_FIELDS.contains(&sort_by.as_str()) {
+                    return Err(SDKError::InvalidSortField(sort_by));
                 }
 
-                self.create_task(subtask).await?;
+                query.push_str(format!("ORDER BY {} ", sort_by).as_str());
+
+                if let Some(sort_order) = input.sort_order {
+                    query.push_str(format!("{} ", sort_order.to_sql()).as_str());
+                }
             }
+
+            if let Some(limit) = input.limit {
+                query.push_str(format!("LIMIT {} ", limit).as_str());
+            }
+
+            if let Some(offset) = input.offset {
+                query.push_str(format!("OFFSET {} ", offset).as_str());
+            }
+
+            query
         }
+        None => {
+            query.push_str("WHERE archived_at IS NULL ");
 
-        // if let Some(assets) = input.assets {
-        //     for asset in assets {
-        //         sqlx::query!(
-        //             r#"
-        //             INSERT INTO assets_by_tasks (task_id, asset_id)
-        //             VALUES ($1, $2)
-        //             "#,
-        //             task.id,
-        //             asset,
-        //         )
-        //         .execute(&mut *tx)
-        //         .await?;
-        //     }
-        // }
+            query
+        }
+    };
 
-        tx.commit().await?;
+    Ok((query, args))
+}
 
-        let task = Task {
-            id: task.id,
-            created_at: task.created_at,
-            updated_at: task.updated_at,
-            title: task.title,
-            description: task.description,
-            status: task
+/// A full `tasks` row, for `query_as!` reads of `SELECT *` and `RETURNING *`. Columns without a `Task`
+/// field are listed too, since `query_as!` needs a field for every selected column.
+#[allow(dead_code)]
+pub(crate) struct TaskRecord {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub title: String,
+    pub description: Option<String>,
+    pub owner_id: Uuid,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub project_id: Option<Uuid>,
+    pub lead_id: Option<Uuid>,
+    pub labels: Option<serde_json::Value>,
+    pub count: i32,
+    pub parent_id: Option<Uuid>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+    pub recurrence_source_id: Option<Uuid>,
+    pub position: i32,
+    pub created_by: Option<Uuid>,
+    pub updated_by: Option<Uuid>,
+    pub idempotency_key: Option<String>,
+}
+
+/// A NULL status or priority reads as `None`; a value that names no variant is an error, not a default.
+impl TryFrom<TaskRecord> for Task {
+    type Error = SDKError;
+
+    fn try_from(task_info: TaskRecord) -> Result<Self, Self::Error> {
+        Ok(Task {
+            id: task_info.id,
+            created_at: task_info.created_at,
+            updated_at: task_info.updated_at,
+            title: task_info.title,
+            description: task_info.description,
+            status: task_info
                 .status
-                .and_then(|a| TaskStatus::from_str(&a).ok())
+                .as_deref()
+                .map(TaskStatus::from_str)
+                .transpose()?
                 .unwrap_or_default(),
-            priority: task
+            priority: task_info
                 .priority
-                .and_then(|a| TaskPriority::from_str(&a).ok())
+                .as_deref()
+                .map(TaskPriority::from_str)
+                .transpose()?
                 .unwrap_or_default(),
-            due_date: task.due_date,
-            project_id: task.project_id,
-            lead_id: task.lead_id,
-            owner_id: task.owner_id,
-            count: task.count,
-            parent_id: task.parent_id,
-        };
+            due_date: task_info.due_date,
+            project_id: task_info.project_id,
+            lead_id: task_info.lead_id,
+            owner_id: task_info.owner_id,
+            count: task_info.count,
+            parent_id: task_info.parent_id,
+            archived_at: task_info.archived_at,
+            recurrence: task_info.recurrence,
+            position: task_info.position,
+        })
+    }
+}
+
+pub(crate) fn task_from_row(task_info: &PgRow) -> Result<Task, SDKError> {
+    Ok(Task {
+        id: task_info.get("id"),
+        created_at: task_info.get("created_at"),
+        updated_at: task_info.get("updated_at"),
+        title: task_info.get("title"),
+        description: task_info.get("description"),
+        status: task_info
+            .get::<'_, Option<String>, _>("status")
+            .as_deref()
+            .map(TaskStatus::from_str)
+            .transpose()?
+            .unwrap_or_default(),
+        priority: task_info
+            .get::<'_, Option<String>, _>("priority")
+            .as_deref()
+            .map(TaskPriority::from_str)
+            .transpose()?
+            .unwrap_or_default(),
+        // status: TaskStatus::from_optional_str(&task_info.get("status")),
+        // priority: TaskPriority::from_optional_str(&),
+        due_date: task_info.get("due_date"),
+        project_id: task_info.get("project_id"),
+        lead_id: task_info.get("lead_id"),
+        owner_id: task_info.get("owner_id"),
+        count: task_info.get("count"),
+        parent_id: task_info.get("parent_id"),
+        archived_at: task_info.get("archived_at"),
+        recurrence: task_info.get("recurrence"),
+        position: task_info.get("position"),
+    })
+}
+
+/// Offset-paginated task query, shared by `get_tasks` and `get_tasks_page`. The limit is used as given.
+async fn fetch_tasks(engine: &SDKEngine, input: Option<GetTasksInput>) -> Result<Vec<Task>, SDKError> {
+    let (query, args) = compile_tasks_query("SELECT * FROM tasks ", input)?;
+
+    engine.log_sql(&query, &args);
+
+    let tasks_info = args
+        .into_iter()
+        .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+        .fetch_all(&mut *engine.acquire_read().await?)
+        .await?;
+
+    Span::current().record("rows", tasks_info.len());
+
+    tasks_info.iter().map(task_from_row).collect()
+}
+
+/// Tasks matching `filter`, skipping archived ones unless `include_archived`; the total behind `get_tasks_page`.
+async fn count_tasks(
+    executor: impl PgExecutor<'_>,
+    filter: Option<&GetTasksWhere>,
+    include_archived: bool,
+) -> Result<i64, SDKError> {
+    let mut query = "SELECT COUNT(*) FROM tasks ".to_string();
+    let mut args = Vec::new();
+    let mut conditions = Vec::new();
+
+    if let Some(filter) = filter {
+        let (where_clause, where_args) = filter.compile_sql();
+
+        if !where_clause.is_empty() {
+            conditions.push(format!("({})", where_clause));
+            args = where_args;
+        }
+    }
+
+    if !include_archived {
+        conditions.push("archived_at IS NULL".to_string());
+    }
+
+    if !conditions.is_empty() {
+        query.push_str(format!("WHERE {} ", conditions.join(" AND ")).as_str());
+    }
+
+    let count = args
+        .into_iter()
+        .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+        .fetch_one(executor)
+        .await?
+        .get::<i64, _>(0);
+
+    Ok(count)
+}
+
+#[async_trait]
+impl TaskCrudOperations for SDKEngine {
+    #[instrument(skip_all, fields(resource = "task", operation = "create"))]
+    async fn create_task(&self, mut input: CreateTaskInput) -> Result<Task, SDKError> {
+        let mut errors = Vec::new();
+        validate_titles(&mut input, 0, "", self.config.max_title_length, &mut errors);
+
+        if !errors.is_empty() {
+            return Err(SDKError::Validation(errors));
+        }
+
+        // let saved_input = input.clone();
+        let (task, created) = insert_task(&mut *self.acquire().await?, input).await?;
 
         // if self.config.with_changes_registration {
         //     let input = saved_input.clone();
@@ -340,60 +700,117 @@ impl TaskCrudOperations for SDKEngine {
         //     });
         // }
 
+        if created {
+            self.emit(|| SDKEvent::TaskCreated(task.clone()));
+        }
+
         Ok(task)
     }
 
+    #[instrument(skip_all, fields(resource = "task", operation = "read"))]
     async fn get_task(&self, id: Uuid) -> Result<Task, SDKError> {
-        let task_info = sqlx::query!(
+        let task_info = sqlx::query_as!(
+            TaskRecord,
             r#"
             SELECT * FROM tasks WHERE id = $1
             "#,
             id,
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("task", id))?;
 
-        let task = Task {
-            id: task_info.id,
-            created_at: task_info.created_at,
-            updated_at: task_info.updated_at,
-            title: task_info.title,
-            description: task_info.description,
-            status: task_info
-                .status
-                .and_then(|a| TaskStatus::from_str(&a).ok())
-                .unwrap_or_default(),
-            priority: task_info
-                .priority
-                .and_then(|a| TaskPriority::from_str(&a).ok())
-                .unwrap_or_default(),
-            due_date: task_info.due_date,
-            project_id: task_info.project_id,
-            lead_id: task_info.lead_id,
-            owner_id: task_info.owner_id,
-            count: task_info.count,
-            parent_id: task_info.parent_id,
-        };
+        let task = Task::try_from(task_info)?;
 
         Ok(task)
     }
 
-    async fn update_task(&self, id: Uuid, input: UpdateTaskInput) -> Result<Task, SDKError> {
-        let mut tx = self.db_pool.begin().await?;
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_tasks_by_ids(&self, ids: Vec<Uuid>) -> Result<Vec<Task>, SDKError> {
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT * FROM tasks WHERE id = ANY($1)
+            "#,
+            &ids,
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Span::current().record("rows", tasks_info.len());
+
+        let mut tasks_map: HashMap<Uuid, Task> = tasks_info
+            .into_iter()
+            .map(|task_info| Ok((task_info.id, Task::try_from(task_info)?)))
+            .collect::<Result<_, SDKError>>()?;
+
+        // input order, missing ids are skipped; a repeated id is returned once
+        Ok(ids.iter().filter_map(|id| tasks_map.remove(id)).collect())
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "update"))]
+    async fn update_task(&self, id: Uuid, mut input: UpdateTaskInput) -> Result<Task, SDKError> {
+        if let Some(title) = input.title.as_mut() {
+            validate_title(title, self.config.max_title_length)
+                .map_err(|message| SDKError::Validation(vec![FieldError::new(0, "title", message)]))?;
+        }
+
+        // an empty rule clears the recurrence
+        if let Some(recurrence) = input.recurrence.as_ref().filter(|r| !r.is_empty()) {
+            Recurrence::from_str(recurrence)?;
+        }
+
+        let cleared: Vec<String> = input.clear.iter().flatten().map(|field| field.to_string()).collect();
 
-        let task_final_info = sqlx::query!(
+        if !cleared.iter().any(|field| field == "due_date") {
+            validate_due_date(input.due_date, input.allow_past_due)?;
+        }
+
+        let mut tx = self.begin().await?;
+
+        if let Some(next_status) = input.status {
+            let current_status = sqlx::query!(
+                r#"
+                SELECT status FROM tasks WHERE id = $1 FOR UPDATE
+                "#,
+                id,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(SDKError::or_not_found("task", id))?
+            .status
+            .as_deref()
+            .map(TaskStatus::from_str)
+            .transpose()?
+            .unwrap_or_default();
+
+            if !current_status.can_transition_to(next_status) {
+                return Err(SDKError::InvalidStatusTransition {
+                    from: current_status,
+                    to: next_status,
+                });
+            }
+        }
+
+        let task_final_info = sqlx::query_as!(
+            TaskRecord,
             r#"
             UPDATE tasks
             SET
                 status = COALESCE($1, status),
                 priority = COALESCE($2, priority),
                 title = COALESCE($3, title),
-                description = COALESCE($4, description),
-                due_date = COALESCE($5, due_date),
-                project_id = NULLIF(COALESCE($6, project_id), '00000000-0000-0000-0000-000000000000'),
-                lead_id = NULLIF(COALESCE($7, lead_id), '00000000-0000-0000-0000-000000000000'),
-                parent_id = NULLIF(COALESCE($8, parent_id), '00000000-0000-0000-0000-000000000000')
-            WHERE id = $9
+                description = CASE WHEN 'description' = ANY($11) THEN NULL ELSE COALESCE($4, description) END,
+                due_date = CASE WHEN 'due_date' = ANY($11) THEN NULL ELSE COALESCE($5, due_date) END,
+                project_id = CASE WHEN 'project_id' = ANY($11) THEN NULL
+                    ELSE NULLIF(COALESCE($6, project_id), '00000000-0000-0000-0000-000000000000') END,
+                lead_id = CASE WHEN 'lead_id' = ANY($11) THEN NULL
+                    ELSE NULLIF(COALESCE($7, lead_id), '00000000-0000-0000-0000-000000000000') END,
+                parent_id = CASE WHEN 'parent_id' = ANY($11) THEN NULL
+                    ELSE NULLIF(COALESCE($8, parent_id), '00000000-0000-0000-0000-000000000000') END,
+                recurrence = CASE WHEN 'recurrence' = ANY($11) THEN NULL ELSE NULLIF(COALESCE($9, recurrence), '') END,
+                updated_by = $13
+            WHERE id = $10 AND ($12::timestamptz IS NULL OR updated_at = $12)
             RETURNING *
             "#,
             input.status.map(|status| status.to_string()),
@@ -404,11 +821,32 @@ impl TaskCrudOperations for SDKEngine {
             input.project_id,
             input.lead_id,
             input.parent_id,
+            input.recurrence,
             id,
+            &cleared,
+            input.expected_updated_at,
+            input.actor_id,
         )
-        .fetch_one(&mut *tx)
+        .fetch_optional(&mut *tx)
         .await?;
 
+        let Some(task_final_info) = task_final_info else {
+            let exists = sqlx::query!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM tasks WHERE id = $1) AS "exists!"
+                "#,
+                id,
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .exists;
+
+            return Err(match exists {
+                true => SDKError::Conflict { resource: "task", id },
+                false => SDKError::NotFound { resource: "task", id },
+            });
+        };
+
         if let Some(labels) = input.labels {
             for label in labels.add {
                 sqlx::query!(
@@ -492,27 +930,7 @@ impl TaskCrudOperations for SDKEngine {
         //     }
         // }
 
-        let task = Task {
-            id: task_final_info.id,
-            created_at: task_final_info.created_at,
-            updated_at: task_final_info.updated_at,
-            title: task_final_info.title,
-            description: task_final_info.description,
-            status: task_final_info
-                .status
-                .and_then(|a| TaskStatus::from_str(&a).ok())
-                .unwrap_or_default(),
-            priority: task_final_info
-                .priority
-                .and_then(|a| TaskPriority::from_str(&a).ok())
-                .unwrap_or_default(),
-            due_date: task_final_info.due_date,
-            project_id: task_final_info.project_id,
-            lead_id: task_final_info.lead_id,
-            owner_id: task_final_info.owner_id,
-            count: task_final_info.count,
-            parent_id: task_final_info.parent_id,
-        };
+        let task = Task::try_from(task_final_info)?;
 
         // if self.config.with_changes_registration {
         //     let task = task.clone();
@@ -541,41 +959,133 @@ impl TaskCrudOperations for SDKEngine {
         //     });
         // }
 
+        self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+
         Ok(task)
     }
 
-    async fn delete_task(&self, id: Uuid) -> Result<Task, SDKError> {
-        let task_info = sqlx::query!(
+    #[instrument(skip_all, fields(resource = "task", operation = "update", rows))]
+    async fn update_tasks_status(
+        &self,
+        ids: Vec<Uuid>,
+        status: TaskStatus,
+        actor_id: Option<Uuid>,
+    ) -> Result<Vec<Task>, SDKError> {
+        let mut tx = self.begin().await?;
+
+        let current_statuses = sqlx::query!(
             r#"
-            DELETE FROM tasks WHERE id = $1
-            RETURNING *
+            SELECT status FROM tasks WHERE id = ANY($1) FOR UPDATE
             "#,
-            id,
+            &ids,
         )
-        .fetch_one(self.db_pool.as_ref())
+        .fetch_all(&mut *tx)
         .await?;
 
-        let task = Task {
-            id: task_info.id,
-            created_at: task_info.created_at,
-            updated_at: task_info.updated_at,
-            title: task_info.title,
-            description: task_info.description,
-            status: task_info
+        for current_status in current_statuses {
+            let current_status = current_status
                 .status
-                .and_then(|a| TaskStatus::from_str(&a).ok())
-                .unwrap_or_default(),
-            priority: task_info
-                .priority
-                .and_then(|a| TaskPriority::from_str(&a).ok())
-                .unwrap_or_default(),
-            due_date: task_info.due_date,
-            project_id: task_info.project_id,
-            lead_id: task_info.lead_id,
-            owner_id: task_info.owner_id,
-            count: task_info.count,
-            parent_id: task_info.parent_id,
-        };
+                .as_deref()
+                .map(TaskStatus::from_str)
+                .transpose()?
+                .unwrap_or_default();
+
+            if !current_status.can_transition_to(status) {
+                return Err(SDKError::InvalidStatusTransition {
+                    from: current_status,
+                    to: status,
+                });
+            }
+        }
+
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            UPDATE tasks
+            SET status = $1, updated_by = $3
+            WHERE id = ANY($2)
+            RETURNING *
+            "#,
+            status.to_string(),
+            &ids,
+            actor_id,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Span::current().record("rows", tasks_info.len());
+
+        tx.commit().await?;
+
+        let tasks = tasks_info
+            .into_iter()
+            .map(Task::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for task in &tasks {
+            self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+        }
+
+        Ok(tasks)
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "update"))]
+    async fn move_task_to_project(&self, task_id: Uuid, project_id: Option<Uuid>) -> Result<Task, SDKError> {
+        let mut tx = self.begin().await?;
+
+        if let Some(project_id) = project_id {
+            let project_exists = sqlx::query!(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM projects WHERE id = $1) AS "exists!"
+                "#,
+                project_id,
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .exists;
+
+            if !project_exists {
+                return Err(SDKError::ProjectNotFound);
+            }
+        }
+
+        // the parent link is kept only when the parent task lives in the target project too
+        let task_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            UPDATE tasks
+            SET
+                project_id = $1,
+                parent_id = (
+                    SELECT parent.id FROM tasks parent
+                    WHERE parent.id = tasks.parent_id AND parent.project_id IS NOT DISTINCT FROM $1
+                ),
+                position = (
+                    SELECT COALESCE(MAX(sibling.position) + 1, 0) FROM tasks sibling
+                    WHERE sibling.project_id IS NOT DISTINCT FROM $1 AND sibling.id <> $2
+                )
+            WHERE id = $2
+            RETURNING *
+            "#,
+            project_id,
+            task_id,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(SDKError::or_not_found("task", task_id))?;
+
+        tx.commit().await?;
+
+        let task = Task::try_from(task_info)?;
+
+        self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+
+        Ok(task)
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "delete"))]
+    async fn delete_task(&self, id: Uuid) -> Result<Task, SDKError> {
+        let task = self.acquire().await?.delete_task(id).await?;
 
         // if self.config.with_changes_registration {
         //     let task = task.clone();
@@ -605,66 +1115,480 @@ impl TaskCrudOperations for SDKEngine {
         //     });
         // }
 
+        self.emit(|| SDKEvent::TaskDeleted { id: task.id });
+
+        Ok(task)
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "update"))]
+    async fn archive_task(&self, id: Uuid) -> Result<Task, SDKError> {
+        let task_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            UPDATE tasks
+            SET archived_at = COALESCE(archived_at, now())
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("task", id))?;
+
+        let task = Task::try_from(task_info)?;
+
+        self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+
+        Ok(task)
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "update"))]
+    async fn unarchive_task(&self, id: Uuid) -> Result<Task, SDKError> {
+        let task_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            UPDATE tasks
+            SET archived_at = NULL
+            WHERE id = $1
+            RETURNING *
+            "#,
+            id,
+        )
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("task", id))?;
+
+        let task = Task::try_from(task_info)?;
+
+        self.emit(|| SDKEvent::TaskUpdated(task.clone()));
+
         Ok(task)
     }
 
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
     async fn get_tasks(&self, input: Option<GetTasksInput>) -> Result<Vec<Task>, SDKError> {
+        let input = match input {
+            Some(input) if input.cursor.is_some() => {
+                return self.get_tasks_by_cursor(input).await.map(|page| page.items);
+            }
+            input => input,
+        };
+
+        let input = input.unwrap_or_default();
+        let input = GetTasksInput {
+            limit: Some(clamp_page(input.limit, input.offset, self.config.max_page_size)?),
+            ..input
+        };
+
+        fetch_tasks(self, Some(input)).await
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_tasks_by_cursor(&self, input: GetTasksInput) -> Result<CursorPage<Task>, SDKError> {
+        if input.sort_by.is_some() {
+            return Err(SDKError::InvalidCursor(
+                "cursor pages are always sorted by created_at and id".to_string(),
+            ));
+        }
+
+        let limit = clamp_page(input.limit, None, self.config.max_page_size)?;
+
         let mut query = "SELECT * FROM tasks ".to_string();
+        let mut args = Vec::new();
+        let mut conditions = Vec::new();
 
-        let query = match input {
-            Some(input) => {
-                if let Some(filter) = input.filter {
-                    query.push_str(format!("WHERE {} ", filter.compile_sql()).as_str());
-                }
+        if let Some(filter) = input.filter {
+            let (where_clause, where_args) = filter.compile_sql();
 
-                if let Some(sort_by) = input.sort_by {
-                    query.push_str(format!("ORDER BY {} ", sort_by).as_str());
-                }
+            if !where_clause.is_empty() {
+                conditions.push(format!("({})", where_clause));
+                args = where_args;
+            }
+        }
 
-                if let Some(sort_order) = input.sort_order {
-                    query.push_str(format!("{} ", sort_order).as_str());
-                }
+        if !input.include_archived.unwrap_or_default() {
+            conditions.push("archived_at IS NULL".to_string());
+        }
 
-                if let Some(limit) = input.limit {
-                    query.push_str(format!("LIMIT {} ", limit).as_str());
-                }
+        if let Some(cursor) = input.cursor {
+            let cursor = Cursor::decode(&cursor)?;
+
+            args.push(SQLArgument::Timestamp(cursor.created_at));
+            args.push(SQLArgument::Uuid(cursor.id));
+            conditions.push(format!("(created_at, id) > (${}, ${})", args.len() - 1, args.len()));
+        }
+
+        if !conditions.is_empty() {
+            query.push_str(format!("WHERE {} ", conditions.join(" AND ")).as_str());
+        }
+        query.push_str("ORDER BY created_at ASC, id ASC ");
+
+        query.push_str(format!("LIMIT {} ", limit).as_str());
 
-                if let Some(offset) = input.offset {
-                    query.push_str(format!("OFFSET {} ", offset).as_str());
+        self.log_sql(&query, &args);
+
+        let tasks_info = args
+            .into_iter()
+            .fold(sqlx::query(query.as_str()), |query, arg| arg.bind(query))
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
+
+        Span::current().record("rows", tasks_info.len());
+
+        let tasks = tasks_info.iter().map(task_from_row).collect::<Result<Vec<_>, _>>()?;
+
+        let next_cursor = match tasks.last() {
+            Some(last) if tasks.len() as i32 >= limit => Some(
+                Cursor {
+                    created_at: last.created_at,
+                    id: last.id,
                 }
+                .encode(),
+            ),
+            _ => None,
+        };
+
+        Ok(CursorPage {
+            items: tasks,
+            next_cursor,
+        })
+    }
 
-                query
+    /// Every matching task, archived ones included, in `(created_at, id)` order.
+    /// Pages are fetched by keyset only once the previous one has been consumed.
+    fn stream_tasks(&self, filter: Option<GetTasksWhere>) -> BoxStream<'_, Result<Task, SDKError>> {
+        // `None` once the last page has been fetched
+        let first_page: Option<Option<String>> = Some(None);
+
+        stream::try_unfold(first_page, move |cursor| {
+            let filter = filter.clone();
+
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok(None);
+                };
+
+                let page = self
+                    .get_tasks_by_cursor(GetTasksInput {
+                        filter,
+                        limit: Some(TASK_STREAM_PAGE_SIZE),
+                        include_archived: Some(true),
+                        cursor,
+                        ..Default::default()
+                    })
+                    .await?;
+
+                let items = stream::iter(page.items.into_iter().map(Ok::<_, SDKError>));
+
+                Ok::<_, SDKError>(Some((items, page.next_cursor.map(Some))))
             }
-            None => query,
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_tasks_page(&self, input: GetTasksInput, with_total: bool) -> Result<Page<Task>, SDKError> {
+        if input.cursor.is_some() {
+            return Err(SDKError::InvalidPagination(
+                "get_tasks_page pages by offset, not by cursor".to_string(),
+            ));
+        }
+
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        let offset = input.offset.unwrap_or(0);
+
+        let total = match with_total {
+            true => Some(
+                count_tasks(
+                    &mut *self.acquire_read().await?,
+                    input.filter.as_ref(),
+                    input.include_archived.unwrap_or_default(),
+                )
+                .await?,
+            ),
+            false => None,
+        };
+
+        // the extra row only tells whether another page exists, so it may exceed `max_page_size`
+        let tasks = fetch_tasks(
+            self,
+            Some(GetTasksInput {
+                limit: Some(limit + 1),
+                offset: Some(offset),
+                ..input
+            }),
+        )
+        .await?;
+
+        Ok(Page::from_overfetch(tasks, total, limit, offset))
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_tasks_due_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        owner_id: Option<Uuid>,
+    ) -> Result<Vec<Task>, SDKError> {
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT * FROM tasks
+            WHERE due_date >= $1 AND due_date < $2
+                AND ($3::uuid IS NULL OR owner_id = $3)
+                AND status IS DISTINCT FROM $4
+                AND status IS DISTINCT FROM $5
+                AND archived_at IS NULL
+            ORDER BY due_date ASC
+            "#,
+            start,
+            end,
+            owner_id,
+            TaskStatus::Done.to_string(),
+            TaskStatus::Canceled.to_string(),
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Span::current().record("rows", tasks_info.len());
+
+        tasks_info.into_iter().map(Task::try_from).collect()
+    }
+
+    #[instrument(skip_all, fields(resource = "task", operation = "read", rows))]
+    async fn get_overdue_tasks(&self, owner_id: Option<Uuid>, now: DateTime<Utc>) -> Result<Vec<Task>, SDKError> {
+        let tasks_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            SELECT * FROM tasks
+            WHERE due_date < $1
+                AND ($2::uuid IS NULL OR owner_id = $2)
+                AND status IS DISTINCT FROM $3
+                AND status IS DISTINCT FROM $4
+                AND archived_at IS NULL
+            ORDER BY due_date ASC, id ASC
+            "#,
+            now,
+            owner_id,
+            TaskStatus::Done.to_string(),
+            TaskStatus::Canceled.to_string(),
+        )
+        .fetch_all(&mut *self.acquire_read().await?)
+        .await?;
+
+        Span::current().record("rows", tasks_info.len());
+
+        tasks_info.into_iter().map(Task::try_from).collect()
+    }
+}
+
+#[async_trait]
+impl TaskTransactionOperations for PgConnection {
+    async fn create_task(&mut self, input: CreateTaskInput) -> Result<Task, SDKError> {
+        insert_task(self, input).await.map(|(task, _)| task)
+    }
+
+    async fn delete_task(&mut self, id: Uuid) -> Result<Task, SDKError> {
+        let task_info = sqlx::query_as!(
+            TaskRecord,
+            r#"
+            DELETE FROM tasks WHERE id = $1
+            RETURNING *
+            "#,
+            id,
+        )
+        .fetch_one(&mut *self)
+        .await
+        .map_err(SDKError::or_not_found("task", id))?;
+
+        let task = Task::try_from(task_info)?;
+
+        Ok(task)
+    }
+}
+
+/// Inserts the task with its labels, assignees and subtasks, and reports whether it was created. A task
+/// whose `idempotency_key` is already taken is returned as stored, without touching its relations.
+pub(crate) async fn insert_task(conn: &mut PgConnection, input: CreateTaskInput) -> Result<(Task, bool), SDKError> {
+    if let Some(recurrence) = &input.recurrence {
+        Recurrence::from_str(recurrence)?;
+    }
+
+    validate_due_date(input.due_date, input.allow_past_due)?;
+
+    let mut tx = conn.begin().await?;
+
+    let owner_id = match &input.owner_email {
+        Some(owner_email) => {
+            find_or_create_member_by_email(&mut tx, owner_email, input.create_owner_if_missing.unwrap_or_default())
+                .await?
+        }
+        None => input.owner_id,
+    };
+
+    let task = sqlx::query_as!(
+        TaskRecord,
+        r#"
+        INSERT INTO tasks (id, title, description, owner_id, status, priority, due_date, project_id, lead_id, parent_id, recurrence, created_by, updated_by, idempotency_key)
+        VALUES (COALESCE($12, gen_random_uuid()), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $11, $13)
+        ON CONFLICT (idempotency_key) DO NOTHING
+        RETURNING *
+        "#,
+        input.title,
+        input.description,
+        owner_id,
+        input.status.unwrap_or_default().to_string(),
+        input.priority.unwrap_or_default().to_string(),
+        input.due_date,
+        input.project_id,
+        input.lead_id,
+        input.parent_id,
+        input.recurrence,
+        input.actor_id,
+        input.id,
+        input.idempotency_key,
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(task) = task else {
+        // read in a fresh statement, so a concurrent create holding the key is seen once it commits
+        let task_info = sqlx::query("SELECT * FROM tasks WHERE idempotency_key = $1")
+            .bind(&input.idempotency_key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        return Ok((task_from_row(&task_info)?, false));
+    };
+
+    if let Some(labels) = input.labels {
+        for label in labels {
+            sqlx::query!(
+                r#"
+                INSERT INTO labels_by_tasks (task_id, label_id)
+                VALUES ($1, $2)
+                "#,
+                task.id,
+                label,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    if let Some(assignees) = input.assignees {
+        for assignee in assignees {
+            sqlx::query!(
+                r#"
+                INSERT INTO tasks_by_assignees (task_id, assignee_id)
+                VALUES ($1, $2)
+                "#,
+                task.id,
+                assignee,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    if let Some(subtasks) = input.subtasks {
+        for mut subtask in subtasks {
+            // nesting defines the link, so a stray parent_id on a subtask can't detach it
+            subtask.parent_id = Some(task.id);
+            subtask.actor_id = subtask.actor_id.or(input.actor_id);
+
+            tx.create_task(subtask).await?;
+        }
+    }
+
+    // if let Some(assets) = input.assets {
+    //     for asset in assets {
+    //         sqlx::query!(
+    //             r#"
+    //             INSERT INTO assets_by_tasks (task_id, asset_id)
+    //             VALUES ($1, $2)
+    //             "#,
+    //             task.id,
+    //             asset,
+    //         )
+    //         .execute(&mut *tx)
+    //         .await?;
+    //     }
+    // }
+
+    tx.commit().await?;
+
+    let task = Task::try_from(task)?;
+
+    Ok((task, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_nested_filters_add_no_condition() {
+        let filter = GetTasksWhere {
+            _and: Some(vec![]),
+            _or: Some(vec![GetTasksWhere::default()]),
+            ..Default::default()
         };
 
-        let tasks_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
-
-        Ok(tasks_info
-            .iter()
-            .map(|task_info| Task {
-                id: task_info.get("id"),
-                created_at: task_info.get("created_at"),
-                updated_at: task_info.get("updated_at"),
-                title: task_info.get("title"),
-                description: task_info.get("description"),
-                status: task_info
-                    .get::<'_, Option<String>, _>("status")
-                    .and_then(|a| TaskStatus::from_str(&a).ok())
-                    .unwrap_or_default(),
-                priority: task_info
-                    .get::<'_, Option<String>, _>("priority")
-                    .and_then(|a| TaskPriority::from_str(&a).ok())
-                    .unwrap_or_default(),
-                // status: TaskStatus::from_optional_str(&task_info.get("status")),
-                // priority: TaskPriority::from_optional_str(&),
-                due_date: task_info.get("due_date"),
-                project_id: task_info.get("project_id"),
-                lead_id: task_info.get("lead_id"),
-                owner_id: task_info.get("owner_id"),
-                count: task_info.get("count"),
-                parent_id: task_info.get("parent_id"),
-            })
-            .collect())
+        assert_eq!(filter.compile_sql().0, "");
+    }
+
+    #[test]
+    fn empty_nested_filters_are_dropped_from_their_group() {
+        let owner_id = Uuid::new_v4();
+
+        let filter = GetTasksWhere {
+            status: Some(FilterOp::Eq(TaskStatus::Done)),
+            _and: Some(vec![
+                GetTasksWhere::default(),
+                GetTasksWhere {
+                    owner_id: Some(FilterOp::Eq(owner_id)),
+                    ..Default::default()
+                },
+            ]),
+            _or: Some(vec![]),
+            ..Default::default()
+        };
+
+        let (where_clause, args) = filter.compile_sql();
+
+        assert_eq!(where_clause, "status = $1 AND (owner_id = $2)");
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn raw_fragments_are_numbered_after_the_builder_fields() {
+        let owner_id = Uuid::new_v4();
+
+        let filter = GetTasksWhereBuilder::default()
+            .status(TaskStatus::Done)
+            .raw(RawFilter::new("position > $1", vec![SQLArgument::Text("3".to_string())]).unwrap())
+            ._and(vec![GetTasksWhere {
+                owner_id: Some(FilterOp::Eq(owner_id)),
+                ..Default::default()
+            }])
+            .build()
+            .unwrap();
+
+        let (where_clause, args) = filter.compile_sql();
+
+        assert_eq!(where_clause, "status = $1 AND (position > $2) AND (owner_id = $3)");
+        assert_eq!(
+            args,
+            vec![
+                SQLArgument::Text(TaskStatus::Done.to_string()),
+                SQLArgument::Text("3".to_string()),
+                SQLArgument::Uuid(owner_id),
+            ]
+        );
     }
 }
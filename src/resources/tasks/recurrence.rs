@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Months, TimeDelta, Utc};
+use strum_macros::{Display, EnumString};
+
+use crate::errors::sdk::SDKError;
+
+#[derive(Debug, Copy, Clone, Display, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Subset of an RFC 5545 RRULE, e.g. `FREQ=WEEKLY;INTERVAL=2`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Recurrence {
+    pub frequency: RecurrenceFrequency,
+    pub interval: u32,
+}
+
+impl FromStr for Recurrence {
+    type Err = SDKError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || SDKError::InvalidRecurrence(rule.to_string());
+
+        let mut frequency = None;
+        let mut interval = 1;
+
+        for part in rule.trim().trim_start_matches("RRULE:").split(';') {
+            let (key, value) = part.split_once('=').ok_or_else(invalid)?;
+
+            match key.trim().to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency =
+                        Some(RecurrenceFrequency::from_str(&value.trim().to_uppercase()).map_err(|_| invalid())?)
+                }
+                "INTERVAL" => interval = value.trim().parse::<u32>().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+
+        if interval == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Recurrence {
+            frequency: frequency.ok_or_else(invalid)?,
+            interval,
+        })
+    }
+}
+
+impl Recurrence {
+    /// The `n`-th occurrence after `anchor`, where `n = 0` is the anchor itself; `None` once it is past
+    /// the last representable date.
+    pub fn nth_after(&self, anchor: DateTime<Utc>, n: u32) -> Option<DateTime<Utc>> {
+        let steps = self.interval.checked_mul(n)?;
+
+        match self.frequency {
+            RecurrenceFrequency::Daily => {
+                TimeDelta::try_days(steps.into()).and_then(|days| anchor.checked_add_signed(days))
+            }
+            RecurrenceFrequency::Weekly => {
+                TimeDelta::try_weeks(steps.into()).and_then(|weeks| anchor.checked_add_signed(weeks))
+            }
+            RecurrenceFrequency::Monthly => anchor.checked_add_months(Months::new(steps)),
+        }
+    }
+
+    /// Occurrences strictly after `anchor` and up to `until` (inclusive).
+    pub fn occurrences(&self, anchor: DateTime<Utc>, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        (1..)
+            .map_while(|n| self.nth_after(anchor, n))
+            .take_while(|occurrence| *occurrence <= until)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn parses_frequency_and_interval() {
+        assert_eq!(
+            Recurrence::from_str("RRULE:FREQ=weekly;INTERVAL=2").unwrap(),
+            Recurrence {
+                frequency: RecurrenceFrequency::Weekly,
+                interval: 2,
+            }
+        );
+        assert_eq!(Recurrence::from_str("FREQ=DAILY").unwrap().interval, 1);
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        for rule in [
+            "",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "INTERVAL=2",
+            "FREQ=DAILY;COUNT=3",
+        ] {
+            assert!(
+                matches!(Recurrence::from_str(rule), Err(SDKError::InvalidRecurrence(_))),
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn weekly_occurrences_across_a_month() {
+        let anchor = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2026, 3, 31, 23, 59, 59).unwrap();
+
+        let occurrences = Recurrence::from_str("FREQ=WEEKLY").unwrap().occurrences(anchor, until);
+
+        assert_eq!(
+            occurrences,
+            [9, 16, 23, 30].map(|day| Utc.with_ymd_and_hms(2026, 3, day, 9, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn monthly_occurrences_clamp_to_the_end_of_shorter_months() {
+        let anchor = Utc.with_ymd_and_hms(2026, 1, 31, 0, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2026, 3, 31, 0, 0, 0).unwrap();
+
+        let occurrences = Recurrence::from_str("FREQ=MONTHLY").unwrap().occurrences(anchor, until);
+
+        assert_eq!(
+            occurrences,
+            [
+                Utc.with_ymd_and_hms(2026, 2, 28, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 3, 31, 0, 0, 0).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn huge_intervals_end_the_occurrences_instead_of_panicking() {
+        let anchor = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2026, 12, 31, 0, 0, 0).unwrap();
+
+        for rule in [
+            "FREQ=DAILY;INTERVAL=200000000",
+            "FREQ=WEEKLY;INTERVAL=4294967295",
+            "FREQ=MONTHLY;INTERVAL=4294967295",
+        ] {
+            let recurrence = Recurrence::from_str(rule).unwrap();
+
+            assert_eq!(recurrence.nth_after(anchor, 1), None, "{rule}");
+            assert!(recurrence.occurrences(anchor, until).is_empty(), "{rule}");
+        }
+    }
+}
@@ -75,7 +75,7 @@ impl TaskRelations for Task {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -93,25 +93,16 @@ impl TaskRelations for Task {
     }
 
     async fn labels(&self, loaders: &SDKLoaders) -> Result<Vec<Label>, SDKError> {
-        let ids: Vec<Uuid> = sqlx::query!(
-            r#"
-            SELECT label_id FROM labels_by_tasks
-            WHERE task_id = $1
-            "#,
-            &self.id
-        )
-        .fetch_all(&*loaders.engine.db_pool)
-        .await
-        .unwrap()
-        .into_iter()
-        .map(|id| id.label_id)
-        .collect();
+        let ids = loaders
+            .task_labels_loader
+            .load_one(self.id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
 
         let labels_map = loaders.label_loader.load_many(ids.clone()).await.unwrap();
 
-        let labels: &Vec<Label> = &ids.into_iter().map(|id| labels_map.get(&id).unwrap().clone()).collect();
-
-        Ok(labels.clone())
+        Ok(ids.into_iter().filter_map(|id| labels_map.get(&id).cloned()).collect())
     }
 
     async fn subtasks(&self, loaders: &SDKLoaders) -> Result<Vec<Task>, SDKError> {
@@ -122,7 +113,7 @@ impl TaskRelations for Task {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -146,7 +137,7 @@ impl TaskRelations for Task {
         WHERE resource_id = $1"#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap();
 
@@ -1,14 +1,19 @@
+use std::str::FromStr;
+
 use async_graphql::{Enum, SimpleObject};
 use chrono::{DateTime, Utc};
 
 use poem_openapi::Object;
-use strum_macros::{Display, EnumString};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
 use uuid::Uuid;
 
+use crate::errors::sdk::SDKError;
+
 use poem_openapi::Enum as OpenApiEnum;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, SimpleObject, Object, Clone, Serialize)]
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
 #[graphql(name = "SDKTask")]
 pub struct Task {
     pub id: Uuid,
@@ -27,12 +32,23 @@ pub struct Task {
     pub project_id: Option<Uuid>,
     pub lead_id: Option<Uuid>,
     pub parent_id: Option<Uuid>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub recurrence: Option<String>,
+    pub position: i32,
+}
+
+/// A task together with its whole subtree, children ordered by position.
+#[derive(Debug, SimpleObject, Object, Clone, Serialize, Deserialize)]
+#[graphql(name = "SDKTaskNode")]
+pub struct TaskNode {
+    pub task: Task,
+    pub children: Vec<TaskNode>,
 }
 
 #[derive(
-    Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumString, Deserialize, Serialize, Eq, PartialEq,
+    Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumIter, Deserialize, Serialize, Eq, PartialEq, Hash,
 )]
-
+#[graphql(rename_items = "SCREAMING_SNAKE_CASE")]
 pub enum TaskStatus {
     #[default]
     None,
@@ -44,10 +60,47 @@ pub enum TaskStatus {
     Canceled,
 }
 
-#[derive(
-    Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumString, Deserialize, Serialize, Eq, PartialEq,
-)]
+/// Whether `value` names `variant` in any of its spellings: Rust and SQL (`InProgress`) or GraphQL (`IN_PROGRESS`).
+fn is_variant_name(variant: &str, value: &str) -> bool {
+    let normalize = |name: &str| name.trim().replace('_', "").to_ascii_lowercase();
+
+    normalize(variant) == normalize(value)
+}
 
+/// `Display` gives the SQL spelling; parsing accepts the GraphQL one as well and rejects unknown names.
+impl FromStr for TaskStatus {
+    type Err = SDKError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        TaskStatus::iter()
+            .find(|status| is_variant_name(&status.to_string(), value))
+            .ok_or_else(|| SDKError::InvalidTaskStatus(value.to_string()))
+    }
+}
+
+impl TaskStatus {
+    /// Moving to or from `None` and staying on the same status are always allowed.
+    pub fn can_transition_to(&self, next: TaskStatus) -> bool {
+        use TaskStatus::*;
+
+        if *self == next || *self == None || next == None {
+            return true;
+        }
+
+        match self {
+            Draft => matches!(next, Backlog | ToDo | Canceled),
+            Backlog => matches!(next, Draft | ToDo | InProgress | Canceled),
+            ToDo => matches!(next, Backlog | InProgress | Canceled),
+            InProgress => matches!(next, Backlog | ToDo | Done | Canceled),
+            Done => matches!(next, ToDo | InProgress),
+            Canceled => matches!(next, Backlog | ToDo),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Enum, OpenApiEnum, Copy, Clone, Default, Display, EnumIter, Deserialize, Serialize, Eq, PartialEq)]
+#[graphql(rename_items = "SCREAMING_SNAKE_CASE")]
 pub enum TaskPriority {
     #[default]
     None,
@@ -56,3 +109,101 @@ pub enum TaskPriority {
     High,
     Urgent,
 }
+
+/// `Display` gives the SQL spelling; parsing accepts the GraphQL one as well and rejects unknown names.
+impl FromStr for TaskPriority {
+    type Err = SDKError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        TaskPriority::iter()
+            .find(|priority| is_variant_name(&priority.to_string(), value))
+            .ok_or_else(|| SDKError::InvalidTaskPriority(value.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_follow_the_workflow() {
+        use TaskStatus::*;
+
+        assert!(ToDo.can_transition_to(InProgress));
+        assert!(InProgress.can_transition_to(Done));
+        assert!(Done.can_transition_to(InProgress));
+        assert!(Canceled.can_transition_to(ToDo));
+
+        assert!(!Canceled.can_transition_to(InProgress));
+        assert!(!Canceled.can_transition_to(Done));
+        assert!(!Draft.can_transition_to(Done));
+        assert!(!ToDo.can_transition_to(Done));
+        assert!(!Done.can_transition_to(Canceled));
+    }
+
+    #[test]
+    fn enums_round_trip_through_json() {
+        for status in TaskStatus::iter() {
+            let json = serde_json::to_string(&status).unwrap();
+
+            assert_eq!(json, format!("\"{}\"", status));
+            assert_eq!(serde_json::from_str::<TaskStatus>(&json).unwrap(), status);
+        }
+
+        for priority in TaskPriority::iter() {
+            let json = serde_json::to_string(&priority).unwrap();
+
+            assert_eq!(json, format!("\"{}\"", priority));
+            assert_eq!(serde_json::from_str::<TaskPriority>(&json).unwrap(), priority);
+        }
+    }
+
+    #[test]
+    fn none_and_unchanged_statuses_are_always_allowed() {
+        for status in TaskStatus::iter() {
+            assert!(status.can_transition_to(status));
+            assert!(status.can_transition_to(TaskStatus::None));
+            assert!(TaskStatus::None.can_transition_to(status));
+        }
+    }
+
+    #[test]
+    fn sql_and_graphql_names_round_trip_for_every_variant() {
+        use async_graphql::{InputType, Name, Value};
+
+        for status in TaskStatus::iter() {
+            let Value::Enum(graphql_name) = status.to_value() else {
+                panic!("{} is not a GraphQL enum value", status);
+            };
+
+            assert_eq!(TaskStatus::from_str(&status.to_string()).unwrap(), status);
+            assert_eq!(TaskStatus::from_str(&graphql_name).unwrap(), status);
+            assert_eq!(TaskStatus::parse(Some(Value::Enum(graphql_name))).unwrap(), status);
+        }
+
+        for priority in TaskPriority::iter() {
+            let Value::Enum(graphql_name) = priority.to_value() else {
+                panic!("{} is not a GraphQL enum value", priority);
+            };
+
+            assert_eq!(TaskPriority::from_str(&priority.to_string()).unwrap(), priority);
+            assert_eq!(TaskPriority::from_str(&graphql_name).unwrap(), priority);
+            assert_eq!(TaskPriority::parse(Some(Value::Enum(graphql_name))).unwrap(), priority);
+        }
+
+        assert_eq!(TaskStatus::InProgress.to_string(), "InProgress");
+        assert_eq!(TaskStatus::InProgress.to_value(), Value::Enum(Name::new("IN_PROGRESS")));
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert!(matches!(
+            TaskStatus::from_str("Sideways"),
+            Err(SDKError::InvalidTaskStatus(value)) if value == "Sideways"
+        ));
+        assert!(matches!(
+            TaskPriority::from_str(""),
+            Err(SDKError::InvalidTaskPriority(value)) if value.is_empty()
+        ));
+    }
+}
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::{
     backend::engine::SDKEngine,
-    common::commons::{SortOrder, UpdateListInput},
+    common::commons::{clamp_page, SortOrder, UpdateListInput},
     errors::sdk::SDKError,
 };
 
@@ -153,7 +153,7 @@ impl GetTeamsWhere {
 #[async_trait]
 impl TeamCrudOperations for SDKEngine {
     async fn create_team(&self, input: CreateTeamInput) -> Result<Team, SDKError> {
-        let mut tx = self.db_pool.begin().await?;
+        let mut tx = self.begin().await?;
 
         let team_final_info = sqlx::query!(
             r#"
@@ -226,8 +226,9 @@ impl TeamCrudOperations for SDKEngine {
             "#,
             id
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire_read().await?)
+        .await
+        .map_err(SDKError::or_not_found("team", id))?;
 
         let team = Team {
             id: team_info.id,
@@ -260,15 +261,16 @@ impl TeamCrudOperations for SDKEngine {
             query.push_str(format!("{} ", sort_order).as_str());
         }
 
-        if let Some(limit) = input.limit {
-            query.push_str(format!("LIMIT {} ", limit).as_str());
-        }
+        let limit = clamp_page(input.limit, input.offset, self.config.max_page_size)?;
+        query.push_str(format!("LIMIT {} ", limit).as_str());
 
         if let Some(offset) = input.offset {
             query.push_str(format!("OFFSET {} ", offset).as_str());
         }
 
-        let teams_info = sqlx::query(query.as_str()).fetch_all(self.db_pool.as_ref()).await?;
+        let teams_info = sqlx::query(query.as_str())
+            .fetch_all(&mut *self.acquire_read().await?)
+            .await?;
 
         let teams = teams_info
             .iter()
@@ -290,7 +292,7 @@ impl TeamCrudOperations for SDKEngine {
     }
 
     async fn update_team(&self, id: Uuid, input: UpdateTeamInput) -> Result<Team, SDKError> {
-        let mut tx = self.db_pool.begin().await?;
+        let mut tx = self.begin().await?;
 
         let team_final_info = sqlx::query!(
             r#"
@@ -311,7 +313,8 @@ impl TeamCrudOperations for SDKEngine {
             id,
         )
         .fetch_one(&mut *tx)
-        .await?;
+        .await
+        .map_err(SDKError::or_not_found("team", id))?;
 
         if let Some(members) = input.members {
             for member_id in members.add {
@@ -396,8 +399,9 @@ impl TeamCrudOperations for SDKEngine {
             "#,
             id
         )
-        .fetch_one(self.db_pool.as_ref())
-        .await?;
+        .fetch_one(&mut *self.acquire().await?)
+        .await
+        .map_err(SDKError::or_not_found("team", id))?;
 
         let team = Team {
             id: team_info.id,
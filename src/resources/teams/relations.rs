@@ -31,7 +31,7 @@ impl TeamRelations for Team {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
@@ -56,7 +56,7 @@ impl TeamRelations for Team {
             "#,
             &self.id
         )
-        .fetch_all(&*loaders.engine.db_pool)
+        .fetch_all(&mut *loaders.engine.acquire_read().await?)
         .await
         .unwrap()
         .into_iter()
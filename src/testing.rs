@@ -0,0 +1,457 @@
+//! Disposable databases for integration tests, behind the `test-util` feature.
+//!
+//! Each [`TestDatabase`] is a brand new, fully migrated database on the server named by
+//! `PLEXO_TEST_DATABASE_URL`, so tests can run in parallel without seeing each other's rows.
+//! [`MockLlm`] stands in for the LLM provider so cognition can be tested offline, and [`TraceCapture`]
+//! records the spans and queries an operation emits.
+
+use std::{
+    collections::BTreeMap,
+    env::var,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_openai::{config::OpenAIConfig, Client};
+use serde_json::{json, Value};
+use sqlx::{Connection, Executor, PgConnection};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{
+    dispatcher::DefaultGuard,
+    field::{Field, Visit},
+    span, Dispatch, Event, Metadata, Subscriber,
+};
+use tracing_core::span::Current;
+use uuid::Uuid;
+
+use crate::{
+    backend::engine::{LlmProvider, SDKConfigBuilder, SDKEngine},
+    errors::sdk::SDKError,
+};
+
+/// Connection string of the server test databases are created on. Its role needs `CREATEDB`, and the
+/// database it names is only used to create and drop the test ones.
+pub const TEST_DATABASE_URL_VAR: &str = "PLEXO_TEST_DATABASE_URL";
+
+pub struct TestDatabase {
+    pub engine: SDKEngine,
+    admin_url: String,
+    name: String,
+}
+
+impl TestDatabase {
+    /// Creates a database on the server of `PLEXO_TEST_DATABASE_URL`; see [`TestDatabase::with_url`].
+    pub async fn new() -> Result<TestDatabase, SDKError> {
+        Self::with_url(admin_url()?).await
+    }
+
+    /// Like [`TestDatabase::new`], with `configure` applied to the engine's config, e.g. to shrink
+    /// `max_page_size` or the pool.
+    pub async fn with_config(
+        configure: impl FnOnce(SDKConfigBuilder) -> SDKConfigBuilder,
+    ) -> Result<TestDatabase, SDKError> {
+        Self::create(admin_url()?, configure).await
+    }
+
+    /// Creates `plexo_test_<uuid>` on the server of `admin_url`, applies the migrations and connects an
+    /// engine to it. Cognition is configured with a dummy key, so LLM calls fail.
+    pub async fn with_url(admin_url: impl Into<String>) -> Result<TestDatabase, SDKError> {
+        Self::create(admin_url.into(), |builder| builder).await
+    }
+
+    async fn create(
+        admin_url: String,
+        configure: impl FnOnce(SDKConfigBuilder) -> SDKConfigBuilder,
+    ) -> Result<TestDatabase, SDKError> {
+        let name = format!("plexo_test_{}", Uuid::new_v4().simple());
+
+        let config = configure(test_config(with_database(&admin_url, &name))).build()?;
+
+        let mut admin = PgConnection::connect(&admin_url).await?;
+        admin.execute(format!(r#"CREATE DATABASE "{}""#, name).as_str()).await?;
+        admin.close().await?;
+
+        match SDKEngine::new(config).await {
+            Ok(engine) => Ok(TestDatabase {
+                engine,
+                admin_url,
+                name,
+            }),
+            Err(err) => {
+                // don't leave the half-made database behind
+                drop_database(&admin_url, &name).await?;
+                Err(err)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Closes the engine and drops the database. Without it the database outlives the test, as there is
+    /// no async drop to do it automatically.
+    pub async fn teardown(self) -> Result<(), SDKError> {
+        self.engine.close().await?;
+
+        drop_database(&self.admin_url, &self.name).await
+    }
+}
+
+fn admin_url() -> Result<String, SDKError> {
+    var(TEST_DATABASE_URL_VAR).map_err(|_| SDKError::InvalidConfig(format!("{} is not set", TEST_DATABASE_URL_VAR)))
+}
+
+async fn drop_database(admin_url: &str, name: &str) -> Result<(), SDKError> {
+    let mut admin = PgConnection::connect(admin_url).await?;
+    admin
+        .execute(format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, name).as_str())
+        .await?;
+    admin.close().await?;
+
+    Ok(())
+}
+
+/// `url` with its database, the path after the host, replaced by `name`; the query string is kept.
+fn with_database(url: &str, name: &str) -> String {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+
+    let authority_start = base.find("://").map_or(0, |scheme_end| scheme_end + 3);
+    let server = match base[authority_start..].find('/') {
+        Some(path_start) => &base[..authority_start + path_start],
+        None => base,
+    };
+
+    match query {
+        Some(query) => format!("{}/{}?{}", server, name, query),
+        None => format!("{}/{}", server, name),
+    }
+}
+
+fn test_config(database_url: String) -> SDKConfigBuilder {
+    SDKConfigBuilder::default()
+        .database_url(database_url)
+        .llm_api_key("test")
+        .llm_max_attempts(1)
+        .max_connections(5)
+        .acquire_timeout_secs(30)
+        .auto_migrate(true)
+}
+
+/// Token counts the mock reports for every answer.
+pub const MOCK_PROMPT_TOKENS: u32 = 10;
+pub const MOCK_COMPLETION_TOKENS: u32 = 5;
+
+/// An OpenAI-compatible endpoint on localhost that answers with canned replies, in order, repeating
+/// the last one once they run out. Every request body it receives is recorded, along with the most
+/// requests it was handling at once.
+///
+/// Chat requests get the reply as the message content; embedding requests get it parsed as the
+/// embedding, so a reply for them reads like `[0.1, 0.2]`.
+pub struct MockLlm {
+    url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+    peak_concurrency: Arc<AtomicUsize>,
+}
+
+impl MockLlm {
+    pub async fn start(replies: Vec<String>) -> Result<MockLlm, SDKError> {
+        Self::start_with_delay(replies, Duration::ZERO).await
+    }
+
+    /// Like [`MockLlm::start`], but every answer takes `delay`, standing in for a slow provider.
+    pub async fn start_with_delay(replies: Vec<String>, delay: Duration) -> Result<MockLlm, SDKError> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| SDKError::unavailable(err.to_string()))?;
+        let url = format!(
+            "http://{}/v1",
+            listener
+                .local_addr()
+                .map_err(|err| SDKError::unavailable(err.to_string()))?
+        );
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak_concurrency = Arc::new(AtomicUsize::new(0));
+        let peak = peak_concurrency.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let replies = replies.clone();
+                let in_flight = in_flight.clone();
+                let peak = peak.clone();
+
+                tokio::spawn(async move {
+                    let Some((stream, path, body)) = read_request(stream).await else {
+                        return;
+                    };
+
+                    let handling = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(handling, Ordering::SeqCst);
+
+                    let reply = {
+                        let mut requests = recorded.lock().unwrap();
+                        requests.push(body);
+
+                        replies
+                            .get(requests.len() - 1)
+                            .or(replies.last())
+                            .cloned()
+                            .unwrap_or_default()
+                    };
+
+                    let response = match path.ends_with("/embeddings") {
+                        true => embedding_body(&reply),
+                        false => completion_body(&reply),
+                    };
+
+                    tokio::time::sleep(delay).await;
+
+                    let _ = write_response(stream, &response).await;
+
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(MockLlm {
+            url,
+            requests,
+            peak_concurrency,
+        })
+    }
+
+    /// Points the engine's LLM client at the mock.
+    pub fn install(&self, engine: &mut SDKEngine) {
+        engine.config.llm_provider = LlmProvider::OpenAI;
+        *engine.llm_client = Client::with_config(OpenAIConfig::new().with_api_base(&self.url).with_api_key("test"));
+    }
+
+    /// The base URL the mock listens on. Any JSON POST under it is recorded, so it also stands in for a webhook endpoint.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The JSON bodies of the requests received so far.
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The most requests the mock was answering at the same time.
+    pub fn peak_concurrency(&self) -> usize {
+        self.peak_concurrency.load(Ordering::SeqCst)
+    }
+}
+
+async fn read_request(stream: TcpStream) -> Option<(TcpStream, String, Value)> {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await.ok()?;
+
+    let path = request_line.split_whitespace().nth(1)?.to_string();
+
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some((
+        reader.into_inner(),
+        path,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    ))
+}
+
+fn completion_body(content: &str) -> String {
+    json!({
+        "id": "mock",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "mock",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop",
+        }],
+        "usage": {
+            "prompt_tokens": MOCK_PROMPT_TOKENS,
+            "completion_tokens": MOCK_COMPLETION_TOKENS,
+            "total_tokens": MOCK_PROMPT_TOKENS + MOCK_COMPLETION_TOKENS,
+        },
+    })
+    .to_string()
+}
+
+fn embedding_body(embedding: &str) -> String {
+    json!({
+        "object": "list",
+        "model": "mock",
+        "data": [{
+            "object": "embedding",
+            "index": 0,
+            "embedding": serde_json::from_str::<Value>(embedding).unwrap_or(Value::Null),
+        }],
+        "usage": {
+            "prompt_tokens": MOCK_PROMPT_TOKENS,
+            "total_tokens": MOCK_PROMPT_TOKENS,
+        },
+    })
+    .to_string()
+}
+
+async fn write_response(mut stream: TcpStream, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// A span seen by [`TraceCapture`], with every field recorded on it so far, formatted with `Debug`.
+#[derive(Debug, Clone)]
+pub struct CapturedSpan {
+    pub name: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+struct Captured {
+    spans: Vec<(CapturedSpan, &'static Metadata<'static>)>,
+    // entered spans, innermost last, so `Span::current().record(..)` reaches the right one
+    entered: Vec<span::Id>,
+    queries: usize,
+}
+
+/// A `tracing` subscriber that keeps every span and counts the statements sqlx executes.
+///
+/// Only the thread that called [`TraceCapture::install`] reports to it, so use it from a
+/// `#[tokio::test]` on the default current-thread runtime.
+#[derive(Clone, Default)]
+pub struct TraceCapture(Arc<Mutex<Captured>>);
+
+impl TraceCapture {
+    /// Makes the capture the thread's default subscriber until the guard is dropped.
+    pub fn install(&self) -> DefaultGuard {
+        tracing::dispatcher::set_default(&Dispatch::new(self.clone()))
+    }
+
+    pub fn spans(&self) -> Vec<CapturedSpan> {
+        self.0
+            .lock()
+            .unwrap()
+            .spans
+            .iter()
+            .map(|(span, _)| span.clone())
+            .collect()
+    }
+
+    pub fn queries(&self) -> usize {
+        self.0.lock().unwrap().queries
+    }
+}
+
+struct FieldRecorder<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldRecorder<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl Subscriber for TraceCapture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+        let mut captured = self.0.lock().unwrap();
+
+        let mut fields = BTreeMap::new();
+        attributes.record(&mut FieldRecorder(&mut fields));
+
+        captured.spans.push((
+            CapturedSpan {
+                name: attributes.metadata().name().to_string(),
+                fields,
+            },
+            attributes.metadata(),
+        ));
+
+        // ids must be non-zero, so span `n` is stored at index `n - 1`
+        span::Id::from_u64(captured.spans.len() as u64)
+    }
+
+    fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+        let mut captured = self.0.lock().unwrap();
+
+        if let Some((span, _)) = captured.spans.get_mut(id.into_u64() as usize - 1) {
+            values.record(&mut FieldRecorder(&mut span.fields));
+        }
+    }
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        if event.metadata().target() == "sqlx::query" {
+            self.0.lock().unwrap().queries += 1;
+        }
+    }
+
+    fn enter(&self, id: &span::Id) {
+        self.0.lock().unwrap().entered.push(id.clone());
+    }
+
+    fn exit(&self, id: &span::Id) {
+        let mut captured = self.0.lock().unwrap();
+
+        if let Some(position) = captured.entered.iter().rposition(|entered| entered == id) {
+            captured.entered.remove(position);
+        }
+    }
+
+    fn current_span(&self) -> Current {
+        let captured = self.0.lock().unwrap();
+
+        match captured.entered.last() {
+            Some(id) => Current::new(id.clone(), captured.spans[id.into_u64() as usize - 1].1),
+            None => Current::none(),
+        }
+    }
+}
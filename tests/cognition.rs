@@ -0,0 +1,736 @@
+#![cfg(feature = "test-util")]
+
+use std::{
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use chrono::{TimeZone, Utc};
+use futures::future::join_all;
+use plexo_sdk::{
+    cognition::{
+        embeddings::TaskEmbeddingOperations,
+        operations::{
+            CognitionOperations, SubdivideTaskInputBuilder, TaskSuggestionBuilder, TaskSuggestionInputBuilder,
+            MAX_COGNITION_CONTEXT_SIZE,
+        },
+        prompts::CognitionPrompts,
+        suggestions::CognitionCapabilities,
+    },
+    errors::sdk::SDKError,
+    resources::{
+        labels::operations::{CreateLabelInputBuilder, LabelCrudOperations},
+        members::{
+            member::MemberRole,
+            operations::{CreateMemberInputBuilder, MemberCrudOperations},
+        },
+        projects::operations::{CreateProjectInputBuilder, ProjectCrudOperations},
+        tasks::{
+            extensions::{CreateTasksInput, TasksExtensionOperations},
+            operations::{CreateTaskInputBuilder, TaskCrudOperations},
+            task::{Task, TaskPriority, TaskStatus},
+        },
+    },
+    testing::{MockLlm, TestDatabase, MOCK_COMPLETION_TOKENS, MOCK_PROMPT_TOKENS},
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn create_task(db: &TestDatabase, title: &str) -> Task {
+    let owner = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name(format!("owner of {}", title))
+                .email(format!("{}@example.com", title.replace(' ', ".")))
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    db.engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title(title.to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+fn suggestion_reply(title: &str) -> Value {
+    json!({
+        "title": title,
+        "description": format!("{} in detail", title),
+        "status": "ToDo",
+        "priority": "Medium",
+        "due_date": "2030-01-01T00:00:00Z",
+    })
+}
+
+#[tokio::test]
+async fn chat_completion_json_reprompts_after_unparsable_answers() {
+    let mut db = TestDatabase::with_config(|config| config.llm_max_attempts(2))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start(vec![
+        "Sure! Here is what you asked for.".to_string(),
+        "Here you go:\n```json\n{\"title\": \"write docs\"}\n```".to_string(),
+    ])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    let (answer, _) = db
+        .engine
+        .chat_completion_json::<Value>("system".to_string(), "user".to_string(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(answer["title"], "write docs");
+
+    let requests = llm.requests();
+
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1]["messages"][1]["content"]
+        .as_str()
+        .unwrap()
+        .contains("could not be parsed"));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn chat_completion_json_gives_up_after_the_last_attempt() {
+    let mut db = TestDatabase::with_config(|config| config.llm_max_attempts(3))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start(vec!["no JSON here".to_string()]).await.unwrap();
+    llm.install(&mut db.engine);
+
+    let result = db
+        .engine
+        .chat_completion_json::<Value>("system".to_string(), "user".to_string(), None)
+        .await;
+
+    assert!(matches!(result, Err(SDKError::SerdeJSONError(_))));
+    assert_eq!(llm.requests().len(), 3);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn chat_completion_clamps_out_of_range_temperatures() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let llm = MockLlm::start(vec!["{}".to_string()]).await.unwrap();
+    llm.install(&mut db.engine);
+
+    for temperature in [5.0, -1.0, 0.7] {
+        db.engine
+            .chat_completion("system".to_string(), "user".to_string(), Some(temperature))
+            .await
+            .unwrap();
+    }
+
+    db.engine
+        .chat_completion("system".to_string(), "user".to_string(), None)
+        .await
+        .unwrap();
+
+    let temperatures: Vec<Option<f64>> = llm
+        .requests()
+        .iter()
+        .map(|request| request["temperature"].as_f64())
+        .collect();
+
+    assert_eq!(temperatures.len(), 4);
+    assert_eq!(temperatures[0], Some(2.0));
+    assert_eq!(temperatures[1], Some(0.0));
+    assert!((temperatures[2].unwrap() - 0.7).abs() < 1e-6);
+    // unset leaves the provider's default
+    assert_eq!(temperatures[3], None);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn cognition_results_report_token_usage() {
+    let mut db = TestDatabase::with_config(|config| config.llm_max_attempts(2))
+        .await
+        .unwrap();
+
+    let task = create_task(&db, "launch").await;
+
+    let llm = MockLlm::start(vec![
+        json!([suggestion_reply("write docs"), suggestion_reply("ship it")]).to_string(),
+        "not JSON".to_string(),
+        suggestion_reply("announce").to_string(),
+    ])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    let result = db
+        .engine
+        .subdivide_task(
+            SubdivideTaskInputBuilder::default()
+                .task_id(task.id)
+                .subtasks(2)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.subtasks.len(), 2);
+    assert_eq!(result.usage.prompt_tokens, MOCK_PROMPT_TOKENS);
+    assert_eq!(result.usage.completion_tokens, MOCK_COMPLETION_TOKENS);
+
+    // the unparsable first answer is paid for as well
+    let suggestion = db
+        .engine
+        .get_suggestions(TaskSuggestionInputBuilder::default().build().unwrap())
+        .await
+        .unwrap();
+    let usage = suggestion.usage.unwrap();
+
+    assert_eq!(suggestion.title, "announce");
+    assert_eq!(usage.prompt_tokens, 2 * MOCK_PROMPT_TOKENS);
+    assert_eq!(usage.completion_tokens, 2 * MOCK_COMPLETION_TOKENS);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn suggestions_missing_a_field_are_incomplete() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let task = create_task(&db, "launch").await;
+
+    let mut without_priority = suggestion_reply("write docs");
+    without_priority.as_object_mut().unwrap().remove("priority");
+
+    let mut without_status = suggestion_reply("ship it");
+    without_status.as_object_mut().unwrap().remove("status");
+
+    let llm = MockLlm::start(vec![
+        without_priority.to_string(),
+        json!([suggestion_reply("announce"), without_status]).to_string(),
+    ])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    assert!(matches!(
+        db.engine
+            .get_suggestions(TaskSuggestionInputBuilder::default().build().unwrap())
+            .await,
+        Err(SDKError::IncompleteSuggestion { field: "priority" })
+    ));
+
+    assert!(matches!(
+        db.engine
+            .subdivide_task(
+                SubdivideTaskInputBuilder::default()
+                    .task_id(task.id)
+                    .subtasks(2)
+                    .build()
+                    .unwrap(),
+            )
+            .await,
+        Err(SDKError::IncompleteSuggestion { field: "status" })
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn find_similar_tasks_ranks_the_closest_first() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let reference = create_task(&db, "write docs").await;
+    let close = create_task(&db, "write guides").await;
+    let far = create_task(&db, "fix the build").await;
+
+    // one embedding per request: `far`, `close`, then the reference, embedded on demand
+    let llm = MockLlm::start(vec![
+        "[0.0, 1.0, 0.0]".to_string(),
+        "[0.9, 0.1, 0.0]".to_string(),
+        "[1.0, 0.0, 0.0]".to_string(),
+    ])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    db.engine.embed_task(far.id).await.unwrap();
+    db.engine.embed_task(close.id).await.unwrap();
+
+    let similar = db.engine.find_similar_tasks(reference.id, 10).await.unwrap();
+
+    assert_eq!(llm.requests().len(), 3);
+    assert_eq!(similar.len(), 2);
+    assert_eq!(similar[0].0.id, close.id);
+    assert_eq!(similar[1].0.id, far.id);
+    assert!(similar[0].1 > similar[1].1);
+
+    let closest = db.engine.find_similar_tasks(reference.id, 1).await.unwrap();
+
+    // the reference embedding is stored now, so no new request is made
+    assert_eq!(llm.requests().len(), 3);
+    assert_eq!(closest.len(), 1);
+    assert_eq!(closest[0].0.id, close.id);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn identical_prompts_are_answered_from_the_cache() {
+    let mut db = TestDatabase::with_config(|config| config.llm_cache_capacity(NonZeroUsize::new(8).unwrap()))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start(vec![
+        suggestion_reply("write docs").to_string(),
+        suggestion_reply("ship it").to_string(),
+    ])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    let ask = |user_message: &str| {
+        db.engine
+            .chat_completion_json::<Value>("system".to_string(), user_message.to_string(), None)
+    };
+
+    let (first, first_usage) = ask("user").await.unwrap();
+    let (second, second_usage) = ask("user").await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(llm.requests().len(), 1);
+    assert_eq!(first_usage.prompt_tokens, MOCK_PROMPT_TOKENS);
+    // a cached answer costs nothing
+    assert_eq!(second_usage.prompt_tokens, 0);
+
+    let (other, _) = ask("another user").await.unwrap();
+
+    assert_eq!(other["title"], "ship it");
+    assert_eq!(llm.requests().len(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn custom_prompt_templates_shape_the_user_message() {
+    let prompts = CognitionPrompts {
+        suggestion_user: "Plan the next sprint item.\nBacklog:\n{context}\nDraft:\n{task}".to_string(),
+        ..Default::default()
+    };
+
+    let mut db = TestDatabase::with_config(|config| config.cognition_prompts(prompts))
+        .await
+        .unwrap();
+
+    create_task(&db, "launch").await;
+
+    let llm = MockLlm::start(vec![suggestion_reply("write docs").to_string()])
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    db.engine
+        .get_suggestions(
+            TaskSuggestionInputBuilder::default()
+                .title("write docs".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let requests = llm.requests();
+    let user_message = requests[0]["messages"][1]["content"].as_str().unwrap();
+
+    assert!(user_message.starts_with("Plan the next sprint item.\nBacklog:\n"));
+    assert!(user_message.contains("\"title\":\"launch\""));
+    assert!(user_message.ends_with("Draft:\nTask Title: write docs\nTask Description: <suggest>\nTask Status: <suggest>\nTask Priority: <suggest>\nTask Due Date: <suggest>"));
+    assert!(!user_message.contains("Current Time"));
+
+    db.teardown().await.unwrap();
+}
+
+/// Number of existing tasks the model was shown as context in the first request.
+fn context_task_count(llm: &MockLlm) -> usize {
+    let requests = llm.requests();
+    let user_message = requests[0]["messages"][1]["content"].as_str().unwrap();
+
+    user_message.matches("\"owner_id\":").count()
+}
+
+#[tokio::test]
+async fn the_configured_context_size_limits_the_context_tasks() {
+    let suggest = || {
+        TaskSuggestionInputBuilder::default()
+            .title("write docs".to_string())
+            .build()
+            .unwrap()
+    };
+
+    let mut db = TestDatabase::with_config(|config| config.cognition_context_size(2))
+        .await
+        .unwrap();
+
+    let owner = create_task(&db, "launch").await.owner_id;
+    let tasks = (0..MAX_COGNITION_CONTEXT_SIZE)
+        .map(|i| {
+            CreateTaskInputBuilder::default()
+                .title(format!("task {}", i))
+                .owner_id(owner)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine
+        .create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start(vec![suggestion_reply("write docs").to_string()])
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    db.engine.get_suggestions(suggest()).await.unwrap();
+
+    assert_eq!(context_task_count(&llm), 2);
+
+    // sizes past the cap are clamped, even with more tasks available
+    db.engine.config.cognition_context_size = MAX_COGNITION_CONTEXT_SIZE * 10;
+
+    let llm = MockLlm::start(vec![suggestion_reply("write docs").to_string()])
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    db.engine.get_suggestions(suggest()).await.unwrap();
+
+    assert_eq!(context_task_count(&llm), MAX_COGNITION_CONTEXT_SIZE as usize);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn subdivide_task_recursive_builds_a_tree_of_the_given_depth() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let task = create_task(&db, "launch").await;
+
+    let llm = MockLlm::start(vec![json!([
+        suggestion_reply("write docs"),
+        suggestion_reply("ship it")
+    ])
+    .to_string()])
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    let input = || {
+        SubdivideTaskInputBuilder::default()
+            .task_id(task.id)
+            .subtasks(2)
+            .build()
+            .unwrap()
+    };
+
+    let tree = db.engine.subdivide_task_recursive(input(), 2).await.unwrap();
+
+    assert_eq!(tree.len(), 2);
+    for node in &tree {
+        assert_eq!(node.children.len(), 2);
+        for child in &node.children {
+            assert_eq!(child.children.len(), 2);
+            assert!(child.children.iter().all(|leaf| leaf.children.is_empty()));
+        }
+    }
+    // one call for the task, one per node above the leaves
+    assert_eq!(llm.requests().len(), 1 + 2 + 4);
+
+    let flat = db.engine.subdivide_task_recursive(input(), 0).await.unwrap();
+
+    assert_eq!(flat.len(), 2);
+    assert!(flat.iter().all(|node| node.children.is_empty()));
+    assert_eq!(llm.requests().len(), 7 + 1);
+
+    let too_large = SubdivideTaskInputBuilder::default()
+        .task_id(task.id)
+        .subtasks(10)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.subdivide_task_recursive(too_large, 2).await,
+        Err(SDKError::SubdivisionTooLarge(1110))
+    ));
+    assert_eq!(llm.requests().len(), 8);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn suggest_labels_ignores_names_that_are_not_labels() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let task = create_task(&db, "fix the login button").await;
+
+    for name in ["bug", "docs", "frontend"] {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(task.owner_id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let llm = MockLlm::start(vec![r#"["Bug", " frontend", "urgent"]"#.to_string()])
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    let labels = db.engine.suggest_labels(task.id).await.unwrap();
+    let names: Vec<&str> = labels.iter().map(|label| label.name.as_str()).collect();
+
+    assert_eq!(names, ["bug", "frontend"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_calls_stay_within_the_configured_limit() {
+    let mut db = TestDatabase::with_config(|config| config.cognition_max_concurrency(NonZeroUsize::new(2).unwrap()))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start_with_delay(vec!["ok".to_string()], Duration::from_millis(50))
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    let calls = (0..20).map(|i| {
+        db.engine
+            .chat_completion("system".to_string(), format!("question {}", i), None)
+    });
+
+    for result in join_all(calls).await {
+        assert_eq!(result.unwrap().content, "ok");
+    }
+
+    assert_eq!(llm.requests().len(), 20);
+    assert_eq!(llm.peak_concurrency(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn a_slow_provider_times_out() {
+    let mut db = TestDatabase::with_config(|config| config.cognition_timeout_secs(1))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start_with_delay(vec!["too late".to_string()], Duration::from_secs(10))
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    let started_at = Instant::now();
+
+    let result = db
+        .engine
+        .chat_completion("system".to_string(), "user".to_string(), None)
+        .await;
+
+    assert!(matches!(result, Err(SDKError::Timeout(_))));
+    // the request is abandoned at the deadline rather than awaited
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn subdivide_tasks_answers_every_input_in_order_within_the_limit() {
+    let mut db = TestDatabase::with_config(|config| config.cognition_max_concurrency(NonZeroUsize::new(2).unwrap()))
+        .await
+        .unwrap();
+
+    let llm = MockLlm::start_with_delay(
+        vec![json!([suggestion_reply("write docs"), suggestion_reply("ship it")]).to_string()],
+        Duration::from_millis(50),
+    )
+    .await
+    .unwrap();
+    llm.install(&mut db.engine);
+
+    let mut task_ids = vec![
+        create_task(&db, "plan the launch").await.id,
+        create_task(&db, "hire a designer").await.id,
+        create_task(&db, "move offices").await.id,
+    ];
+    // a missing task fails on its own, without aborting the others
+    task_ids.insert(1, Uuid::new_v4());
+
+    let inputs = task_ids
+        .iter()
+        .map(|task_id| {
+            SubdivideTaskInputBuilder::default()
+                .task_id(*task_id)
+                .subtasks(2)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    let results = db.engine.subdivide_tasks(inputs).await;
+
+    assert_eq!(results.len(), 4);
+    assert!(matches!(&results[1], Err(SDKError::NotFound { resource: "task", .. })));
+
+    for result in [&results[0], &results[2], &results[3]] {
+        let titles: Vec<_> = result
+            .as_ref()
+            .unwrap()
+            .subtasks
+            .iter()
+            .map(|suggestion| suggestion.title.as_str())
+            .collect();
+
+        assert_eq!(titles, ["write docs", "ship it"]);
+    }
+
+    // the three existing tasks were subdivided concurrently, two at a time
+    assert_eq!(llm.requests().len(), 3);
+    assert_eq!(llm.peak_concurrency(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn accepted_suggestions_become_tasks_with_their_fields() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let parent = create_task(&db, "plan the launch").await;
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("launch".to_string())
+                .owner_id(parent.owner_id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let due_date = Utc.with_ymd_and_hms(2030, 1, 1, 9, 0, 0).unwrap();
+    let suggestion = |title: &str| {
+        TaskSuggestionBuilder::default()
+            .title(title.to_string())
+            .description(format!("{} in detail", title))
+            .status(TaskStatus::Backlog)
+            .priority(TaskPriority::High)
+            .due_date(due_date)
+            .build()
+            .unwrap()
+    };
+
+    let task = db
+        .engine
+        .accept_suggestion(suggestion("write docs"), parent.owner_id, Some(project.id))
+        .await
+        .unwrap();
+
+    assert_eq!(task.title, "write docs");
+    assert_eq!(task.description.as_deref(), Some("write docs in detail"));
+    assert_eq!(task.status, TaskStatus::Backlog);
+    assert_eq!(task.priority, TaskPriority::High);
+    assert_eq!(task.due_date, Some(due_date));
+    assert_eq!(task.owner_id, parent.owner_id);
+    assert_eq!(task.project_id, Some(project.id));
+    assert_eq!(task.parent_id, None);
+
+    let subtasks = db
+        .engine
+        .accept_suggestions(parent.id, vec![suggestion("book a venue"), suggestion("send invites")])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        subtasks.iter().map(|task| task.title.as_str()).collect::<Vec<_>>(),
+        ["book a venue", "send invites"]
+    );
+
+    for subtask in &subtasks {
+        assert_eq!(subtask.parent_id, Some(parent.id));
+        assert_eq!(subtask.owner_id, parent.owner_id);
+        assert_eq!(subtask.project_id, parent.project_id);
+        assert_eq!(subtask.priority, TaskPriority::High);
+    }
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn suggested_label_colors_avoid_the_colors_in_use() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let owner_id = create_task(&db, "paint the labels").await.owner_id;
+
+    for (name, color) in [("bug", "#ff0000"), ("docs", "#0F0")] {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(owner_id)
+                    .color(color.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    // the first answer names a taken color in another spelling, the second a free one
+    let llm = MockLlm::start(vec![json!("#F00").to_string(), json!("#123ABC").to_string()])
+        .await
+        .unwrap();
+    llm.install(&mut db.engine);
+
+    let replaced = db.engine.suggest_label_color("urgent".to_string()).await.unwrap();
+
+    assert!(!["#ff0000", "#00ff00"].contains(&replaced.as_str()));
+    assert_eq!(replaced.len(), 7);
+    assert!(replaced.starts_with('#') && replaced[1..].chars().all(|c| c.is_ascii_hexdigit()));
+
+    assert_eq!(
+        db.engine.suggest_label_color("frontend".to_string()).await.unwrap(),
+        "#123abc"
+    );
+
+    // the existing colors are part of the prompt
+    let prompt = llm.requests()[0].to_string();
+    assert!(prompt.contains("#ff0000") && prompt.contains("#00ff00"));
+
+    db.teardown().await.unwrap();
+}
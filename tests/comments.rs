@@ -0,0 +1,128 @@
+#![cfg(feature = "test-util")]
+
+use plexo_sdk::{
+    errors::sdk::SDKError,
+    resources::{
+        comments::operations::{CreateCommentInputBuilder, TaskCommentOperations, UpdateCommentInputBuilder},
+        members::{
+            member::{Member, MemberRole},
+            operations::{CreateMemberInputBuilder, MemberCrudOperations},
+        },
+        tasks::{
+            operations::{CreateTaskInputBuilder, TaskCrudOperations},
+            task::Task,
+        },
+    },
+    testing::TestDatabase,
+};
+
+async fn author_and_task(db: &TestDatabase, title: &str) -> (Member, Task) {
+    let author = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name(title.to_string())
+                .email(format!("{}@example.com", title))
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title(title.to_string())
+                .owner_id(author.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    (author, task)
+}
+
+#[tokio::test]
+async fn comments_are_created_listed_in_order_and_updated() {
+    let db = TestDatabase::new().await.unwrap();
+    let (author, task) = author_and_task(&db, "launch").await;
+
+    for content in ["first", "second"] {
+        db.engine
+            .create_comment(
+                CreateCommentInputBuilder::default()
+                    .task_id(task.id)
+                    .author_id(author.id)
+                    .content(content.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let comments = db.engine.get_comments(task.id).await.unwrap();
+
+    assert_eq!(
+        comments
+            .iter()
+            .map(|comment| comment.content.as_str())
+            .collect::<Vec<_>>(),
+        ["first", "second"]
+    );
+
+    let updated = db
+        .engine
+        .update_comment(
+            comments[0].id,
+            UpdateCommentInputBuilder::default()
+                .content("edited".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(updated.content, "edited");
+    assert_eq!(updated.author_id, author.id);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn delete_comment_only_deletes_from_its_own_task() {
+    let db = TestDatabase::new().await.unwrap();
+    let (author, task) = author_and_task(&db, "launch").await;
+    let (_, other_task) = author_and_task(&db, "docs").await;
+
+    let comment = db
+        .engine
+        .create_comment(
+            CreateCommentInputBuilder::default()
+                .task_id(task.id)
+                .author_id(author.id)
+                .content("first".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.delete_comment(other_task.id, comment.id).await,
+        Err(SDKError::NotFound {
+            resource: "comment",
+            ..
+        })
+    ));
+    assert_eq!(db.engine.get_comments(task.id).await.unwrap().len(), 1);
+
+    db.engine.delete_comment(task.id, comment.id).await.unwrap();
+
+    assert!(db.engine.get_comments(task.id).await.unwrap().is_empty());
+
+    db.teardown().await.unwrap();
+}
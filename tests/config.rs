@@ -0,0 +1,221 @@
+use std::{env, fs, path::PathBuf, sync::Mutex};
+
+use plexo_sdk::{
+    backend::engine::{LlmProvider, SDKConfig, SDKConfigBuilder},
+    errors::sdk::SDKError,
+};
+use uuid::Uuid;
+
+// the environment is shared by every test of this binary
+static ENV: Mutex<()> = Mutex::new(());
+
+const CONFIG_VARS: [&str; 9] = [
+    "DATABASE_URL",
+    "OPENAI_API_KEY",
+    "ANTHROPIC_API_KEY",
+    "PLEXO_LLM_PROVIDER",
+    "PLEXO_LLM_MODEL",
+    "OPENAI_MODEL_NAME",
+    "PLEXO_DB_MAX_CONNECTIONS",
+    "PLEXO_DB_MIN_CONNECTIONS",
+    "PLEXO_DB_ACQUIRE_TIMEOUT_SECS",
+];
+
+/// Runs `f` with exactly `vars` set among the variables the config reads.
+fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    for name in CONFIG_VARS {
+        env::remove_var(name);
+    }
+
+    for (name, value) in vars {
+        env::set_var(name, value);
+    }
+
+    let result = f();
+
+    for (name, _) in vars {
+        env::remove_var(name);
+    }
+
+    result
+}
+
+#[test]
+fn builder_fills_defaults_without_the_environment() {
+    let config = SDKConfigBuilder::default()
+        .database_url("postgres://localhost/plexo")
+        .llm_api_key("key")
+        .llm_provider(LlmProvider::Anthropic)
+        .max_connections(4)
+        .build()
+        .unwrap();
+
+    assert_eq!(config.database_url, "postgres://localhost/plexo");
+    assert_eq!(config.llm_model_name, LlmProvider::Anthropic.default_model());
+    assert_eq!(config.max_connections, 4);
+    assert_eq!(config.max_page_size, 1000);
+    assert!(config.read_replica_url.is_none());
+}
+
+#[test]
+fn builder_rejects_missing_and_invalid_fields() {
+    let missing = SDKConfigBuilder::default()
+        .database_url("postgres://localhost/plexo")
+        .build();
+    assert!(matches!(missing, Err(SDKError::InvalidConfig(_))));
+
+    let invalid = SDKConfigBuilder::default()
+        .database_url("postgres://localhost/plexo")
+        .llm_api_key("key")
+        .max_connections(2)
+        .min_connections(3)
+        .build();
+    assert!(matches!(invalid, Err(SDKError::InvalidConfig(_))));
+}
+
+#[test]
+fn from_env_reads_the_llm_model_and_provider() {
+    let config = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("ANTHROPIC_API_KEY", "key"),
+            ("PLEXO_LLM_PROVIDER", "anthropic"),
+            ("PLEXO_LLM_MODEL", "claude-3-opus-20240229"),
+        ],
+        SDKConfig::from_env,
+    )
+    .unwrap();
+
+    assert_eq!(config.llm_provider, LlmProvider::Anthropic);
+    assert_eq!(config.llm_model_name, "claude-3-opus-20240229");
+
+    let defaults = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("OPENAI_API_KEY", "key"),
+        ],
+        SDKConfig::from_env,
+    )
+    .unwrap();
+
+    assert_eq!(defaults.llm_provider, LlmProvider::OpenAI);
+    assert_eq!(defaults.llm_model_name, LlmProvider::OpenAI.default_model());
+}
+
+#[test]
+fn from_env_rejects_an_unknown_provider() {
+    let result = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("OPENAI_API_KEY", "key"),
+            ("PLEXO_LLM_PROVIDER", "gemini"),
+        ],
+        SDKConfig::from_env,
+    );
+
+    assert!(matches!(result, Err(SDKError::InvalidConfig(message)) if message.contains("gemini")));
+}
+
+#[test]
+fn from_env_reads_the_pool_options() {
+    let config = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("OPENAI_API_KEY", "key"),
+            ("PLEXO_DB_MAX_CONNECTIONS", "25"),
+            ("PLEXO_DB_MIN_CONNECTIONS", "2"),
+            ("PLEXO_DB_ACQUIRE_TIMEOUT_SECS", "7"),
+        ],
+        SDKConfig::from_env,
+    )
+    .unwrap();
+
+    assert_eq!(config.max_connections, 25);
+    assert_eq!(config.min_connections, 2);
+    assert_eq!(config.acquire_timeout_secs, 7);
+
+    let inverted = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("OPENAI_API_KEY", "key"),
+            ("PLEXO_DB_MAX_CONNECTIONS", "2"),
+            ("PLEXO_DB_MIN_CONNECTIONS", "3"),
+        ],
+        SDKConfig::from_env,
+    );
+    assert!(matches!(inverted, Err(SDKError::InvalidConfig(_))));
+
+    let malformed = with_env(
+        &[
+            ("DATABASE_URL", "postgres://localhost/plexo"),
+            ("OPENAI_API_KEY", "key"),
+            ("PLEXO_DB_MAX_CONNECTIONS", "lots"),
+        ],
+        SDKConfig::from_env,
+    );
+    assert!(matches!(malformed, Err(SDKError::InvalidConfig(_))));
+}
+
+/// Writes `content` to a fresh file in the temp directory; the caller removes it.
+fn config_file(content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("plexo-{}.toml", Uuid::new_v4()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn from_file_reads_a_toml_file_and_env_overrides_it() {
+    let path = config_file(
+        r#"
+        database_url = "postgres://file/plexo"
+        llm_api_key = "file-key"
+        llm_provider = "anthropic"
+        llm_model_name = "claude-3-opus-20240229"
+        max_connections = 12
+        min_connections = 2
+        acquire_timeout_secs = 9
+        auto_migrate = true
+        "#,
+    );
+
+    let config = with_env(&[], || SDKConfig::from_file(&path)).unwrap();
+
+    assert_eq!(config.database_url, "postgres://file/plexo");
+    assert_eq!(config.llm_api_key, "file-key");
+    assert_eq!(config.llm_provider, LlmProvider::Anthropic);
+    assert_eq!(config.llm_model_name, "claude-3-opus-20240229");
+    assert_eq!(config.max_connections, 12);
+    assert_eq!(config.min_connections, 2);
+    assert_eq!(config.acquire_timeout_secs, 9);
+    assert!(config.auto_migrate);
+
+    let overridden = with_env(
+        &[
+            ("DATABASE_URL", "postgres://env/plexo"),
+            ("PLEXO_DB_MAX_CONNECTIONS", "20"),
+        ],
+        || SDKConfig::from_file(&path),
+    )
+    .unwrap();
+
+    assert_eq!(overridden.database_url, "postgres://env/plexo");
+    assert_eq!(overridden.max_connections, 20);
+    assert_eq!(overridden.min_connections, 2);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn from_file_rejects_malformed_and_missing_files() {
+    let path = config_file("database_url = \"postgres://file/plexo");
+
+    let malformed = with_env(&[], || SDKConfig::from_file(&path));
+    assert!(matches!(malformed, Err(SDKError::InvalidConfig(message)) if message.contains("malformed")));
+
+    fs::remove_file(&path).unwrap();
+
+    let missing = with_env(&[], || SDKConfig::from_file(&path));
+    assert!(matches!(missing, Err(SDKError::InvalidConfig(message)) if message.contains("cannot read")));
+}
@@ -0,0 +1,203 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use plexo_sdk::{
+    backend::engine::SDKEngine,
+    errors::sdk::SDKError,
+    resources::labels::operations::{CreateLabelInputBuilder, GetLabelsInput, LabelCrudOperations},
+    testing::TestDatabase,
+};
+use sqlx::postgres::PgPoolOptions;
+use uuid::Uuid;
+
+const TABLES: [&str; 6] = [
+    "labels",
+    "members",
+    "projects",
+    "tasks",
+    "task_comments",
+    "task_embeddings",
+];
+
+async fn existing_tables(engine: &SDKEngine) -> Vec<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT table_name::text FROM information_schema.tables
+        WHERE table_schema = 'public' AND table_name = ANY($1)
+        ORDER BY table_name
+        "#,
+    )
+    .bind(&TABLES[..])
+    .fetch_all(engine.db_pool.as_ref())
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn run_migrations_creates_the_tables_and_can_run_again() {
+    let db = TestDatabase::with_config(|config| config.auto_migrate(false))
+        .await
+        .unwrap();
+
+    assert!(existing_tables(&db.engine).await.is_empty());
+
+    db.engine.run_migrations().await.unwrap();
+
+    let mut expected = TABLES.map(String::from).to_vec();
+    expected.sort();
+
+    assert_eq!(existing_tables(&db.engine).await, expected);
+
+    // already applied migrations are skipped
+    db.engine.run_migrations().await.unwrap();
+
+    assert_eq!(existing_tables(&db.engine).await, expected);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn health_check_passes_on_a_reachable_database() {
+    let db = TestDatabase::new().await.unwrap();
+
+    db.engine.health_check().await.unwrap();
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn health_check_reports_an_unreachable_database_as_unavailable() {
+    let mut db = TestDatabase::new().await.unwrap();
+
+    let healthy_pool = db.engine.db_pool.clone();
+
+    // nothing listens on port 1, so connecting fails right away
+    *db.engine.db_pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(1))
+        .connect_lazy("postgres://plexo@127.0.0.1:1/plexo")
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.health_check().await,
+        Err(SDKError::Unavailable { .. })
+    ));
+
+    db.engine.db_pool = healthy_pool;
+    db.engine.close().await.unwrap();
+
+    // a closed pool is reported, not a panic
+    let closed = db.engine.health_check().await.unwrap_err();
+
+    assert!(closed.is_closed());
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn reads_go_to_the_replica_and_writes_to_the_primary() {
+    // a separate database stands in for the replica, so it never sees the primary's writes
+    let replica = TestDatabase::new().await.unwrap();
+    let replica_url = replica.engine.config.database_url.clone();
+
+    let db = TestDatabase::with_config(|config| config.read_replica_url(replica_url))
+        .await
+        .unwrap();
+
+    let input = |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(Uuid::new_v4())
+            .build()
+            .unwrap()
+    };
+
+    let written = db.engine.create_label(input("primary")).await.unwrap();
+    let replicated = replica.engine.create_label(input("replica")).await.unwrap();
+
+    let labels = db.engine.get_labels(GetLabelsInput::default()).await.unwrap();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].id, replicated.id);
+    assert!(matches!(
+        db.engine.get_label(written.id).await,
+        Err(SDKError::NotFound { .. })
+    ));
+
+    db.teardown().await.unwrap();
+    replica.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn pool_stats_count_a_checked_out_connection_as_in_use() {
+    let db = TestDatabase::new().await.unwrap();
+
+    // connections used while setting up the database are handed back in the background
+    for _ in 0..100 {
+        if db.engine.pool_stats().in_use == 0 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(db.engine.pool_stats().in_use, 0);
+
+    let conn = db.engine.db_pool.acquire().await.unwrap();
+    let during = db.engine.pool_stats();
+
+    assert_eq!(during.in_use, 1);
+    assert_eq!(during.idle + during.in_use, during.size);
+
+    drop(conn);
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn operations_after_close_fail_as_unavailable() {
+    let db = TestDatabase::new().await.unwrap();
+
+    db.engine.close().await.unwrap();
+
+    let result = db.engine.get_labels(GetLabelsInput::default()).await;
+
+    assert!(matches!(result, Err(SDKError::Unavailable { closed: true, .. })));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn a_saturated_pool_waits_for_a_connection_then_gives_up_as_unavailable() {
+    let db = TestDatabase::with_config(|config| config.max_connections(1).acquire_timeout_secs(1))
+        .await
+        .unwrap();
+
+    let input = |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(Uuid::new_v4())
+            .build()
+            .unwrap()
+    };
+
+    // released while the write is still waiting for it
+    let held = db.engine.db_pool.acquire().await.unwrap();
+    let release = async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        drop(held);
+    };
+
+    let (created, ()) = tokio::join!(db.engine.create_label(input("waited")), release);
+    assert_eq!(created.unwrap().name, "waited");
+
+    // held past the whole acquire budget
+    let held = db.engine.db_pool.acquire().await.unwrap();
+
+    assert!(matches!(
+        db.engine.create_label(input("starved")).await,
+        Err(SDKError::Unavailable { .. })
+    ));
+
+    drop(held);
+    db.teardown().await.unwrap();
+}
@@ -0,0 +1,1307 @@
+#![cfg(feature = "test-util")]
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{TimeZone, Utc};
+use plexo_sdk::{
+    backend::loaders::SDKLoaders,
+    common::commons::{FilterOp, SortOrder},
+    errors::sdk::SDKError,
+    resources::{
+        labels::operations::{
+            CreateLabelInputBuilder, GetLabelsInput, GetLabelsInputBuilder, GetLabelsWhereBuilder, LabelCrudOperations,
+            LabelTransactionOperations, UpdateLabelInputBuilder,
+        },
+        members::{
+            member::MemberRole,
+            operations::{CreateMemberInputBuilder, MemberCrudOperations},
+        },
+        tasks::{
+            operations::{CreateTaskInputBuilder, TaskCrudOperations},
+            relations::TaskRelations,
+        },
+    },
+    testing::{TestDatabase, TraceCapture},
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn create_label_then_get_label() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let created = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("bug".to_string())
+                .owner_id(Uuid::new_v4())
+                .color("#d73a4a".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let fetched = db.engine.get_label(created.id).await.unwrap();
+
+    assert_eq!(fetched.id, created.id);
+    assert_eq!(fetched.name, "bug");
+    assert_eq!(fetched.color.as_deref(), Some("#d73a4a"));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_with_total_counts_past_the_page() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let inputs = ["team-a", "team-b", "team-c", "team-d", "other"]
+        .into_iter()
+        .map(|name| {
+            CreateLabelInputBuilder::default()
+                .name(name.to_string())
+                .owner_id(owner_id)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine.create_labels(inputs).await.unwrap();
+
+    let (labels, total) = db
+        .engine
+        .get_labels_with_total(
+            GetLabelsInputBuilder::default()
+                .filter(
+                    GetLabelsWhereBuilder::default()
+                        .name(FilterOp::Like("team-%".to_string()))
+                        .build()
+                        .unwrap(),
+                )
+                .sort_by("name".to_string())
+                .limit(2)
+                .offset(1)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = labels.iter().map(|label| label.name.as_str()).collect();
+
+    assert_eq!(names, ["team-b", "team-c"]);
+    assert_eq!(total, 4);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_label_with_repeated_idempotency_key_returns_the_first_label() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let input = || {
+        CreateLabelInputBuilder::default()
+            .name("bug".to_string())
+            .owner_id(owner_id)
+            .idempotency_key("request-1")
+            .build()
+            .unwrap()
+    };
+
+    let first = db.engine.create_label(input()).await.unwrap();
+    let second = db.engine.create_label(input()).await.unwrap();
+    let batch = db.engine.create_labels(vec![input()]).await.unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&second).unwrap()
+    );
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&batch[0]).unwrap()
+    );
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_clamps_limits_above_max_page_size() {
+    let db = TestDatabase::with_config(|config| config.max_page_size(2))
+        .await
+        .unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let inputs = ["a", "b", "c"]
+        .into_iter()
+        .map(|name| {
+            CreateLabelInputBuilder::default()
+                .name(name.to_string())
+                .owner_id(owner_id)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine.create_labels(inputs).await.unwrap();
+
+    let labels = db
+        .engine
+        .get_labels(GetLabelsInputBuilder::default().limit(1_000_000).build().unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(labels.len(), 2);
+
+    let labels = db.engine.get_labels(GetLabelsInput::default()).await.unwrap();
+
+    assert_eq!(labels.len(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_label_rejects_a_taken_name() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let input = || {
+        CreateLabelInputBuilder::default()
+            .name("bug".to_string())
+            .owner_id(Uuid::new_v4())
+            .build()
+            .unwrap()
+    };
+
+    db.engine.create_label(input()).await.unwrap();
+
+    assert!(matches!(
+        db.engine.create_label(input()).await,
+        Err(SDKError::DuplicateLabel { name }) if name == "bug"
+    ));
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_labels_reports_the_duplicate_name() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let input = |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id)
+            .build()
+            .unwrap()
+    };
+
+    db.engine.create_label(input("bug")).await.unwrap();
+
+    let err = db
+        .engine
+        .create_labels(vec![input("feature"), input("bug")])
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SDKError::DuplicateLabel { name } if name == "bug"));
+
+    let err = db
+        .engine
+        .create_labels(vec![input("docs"), input("docs")])
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SDKError::DuplicateLabel { name } if name == "docs"));
+
+    // the failed batches roll back entirely
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn delete_label_removes_its_task_links() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner_id = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .id;
+
+    let input = |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id)
+            .build()
+            .unwrap()
+    };
+
+    let bug = db.engine.create_label(input("bug")).await.unwrap();
+    let docs = db.engine.create_label(input("docs")).await.unwrap();
+
+    db.engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("fix the readme".to_string())
+                .owner_id(owner_id)
+                .labels(vec![bug.id, docs.id])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    db.engine.delete_label(bug.id).await.unwrap();
+
+    let docs_id = docs.id;
+    db.engine
+        .transaction(move |conn| Box::pin(async move { conn.delete_label(docs_id).await }))
+        .await
+        .unwrap();
+
+    assert_eq!(db.engine.get_label_usage(bug.id).await.unwrap(), 0);
+    assert_eq!(db.engine.get_label_usage(docs.id).await.unwrap(), 0);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn delete_labels_removes_them_with_their_task_links() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner_id = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .id;
+
+    let mut labels = Vec::new();
+
+    for name in ["bug", "docs", "feature", "keep"] {
+        let input = CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id)
+            .build()
+            .unwrap();
+
+        labels.push(db.engine.create_label(input).await.unwrap());
+    }
+
+    db.engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("tidy up".to_string())
+                .owner_id(owner_id)
+                .labels(labels.iter().map(|label| label.id).collect())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // an unknown id is skipped, not counted
+    let deleted = db
+        .engine
+        .delete_labels(vec![labels[0].id, labels[1].id, labels[2].id, Uuid::new_v4()])
+        .await
+        .unwrap();
+
+    assert_eq!(deleted, 3);
+
+    for label in &labels[..3] {
+        assert_eq!(db.engine.get_label_usage(label.id).await.unwrap(), 0);
+    }
+
+    assert_eq!(db.engine.get_label_usage(labels[3].id).await.unwrap(), 1);
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_matches_names_with_quotes() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    for name in ["O'Brien", "Obrien"] {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(owner_id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let labels = db
+        .engine
+        .get_labels(
+            GetLabelsInputBuilder::default()
+                .filter(
+                    GetLabelsWhereBuilder::default()
+                        .name("O'Brien".to_string())
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].name, "O'Brien");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_rejects_unknown_sort_fields() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let result = db
+        .engine
+        .get_labels(
+            GetLabelsInputBuilder::default()
+                .sort_by("name; DROP TABLE labels".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await;
+
+    assert!(matches!(result, Err(SDKError::InvalidSortField(_))));
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 0);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_filters_by_substring() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    for name in ["bug", "Debug", "feature"] {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(owner_id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let names = |filter| async {
+        let mut names: Vec<String> = db
+            .engine
+            .get_labels(GetLabelsInputBuilder::default().filter(filter).build().unwrap())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+
+        names.sort();
+        names
+    };
+
+    assert_eq!(
+        names(
+            GetLabelsWhereBuilder::default()
+                .name_contains("ug".to_string())
+                .build()
+                .unwrap()
+        )
+        .await,
+        ["Debug", "bug"]
+    );
+    assert_eq!(
+        names(
+            GetLabelsWhereBuilder::default()
+                .name_contains("DE".to_string())
+                .build()
+                .unwrap()
+        )
+        .await,
+        ["Debug"]
+    );
+    assert_eq!(
+        names(
+            GetLabelsWhereBuilder::default()
+                .name_ilike("de".to_string())
+                .build()
+                .unwrap()
+        )
+        .await,
+        ["Debug"]
+    );
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_filters_by_ids() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let mut labels = Vec::new();
+
+    for i in 0..5 {
+        let label = db
+            .engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(format!("label {}", i))
+                    .owner_id(owner_id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        labels.push(label);
+    }
+
+    let ids = vec![labels[0].id, labels[2].id, labels[4].id];
+
+    let fetched = db
+        .engine
+        .get_labels(
+            GetLabelsInputBuilder::default()
+                .filter(GetLabelsWhereBuilder::default().ids(ids.clone()).build().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.len(), 3);
+    assert!(fetched.iter().all(|label| ids.contains(&label.id)));
+
+    // combined with another clause of the same group
+    let fetched = db
+        .engine
+        .get_labels(
+            GetLabelsInputBuilder::default()
+                .filter(
+                    GetLabelsWhereBuilder::default()
+                        .ids(ids)
+                        .name("label 2".to_string())
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(fetched.len(), 1);
+    assert_eq!(fetched[0].id, labels[2].id);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_count_honors_the_filter() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let inputs = ["team-a", "team-b", "team-c", "other"]
+        .into_iter()
+        .map(|name| {
+            CreateLabelInputBuilder::default()
+                .name(name.to_string())
+                .owner_id(owner_id)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine.create_labels(inputs).await.unwrap();
+
+    let teams = GetLabelsWhereBuilder::default()
+        .name(FilterOp::Like("team-%".to_string()))
+        .build()
+        .unwrap();
+
+    assert_eq!(db.engine.get_labels_count(Some(teams)).await.unwrap(), 3);
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 4);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_labels_returns_rows_in_input_order() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let names: Vec<String> = (0..10).rev().map(|i| format!("label {}", i)).collect();
+
+    let inputs = names
+        .iter()
+        .map(|name| {
+            CreateLabelInputBuilder::default()
+                .name(name.clone())
+                .owner_id(owner_id)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    let labels = db.engine.create_labels(inputs).await.unwrap();
+
+    assert_eq!(
+        labels.iter().map(|label| &label.name).collect::<Vec<_>>(),
+        names.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 10);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_and_update_label_reject_invalid_colors() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let input = |color: &str| {
+        CreateLabelInputBuilder::default()
+            .name(format!("label {}", color))
+            .owner_id(Uuid::new_v4())
+            .color(color.to_string())
+            .build()
+            .unwrap()
+    };
+
+    assert!(matches!(
+        db.engine.create_label(input("red")).await,
+        Err(SDKError::InvalidColor(_))
+    ));
+
+    let label = db.engine.create_label(input("#fff")).await.unwrap();
+
+    let recolor = |color: &str| {
+        UpdateLabelInputBuilder::default()
+            .color(color.to_string())
+            .build()
+            .unwrap()
+    };
+
+    assert!(matches!(
+        db.engine.update_label(label.id, recolor("red")).await,
+        Err(SDKError::InvalidColor(_))
+    ));
+
+    let updated = db.engine.update_label(label.id, recolor("#AABBCC")).await.unwrap();
+
+    assert_eq!(updated.color.as_deref(), Some("#AABBCC"));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn a_failing_transaction_rolls_back_its_inserts() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner_id = Uuid::new_v4();
+    let input = move |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id)
+            .build()
+            .unwrap()
+    };
+
+    let rolled_back = db
+        .engine
+        .transaction(move |conn| {
+            Box::pin(async move {
+                conn.create_label(input("bug")).await?;
+                // fails, as no label has this id
+                conn.delete_label(Uuid::new_v4()).await
+            })
+        })
+        .await;
+
+    assert!(matches!(rolled_back, Err(SDKError::NotFound { .. })));
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 0);
+
+    let committed = db
+        .engine
+        .transaction(move |conn| Box::pin(async move { conn.create_label(input("docs")).await }))
+        .await
+        .unwrap();
+
+    assert_eq!(db.engine.get_label(committed.id).await.unwrap().name, "docs");
+    assert_eq!(db.engine.get_labels_count(None).await.unwrap(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_emits_a_span_with_the_row_count() {
+    let db = TestDatabase::new().await.unwrap();
+
+    for name in ["bug", "docs"] {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(Uuid::new_v4())
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    db.engine.get_labels(GetLabelsInput::default()).await.unwrap();
+
+    drop(guard);
+
+    let spans = trace.spans();
+    let span = spans.iter().find(|span| span.name == "get_labels").unwrap();
+
+    assert_eq!(span.fields["resource"], "label");
+    assert_eq!(span.fields["operation"], "read");
+    assert_eq!(span.fields["rows"], "2");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn the_label_loader_batches_lookups_into_one_query() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner_id = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .id;
+
+    let labels = db
+        .engine
+        .create_labels(
+            (0..50)
+                .map(|i| {
+                    CreateLabelInputBuilder::default()
+                        .name(format!("label {}", i))
+                        .owner_id(owner_id)
+                        .build()
+                        .unwrap()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+    let ids: Vec<Uuid> = labels.iter().map(|label| label.id).collect();
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("tag everything".to_string())
+                .owner_id(owner_id)
+                .labels(ids.clone())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let engine = Arc::new(db.engine.clone());
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    let loaded = SDKLoaders::new(engine.clone())
+        .label_loader
+        .load_many(ids.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(loaded.len(), 50);
+    assert_eq!(trace.queries(), 1);
+
+    // one query for the task's label ids, one for the labels themselves
+    let task_labels = task.labels(&SDKLoaders::new(engine)).await.unwrap();
+
+    drop(guard);
+
+    assert_eq!(task_labels.len(), 50);
+    assert_eq!(trace.queries(), 3);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn invalidating_a_cached_label_refetches_it() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("bug".to_string())
+                .owner_id(Uuid::new_v4())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let loaders = SDKLoaders::with_cache(Arc::new(db.engine.clone()), Duration::from_secs(3600));
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    loaders.label_loader.load_one(label.id).await.unwrap();
+    loaders.label_loader.load_one(label.id).await.unwrap();
+
+    assert_eq!(trace.queries(), 1);
+
+    drop(guard);
+
+    db.engine
+        .update_label(
+            label.id,
+            UpdateLabelInputBuilder::default()
+                .name("defect".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let guard = trace.install();
+
+    // still the stale value until it is invalidated
+    let stale = loaders.label_loader.load_one(label.id).await.unwrap().unwrap();
+    assert_eq!(stale.name, "bug");
+
+    loaders.invalidate(label.id);
+
+    let fresh = loaders.label_loader.load_one(label.id).await.unwrap().unwrap();
+
+    drop(guard);
+
+    assert_eq!(fresh.name, "defect");
+    assert_eq!(trace.queries(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn created_at_windows_include_the_start_and_exclude_the_end() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let week = |day| Utc.with_ymd_and_hms(2030, 1, day, 0, 0, 0).unwrap();
+
+    for (name, created_at) in [("first", week(1)), ("second", week(8)), ("third", week(15))] {
+        let label = db
+            .engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(Uuid::new_v4())
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        sqlx::query("UPDATE labels SET created_at = $1 WHERE id = $2")
+            .bind(created_at)
+            .bind(label.id)
+            .execute(db.engine.db_pool.as_ref())
+            .await
+            .unwrap();
+    }
+
+    let names = |filter| {
+        let engine = db.engine.clone();
+
+        async move {
+            let input = GetLabelsInputBuilder::default()
+                .filter(filter)
+                .sort_by("created_at".to_string())
+                .build()
+                .unwrap();
+
+            engine
+                .get_labels(input)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|label| label.name)
+                .collect::<Vec<String>>()
+        }
+    };
+
+    let window = GetLabelsWhereBuilder::default()
+        .created_after(week(8))
+        .created_before(week(15))
+        .build()
+        .unwrap();
+    assert_eq!(names(window).await, ["second"]);
+
+    let open_end = GetLabelsWhereBuilder::default().created_after(week(8)).build().unwrap();
+    assert_eq!(names(open_end).await, ["second", "third"]);
+
+    let open_start = GetLabelsWhereBuilder::default()
+        .created_before(week(8))
+        .build()
+        .unwrap();
+    assert_eq!(names(open_start).await, ["first"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn cursor_pages_cover_every_label_once() {
+    let db = TestDatabase::new().await.unwrap();
+
+    // created in one transaction, so they all share `created_at` and only the id breaks the tie
+    let created = db
+        .engine
+        .create_labels(
+            (0..25)
+                .map(|i| {
+                    CreateLabelInputBuilder::default()
+                        .name(format!("label {}", i))
+                        .owner_id(Uuid::new_v4())
+                        .build()
+                        .unwrap()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let mut seen = Vec::new();
+    let mut page_sizes = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let mut input = GetLabelsInputBuilder::default().limit(10);
+
+        if let Some(cursor) = cursor {
+            input = input.cursor::<String>(cursor);
+        }
+
+        let page = db.engine.get_labels_by_cursor(input.build().unwrap()).await.unwrap();
+
+        page_sizes.push(page.items.len());
+        seen.extend(page.items.into_iter().map(|label| label.id));
+
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    let mut expected: Vec<Uuid> = created.iter().map(|label| label.id).collect();
+    expected.sort();
+
+    assert_eq!(page_sizes, [10, 10, 5]);
+    assert_eq!(seen, expected);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_sorts_by_several_columns() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let labels = [
+        ("bug", "#ff0000"),
+        ("defect", "#ff0000"),
+        ("docs", "#0000ff"),
+        ("api", "#0000ff"),
+    ];
+
+    for (name, color) in labels {
+        db.engine
+            .create_label(
+                CreateLabelInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(Uuid::new_v4())
+                    .color(color.to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let input = GetLabelsInputBuilder::default()
+        .sort(vec![
+            ("color".to_string(), SortOrder::Asc),
+            ("name".to_string(), SortOrder::Desc),
+        ])
+        .build()
+        .unwrap();
+
+    let names: Vec<String> = db
+        .engine
+        .get_labels(input)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|label| label.name)
+        .collect();
+
+    assert_eq!(names, ["docs", "api", "defect", "bug"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn the_last_page_has_no_more() {
+    let db = TestDatabase::new().await.unwrap();
+
+    db.engine
+        .create_labels(
+            (0..20)
+                .map(|i| {
+                    CreateLabelInputBuilder::default()
+                        .name(format!("label {:02}", i))
+                        .owner_id(Uuid::new_v4())
+                        .build()
+                        .unwrap()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let page = |offset: i32| {
+        GetLabelsInputBuilder::default()
+            .sort_by("name".to_string())
+            .limit(10)
+            .offset(offset)
+            .build()
+            .unwrap()
+    };
+
+    let first = db.engine.get_labels_page(page(0), true).await.unwrap();
+
+    assert_eq!(first.items.len(), 10);
+    assert_eq!(first.total, Some(20));
+    assert!(first.has_more);
+
+    // exactly at the boundary: a full page, but nothing after it
+    let last = db.engine.get_labels_page(page(10), false).await.unwrap();
+
+    assert_eq!(last.items.len(), 10);
+    assert_eq!(last.items[9].name, "label 19");
+    assert_eq!(last.total, None);
+    assert!(!last.has_more);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_label_update_is_a_conflict() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("bug".to_string())
+                .owner_id(Uuid::new_v4())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let recolor = |color: &str, version| {
+        UpdateLabelInputBuilder::default()
+            .color(color.to_string())
+            .expected_updated_at(version)
+            .build()
+            .unwrap()
+    };
+
+    db.engine
+        .update_label(label.id, recolor("#ff0000", label.updated_at))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.update_label(label.id, recolor("#00ff00", label.updated_at)).await,
+        Err(SDKError::Conflict { resource: "label", id }) if id == label.id
+    ));
+    assert_eq!(
+        db.engine.get_label(label.id).await.unwrap().color.as_deref(),
+        Some("#ff0000")
+    );
+
+    // without a version the write is unconditional, as before
+    let forced = db
+        .engine
+        .update_label(
+            label.id,
+            UpdateLabelInputBuilder::default()
+                .color("#00ff00".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(forced.color.as_deref(), Some("#00ff00"));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn upsert_label_inserts_once_then_updates_by_name() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    let upsert = |color: Option<&str>| {
+        let input = CreateLabelInputBuilder::default()
+            .name("synced".to_string())
+            .owner_id(owner_id)
+            .description("from the sync job".to_string());
+
+        match color {
+            Some(color) => input.color(color.to_string()).build().unwrap(),
+            None => input.build().unwrap(),
+        }
+    };
+
+    let created = db.engine.upsert_label(upsert(Some("#ff0000"))).await.unwrap();
+
+    assert_eq!(created.color.as_deref(), Some("#ff0000"));
+
+    let updated = db.engine.upsert_label(upsert(Some("#00ff00"))).await.unwrap();
+
+    assert_eq!(updated.id, created.id);
+    assert_eq!(updated.color.as_deref(), Some("#00ff00"));
+    assert_eq!(updated.description.as_deref(), Some("from the sync job"));
+
+    // an omitted color keeps the stored one
+    let unchanged = db.engine.upsert_label(upsert(None)).await.unwrap();
+
+    assert_eq!(unchanged.color.as_deref(), Some("#00ff00"));
+    assert_eq!(db.engine.get_labels(GetLabelsInput::default()).await.unwrap().len(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn every_filter_operator_selects_the_matching_labels() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner_id = Uuid::new_v4();
+
+    for (name, color) in [("a", Some("#ff0000")), ("b", Some("#00ff00")), ("c", None)] {
+        let input = CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id);
+
+        let input = match color {
+            Some(color) => input.color(color.to_string()),
+            None => input,
+        };
+
+        db.engine.create_label(input.build().unwrap()).await.unwrap();
+    }
+
+    let names = |filter: GetLabelsWhereBuilder| {
+        let engine = db.engine.clone();
+
+        async move {
+            let input = GetLabelsInputBuilder::default()
+                .filter(filter.build().unwrap())
+                .sort_by("name".to_string())
+                .build()
+                .unwrap();
+
+            engine
+                .get_labels(input)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|label| label.name)
+                .collect::<Vec<String>>()
+        }
+    };
+    let by_name = |op: FilterOp<String>| names(GetLabelsWhereBuilder::default().name(op));
+    let b = || "b".to_string();
+
+    assert_eq!(by_name(b().into()).await, ["b"]);
+    assert_eq!(by_name(FilterOp::Neq(b())).await, ["a", "c"]);
+    assert_eq!(by_name(FilterOp::Gt(b())).await, ["c"]);
+    assert_eq!(by_name(FilterOp::Gte(b())).await, ["b", "c"]);
+    assert_eq!(by_name(FilterOp::Lt(b())).await, ["a"]);
+    assert_eq!(by_name(FilterOp::Lte(b())).await, ["a", "b"]);
+    assert_eq!(
+        by_name(FilterOp::In(vec!["a".to_string(), "c".to_string()])).await,
+        ["a", "c"]
+    );
+    assert_eq!(by_name(FilterOp::Like("_".to_string())).await, ["a", "b", "c"]);
+
+    let by_color = |op: FilterOp<String>| names(GetLabelsWhereBuilder::default().color(op));
+
+    assert_eq!(by_color(FilterOp::Like("#00%".to_string())).await, ["b"]);
+    assert_eq!(by_color(FilterOp::IsNull(true)).await, ["c"]);
+    assert_eq!(by_color(FilterOp::IsNull(false)).await, ["a", "b"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn label_usage_counts_the_tasks_of_each_label() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner_id = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .id;
+
+    let input = |name: &str| {
+        CreateLabelInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owner_id)
+            .build()
+            .unwrap()
+    };
+
+    let bug = db.engine.create_label(input("bug")).await.unwrap();
+    let docs = db.engine.create_label(input("docs")).await.unwrap();
+    let unused = db.engine.create_label(input("wontfix")).await.unwrap();
+
+    for (title, labels) in [
+        ("fix the crash", vec![bug.id]),
+        ("fix the leak", vec![bug.id]),
+        ("fix the readme", vec![bug.id, docs.id]),
+    ] {
+        db.engine
+            .create_task(
+                CreateTaskInputBuilder::default()
+                    .title(title.to_string())
+                    .owner_id(owner_id)
+                    .labels(labels)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(db.engine.get_label_usage(bug.id).await.unwrap(), 3);
+    assert_eq!(db.engine.get_label_usage(docs.id).await.unwrap(), 1);
+    assert_eq!(db.engine.get_label_usage(unused.id).await.unwrap(), 0);
+
+    let usage: Vec<_> = db
+        .engine
+        .get_labels_with_usage()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(label, usage)| (label.id, usage))
+        .collect();
+
+    assert_eq!(usage, vec![(bug.id, 3), (docs.id, 1), (unused.id, 0)]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_labels_page_defaults_to_max_page_size_and_rejects_cursors() {
+    let db = TestDatabase::with_config(|config| config.max_page_size(3))
+        .await
+        .unwrap();
+
+    db.engine
+        .create_labels(
+            (0..5)
+                .map(|i| {
+                    CreateLabelInputBuilder::default()
+                        .name(format!("label {}", i))
+                        .owner_id(Uuid::new_v4())
+                        .build()
+                        .unwrap()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let page = db
+        .engine
+        .get_labels_page(GetLabelsInput::default(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(page.limit, 3);
+    assert_eq!(page.items.len(), 3);
+    assert_eq!(page.total, Some(5));
+    assert!(page.has_more);
+
+    let cursor = db
+        .engine
+        .get_labels_by_cursor(GetLabelsInput::default())
+        .await
+        .unwrap()
+        .next_cursor
+        .unwrap();
+
+    assert!(matches!(
+        db.engine
+            .get_labels_page(GetLabelsInputBuilder::default().cursor(cursor).build().unwrap(), true)
+            .await,
+        Err(SDKError::InvalidPagination(_))
+    ));
+
+    db.teardown().await.unwrap();
+}
@@ -0,0 +1,788 @@
+#![cfg(feature = "test-util")]
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{TimeZone, Utc};
+use futures::future::join_all;
+use plexo_sdk::{
+    backend::{
+        events::{EventSink, SDKEvent},
+        loaders::SDKLoaders,
+    },
+    errors::sdk::SDKError,
+    resources::{
+        labels::operations::{CreateLabelInputBuilder, LabelCrudOperations},
+        members::{
+            member::{Member, MemberRole},
+            operations::{CreateMemberInputBuilder, MemberCrudOperations},
+        },
+        projects::{
+            export::{ProjectExport, ProjectExportOperations},
+            extensions::ProjectsExtensionOperations,
+            members::ProjectMemberOperations,
+            operations::{
+                CreateProjectInputBuilder, GetProjectsInputBuilder, GetProjectsWhereBuilder, ProjectCrudOperations,
+            },
+            project::{ProjectMemberRole, ProjectStatus},
+            relations::ProjectRelations,
+        },
+        tasks::{
+            extensions::{TaskLabelOperations, TasksExtensionOperations},
+            operations::{CreateTaskInputBuilder, TaskCrudOperations},
+            task::TaskStatus,
+        },
+    },
+    testing::{TestDatabase, TraceCapture},
+};
+use uuid::Uuid;
+
+#[derive(Default)]
+struct RecordedEvents(Mutex<Vec<SDKEvent>>);
+
+impl EventSink for RecordedEvents {
+    fn emit(&self, event: SDKEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+async fn create_member(db: &TestDatabase, email: &str) -> Member {
+    db.engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name(email.to_string())
+                .email(email.to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn export_then_import_into_another_database() {
+    let source = TestDatabase::new().await.unwrap();
+    let target = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&source, "owner@example.com").await;
+
+    let project = source
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .lead_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let label = source
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("infra".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    source
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("launch".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .labels(vec![label.id])
+                .subtasks(vec![CreateTaskInputBuilder::default()
+                    .title("write docs".to_string())
+                    .owner_id(owner.id)
+                    .project_id(project.id)
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let export = source.engine.export_project(project.id).await.unwrap();
+    let export: ProjectExport = serde_json::from_str(&serde_json::to_string(&export).unwrap()).unwrap();
+
+    // the source owner doesn't exist on the target, so everything it owned falls back to this member
+    let importer = create_member(&target, "importer@example.com").await;
+
+    let events = Arc::new(RecordedEvents::default());
+    let engine = target.engine.clone().with_event_sink(events.clone());
+
+    let imported = engine.import_project(export, importer.id).await.unwrap();
+
+    assert_ne!(imported.id, project.id);
+    assert_eq!(imported.name, "roadmap");
+    assert_eq!(imported.owner_id, importer.id);
+    assert_eq!(imported.lead_id, None);
+
+    let tree_roots = target.engine.get_project_tasks(imported.id, None).await.unwrap();
+    let root = tree_roots.iter().find(|task| task.parent_id.is_none()).unwrap();
+    let tree = target.engine.get_task_tree(root.id).await.unwrap();
+
+    assert_eq!(tree.task.title, "launch");
+    assert_eq!(tree.task.owner_id, importer.id);
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].task.title, "write docs");
+
+    let labels = target.engine.get_task_labels(root.id).await.unwrap();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].name, "infra");
+
+    assert!(matches!(
+        events.0.lock().unwrap().as_slice(),
+        [SDKEvent::ProjectCreated(created)] if created.id == imported.id
+    ));
+
+    source.teardown().await.unwrap();
+    target.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn import_project_rejects_an_unknown_owner() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let export = db.engine.export_project(project.id).await.unwrap();
+
+    assert!(matches!(
+        db.engine.import_project(export, Uuid::new_v4()).await,
+        Err(SDKError::NotFound { resource: "member", .. })
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn operations_share_a_single_connection_pool() {
+    let db = TestDatabase::with_config(|config| config.max_connections(1).acquire_timeout_secs(5))
+        .await
+        .unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("launch".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("infra".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // every operation waits its turn for the one connection instead of failing
+    let (export, archived, upserted, fetched, tree) = tokio::join!(
+        db.engine.export_project(project.id),
+        db.engine.archive_task(task.id),
+        db.engine.upsert_label(
+            CreateLabelInputBuilder::default()
+                .name("infra".to_string())
+                .owner_id(owner.id)
+                .description("platform work".to_string())
+                .build()
+                .unwrap(),
+        ),
+        db.engine.get_label(label.id),
+        db.engine.get_task_tree(task.id),
+    );
+
+    assert_eq!(export.unwrap().project.id, project.id);
+    assert!(archived.unwrap().archived_at.is_some());
+    assert_eq!(upserted.unwrap().id, label.id);
+    assert_eq!(fetched.unwrap().name, "infra");
+    assert_eq!(tree.unwrap().task.id, task.id);
+
+    db.engine.unarchive_task(task.id).await.unwrap();
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_task_counts_by_status_includes_empty_statuses() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let statuses = [
+        TaskStatus::ToDo,
+        TaskStatus::ToDo,
+        TaskStatus::InProgress,
+        TaskStatus::Done,
+    ];
+
+    for (i, status) in statuses.into_iter().enumerate() {
+        db.engine
+            .create_task(
+                CreateTaskInputBuilder::default()
+                    .title(format!("task {}", i))
+                    .owner_id(owner.id)
+                    .project_id(project.id)
+                    .status(status)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let counts = db.engine.get_task_counts_by_status(project.id).await.unwrap();
+
+    assert_eq!(counts[&TaskStatus::ToDo], 2);
+    assert_eq!(counts[&TaskStatus::InProgress], 1);
+    assert_eq!(counts[&TaskStatus::Done], 1);
+    assert_eq!(counts[&TaskStatus::Canceled], 0);
+    assert_eq!(counts[&TaskStatus::Backlog], 0);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_projects_filters_by_owner_and_name() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let ada = create_member(&db, "ada@example.com").await;
+    let grace = create_member(&db, "grace@example.com").await;
+
+    for (name, owner) in [("roadmap 2030", &ada), ("hiring", &ada), ("roadmap 2031", &grace)] {
+        db.engine
+            .create_project(
+                CreateProjectInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(owner.id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let names = |filter| {
+        let engine = db.engine.clone();
+
+        async move {
+            let input = GetProjectsInputBuilder::default()
+                .filter(filter)
+                .sort_by("name".to_string())
+                .build()
+                .unwrap();
+
+            engine
+                .get_projects(input)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|project| project.name)
+                .collect::<Vec<String>>()
+        }
+    };
+
+    let owned_roadmaps = GetProjectsWhereBuilder::default()
+        .owner_id(ada.id)
+        .name_contains("ROAD".to_string())
+        .build()
+        .unwrap();
+    assert_eq!(names(owned_roadmaps).await, ["roadmap 2030"]);
+
+    let either = GetProjectsWhereBuilder::default()
+        ._or(vec![
+            GetProjectsWhereBuilder::default().owner_id(grace.id).build().unwrap(),
+            GetProjectsWhereBuilder::default()
+                .name_contains("hiring".to_string())
+                .build()
+                .unwrap(),
+        ])
+        .build()
+        .unwrap();
+    assert_eq!(names(either).await, ["hiring", "roadmap 2031"]);
+
+    // the _or group narrows the other fields instead of widening them
+    let owned_either = GetProjectsWhereBuilder::default()
+        .owner_id(ada.id)
+        ._or(vec![
+            GetProjectsWhereBuilder::default()
+                .name_contains("2030".to_string())
+                .build()
+                .unwrap(),
+            GetProjectsWhereBuilder::default()
+                .name_contains("2031".to_string())
+                .build()
+                .unwrap(),
+        ])
+        .build()
+        .unwrap();
+    assert_eq!(names(owned_either).await, ["roadmap 2030"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn project_members_are_added_promoted_and_removed() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+    let ada = create_member(&db, "ada@example.com").await;
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let added = db
+        .engine
+        .add_project_member(project.id, ada.id, ProjectMemberRole::Member)
+        .await
+        .unwrap();
+    assert_eq!(added.role, ProjectMemberRole::Member);
+
+    // adding again updates the role instead of failing
+    db.engine
+        .add_project_member(project.id, ada.id, ProjectMemberRole::Admin)
+        .await
+        .unwrap();
+
+    let members = db.engine.get_project_members(project.id).await.unwrap();
+
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].member_id, ada.id);
+    assert_eq!(members[0].role, ProjectMemberRole::Admin);
+
+    let loaded = project
+        .members(&SDKLoaders::new(Arc::new(db.engine.clone())))
+        .await
+        .unwrap();
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].email, "ada@example.com");
+
+    let removed = db.engine.remove_project_member(project.id, ada.id).await.unwrap();
+
+    assert_eq!(removed.role, ProjectMemberRole::Admin);
+    assert!(db.engine.get_project_members(project.id).await.unwrap().is_empty());
+    assert!(matches!(
+        db.engine.remove_project_member(project.id, ada.id).await,
+        Err(SDKError::NotFound {
+            resource: "project member",
+            ..
+        })
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn archived_projects_are_hidden_unless_requested() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let mut projects = Vec::new();
+
+    for name in ["current", "finished"] {
+        projects.push(
+            db.engine
+                .create_project(
+                    CreateProjectInputBuilder::default()
+                        .name(name.to_string())
+                        .owner_id(owner.id)
+                        .build()
+                        .unwrap(),
+                )
+                .await
+                .unwrap(),
+        );
+    }
+
+    let archived = db
+        .engine
+        .update_project_status(projects[1].id, ProjectStatus::Archived)
+        .await
+        .unwrap();
+
+    assert_eq!(archived.status, ProjectStatus::Archived);
+    assert!(archived.updated_at > projects[1].updated_at);
+
+    let names = |include_archived| {
+        let engine = db.engine.clone();
+
+        async move {
+            let input = GetProjectsInputBuilder::default()
+                .include_archived(include_archived)
+                .sort_by("name".to_string())
+                .build()
+                .unwrap();
+
+            engine
+                .get_projects(input)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|project| project.name)
+                .collect::<Vec<String>>()
+        }
+    };
+
+    assert_eq!(names(false).await, ["current"]);
+    assert_eq!(names(true).await, ["current", "finished"]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_project_stats_aggregates_tasks_and_members() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+    let ada = create_member(&db, "ada@example.com").await;
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("roadmap".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    for member in [&owner, &ada] {
+        db.engine
+            .add_project_member(project.id, member.id, ProjectMemberRole::Member)
+            .await
+            .unwrap();
+    }
+
+    let past = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+    let future = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+
+    let mut tasks = Vec::new();
+
+    for (title, status, due_date) in [
+        ("late", TaskStatus::ToDo, past),
+        ("on time", TaskStatus::InProgress, future),
+        ("finished late", TaskStatus::Done, past),
+        ("shelved", TaskStatus::ToDo, past),
+    ] {
+        let task = db
+            .engine
+            .create_task(
+                CreateTaskInputBuilder::default()
+                    .title(title.to_string())
+                    .owner_id(owner.id)
+                    .project_id(project.id)
+                    .status(status)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // past due dates are rejected on create, so they are backdated directly
+        sqlx::query("UPDATE tasks SET due_date = $1 WHERE id = $2")
+            .bind(due_date)
+            .bind(task.id)
+            .execute(db.engine.db_pool.as_ref())
+            .await
+            .unwrap();
+
+        tasks.push(task);
+    }
+
+    // archived tasks are left out of every number
+    db.engine.archive_task(tasks[3].id).await.unwrap();
+
+    let stats = db.engine.get_project_stats(project.id).await.unwrap();
+    let count = |status| {
+        stats
+            .tasks_by_status
+            .iter()
+            .find(|count| count.status == status)
+            .map(|count| count.count)
+    };
+
+    assert_eq!(stats.project_id, project.id);
+    assert_eq!(stats.total_tasks, 3);
+    assert_eq!(count(TaskStatus::ToDo), Some(1));
+    assert_eq!(count(TaskStatus::InProgress), Some(1));
+    assert_eq!(count(TaskStatus::Done), Some(1));
+    assert_eq!(count(TaskStatus::Canceled), Some(0));
+    assert_eq!(stats.members, 2);
+    assert_eq!(stats.overdue_tasks, 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn clone_project_copies_the_task_tree_as_a_fresh_skeleton() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let source = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("launch 2030".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let due = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+    let task = |title: &str| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+            .project_id(source.id)
+            .status(TaskStatus::InProgress)
+            .due_date(due)
+    };
+
+    let root = db
+        .engine
+        .create_task(
+            task("launch")
+                .subtasks(vec![task("write docs")
+                    .subtasks(vec![task("proofread").build().unwrap()])
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let shelved = db.engine.create_task(task("shelved").build().unwrap()).await.unwrap();
+    db.engine.archive_task(shelved.id).await.unwrap();
+
+    let clone = db
+        .engine
+        .clone_project(source.id, "launch 2031".to_string())
+        .await
+        .unwrap();
+
+    assert_ne!(clone.id, source.id);
+    assert_eq!(clone.name, "launch 2031");
+
+    let tasks = db.engine.get_project_tasks(clone.id, None).await.unwrap();
+
+    assert_eq!(tasks.len(), 3);
+    assert!(tasks.iter().all(|task| task.status == TaskStatus::ToDo));
+    assert!(tasks.iter().all(|task| task.due_date.is_none()));
+
+    let cloned_root = tasks.iter().find(|task| task.parent_id.is_none()).unwrap();
+    let tree = db.engine.get_task_tree(cloned_root.id).await.unwrap();
+
+    assert_ne!(cloned_root.id, root.id);
+    assert_eq!(tree.task.title, "launch");
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].task.title, "write docs");
+    assert_eq!(tree.children[0].children.len(), 1);
+    assert_eq!(tree.children[0].children[0].task.title, "proofread");
+
+    // the source is left untouched
+    assert_eq!(
+        db.engine.get_task(root.id).await.unwrap().status,
+        TaskStatus::InProgress
+    );
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_project_by_name_finds_none_one_or_reports_ambiguity() {
+    let db = TestDatabase::new().await.unwrap();
+    let owner = create_member(&db, "owner@example.com").await;
+
+    let create = |name: &str| {
+        db.engine.create_project(
+            CreateProjectInputBuilder::default()
+                .name(name.to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+    };
+
+    assert!(db
+        .engine
+        .get_project_by_name("Plexo".to_string())
+        .await
+        .unwrap()
+        .is_none());
+
+    let plexo = create("Plexo").await.unwrap();
+    create("Plexo SDK").await.unwrap();
+
+    let found = db.engine.get_project_by_name("Plexo".to_string()).await.unwrap();
+
+    assert_eq!(found.map(|project| project.id), Some(plexo.id));
+
+    create("Plexo").await.unwrap();
+
+    assert!(matches!(
+        db.engine.get_project_by_name("Plexo".to_string()).await,
+        Err(SDKError::AmbiguousMatch { resource: "project", value }) if value == "Plexo"
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn owners_of_many_projects_load_in_one_query() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let mut owners = Vec::new();
+
+    for i in 0..10 {
+        owners.push(create_member(&db, &format!("owner{}@example.com", i)).await);
+    }
+
+    let mut projects = Vec::new();
+
+    for i in 0..100 {
+        let project = db
+            .engine
+            .create_project(
+                CreateProjectInputBuilder::default()
+                    .name(format!("project {}", i))
+                    .owner_id(owners[i % owners.len()].id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        projects.push(project);
+    }
+
+    let loaders = SDKLoaders::new(Arc::new(db.engine.clone()));
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    let loaded = join_all(projects.iter().map(|project| project.owner(&loaders))).await;
+
+    drop(guard);
+
+    assert_eq!(trace.queries(), 1);
+
+    for (project, owner) in projects.iter().zip(loaded) {
+        assert_eq!(owner.unwrap().id, project.owner_id);
+    }
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_projects_clamps_limits_and_rejects_negative_offsets() {
+    let db = TestDatabase::with_config(|config| config.max_page_size(2))
+        .await
+        .unwrap();
+    let owner = create_member(&db, "owner@example.com").await;
+
+    for name in ["a", "b", "c"] {
+        db.engine
+            .create_project(
+                CreateProjectInputBuilder::default()
+                    .name(name.to_string())
+                    .owner_id(owner.id)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let projects = db
+        .engine
+        .get_projects(GetProjectsInputBuilder::default().limit(1_000_000).build().unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(projects.len(), 2);
+
+    assert!(matches!(
+        db.engine
+            .get_projects(GetProjectsInputBuilder::default().offset(-1).build().unwrap())
+            .await,
+        Err(SDKError::InvalidPagination(_))
+    ));
+
+    db.teardown().await.unwrap();
+}
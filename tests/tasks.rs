@@ -0,0 +1,1761 @@
+#![cfg(feature = "test-util")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{SubsecRound, TimeZone, Utc};
+use futures::{future::join_all, StreamExt, TryStreamExt};
+use plexo_sdk::{
+    backend::{
+        events::{EventSink, SDKEvent},
+        loaders::SDKLoaders,
+        webhook::WebhookSink,
+    },
+    errors::sdk::SDKError,
+    resources::{
+        labels::operations::{CreateLabelInputBuilder, LabelCrudOperations, UpdateLabelInputBuilder},
+        members::{
+            extensions::MembersExtensionOperations,
+            member::{Member, MemberRole},
+            operations::{CreateMemberInputBuilder, MemberCrudOperations},
+        },
+        projects::{
+            operations::{CreateProjectInputBuilder, ProjectCrudOperations},
+            project::Project,
+        },
+        tasks::{
+            extensions::{
+                CreateTasksInput, TaskAssigneeOperations, TaskDependencyOperations, TaskLabelOperations,
+                TasksExtensionOperations,
+            },
+            operations::{
+                ClearableTaskField, CreateTaskInput, CreateTaskInputBuilder, GetTasksInput, GetTasksInputBuilder,
+                GetTasksWhereBuilder, TaskCrudOperations, UpdateTaskInputBuilder,
+            },
+            relations::TaskRelations,
+            task::{Task, TaskStatus},
+        },
+    },
+    testing::{MockLlm, TestDatabase, TraceCapture},
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn get_project_tasks_with_owners_loads_every_owner() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let mut owners = Vec::new();
+
+    for i in 0..5 {
+        let owner = db
+            .engine
+            .create_member(
+                CreateMemberInputBuilder::default()
+                    .name(format!("owner {}", i))
+                    .email(format!("owner{}@example.com", i))
+                    .role(MemberRole::Member)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        owners.push(owner);
+    }
+
+    let new_project = |name: &str| {
+        CreateProjectInputBuilder::default()
+            .name(name.to_string())
+            .owner_id(owners[0].id)
+            .build()
+            .unwrap()
+    };
+
+    let project = db.engine.create_project(new_project("board")).await.unwrap();
+    let other_project = db.engine.create_project(new_project("elsewhere")).await.unwrap();
+
+    let task = |i: usize, project_id, status| {
+        CreateTaskInputBuilder::default()
+            .title(format!("task {}", i))
+            .owner_id(owners[i % owners.len()].id)
+            .project_id(project_id)
+            .status(status)
+            .build()
+            .unwrap()
+    };
+
+    let mut tasks: Vec<_> = (0..50).map(|i| task(i, project.id, TaskStatus::ToDo)).collect();
+    tasks.extend((50..55).map(|i| task(i, project.id, TaskStatus::Done)));
+    tasks.extend((55..60).map(|i| task(i, other_project.id, TaskStatus::ToDo)));
+
+    db.engine
+        .create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let input = GetTasksInputBuilder::default()
+        .filter(
+            GetTasksWhereBuilder::default()
+                .status(TaskStatus::ToDo)
+                .build()
+                .unwrap(),
+        )
+        .sort_by("created_at".to_string())
+        .limit(100)
+        .build()
+        .unwrap();
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    let rows = db
+        .engine
+        .get_project_tasks_with_owners(project.id, Some(input))
+        .await
+        .unwrap();
+
+    drop(guard);
+
+    // the owners come from the same query as the tasks
+    assert_eq!(trace.queries(), 1);
+    assert_eq!(rows.len(), 50);
+
+    for (task, owner) in &rows {
+        assert_eq!(task.project_id, Some(project.id));
+        assert_eq!(task.status, TaskStatus::ToDo);
+        assert_eq!(owner.id, task.owner_id);
+        assert!(owners
+            .iter()
+            .any(|member| member.id == owner.id && member.email == owner.email));
+    }
+
+    let all = db.engine.get_project_tasks(project.id, None).await.unwrap();
+
+    assert_eq!(all.len(), 55);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_task_with_repeated_idempotency_key_returns_the_first_task() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let owner = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let input = || {
+        CreateTaskInputBuilder::default()
+            .title("ship the release".to_string())
+            .owner_id(owner.id)
+            .idempotency_key("request-1")
+            .build()
+            .unwrap()
+    };
+
+    let first = db.engine.create_task(input()).await.unwrap();
+    let second = db.engine.create_task(input()).await.unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&second).unwrap()
+    );
+    assert_eq!(db.engine.get_tasks(None).await.unwrap().len(), 1);
+
+    // keys are scoped per resource type, so a label can reuse it
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("release".to_string())
+                .owner_id(owner.id)
+                .idempotency_key("request-1")
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(label.name, "release");
+
+    db.teardown().await.unwrap();
+}
+
+/// A member and a project owned by it, for tests that only need somewhere to put tasks.
+async fn owner_and_project(db: &TestDatabase) -> (Member, Project) {
+    let owner = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("owner".to_string())
+                .email("owner@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let project = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("board".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    (owner, project)
+}
+
+async fn create_project_tasks(db: &TestDatabase, owner: &Member, project: &Project, count: usize) -> Vec<Task> {
+    let tasks = (0..count)
+        .map(|i| {
+            CreateTaskInputBuilder::default()
+                .title(format!("task {}", i))
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine
+        .create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+}
+
+async fn titles_by_position(db: &TestDatabase, project: &Project) -> Vec<String> {
+    let input = GetTasksInputBuilder::default()
+        .sort_by("position".to_string())
+        .build()
+        .unwrap();
+
+    db.engine
+        .get_project_tasks(project.id, Some(input))
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|task| task.title)
+        .collect()
+}
+
+#[tokio::test]
+async fn limits_above_max_page_size_are_clamped() {
+    let db = TestDatabase::with_config(|config| config.max_page_size(3))
+        .await
+        .unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    create_project_tasks(&db, &owner, &project, 5).await;
+
+    let above_cap = GetTasksInputBuilder::default().limit(1_000_000).build().unwrap();
+
+    assert_eq!(db.engine.get_tasks(Some(above_cap)).await.unwrap().len(), 3);
+    assert_eq!(db.engine.get_tasks(None).await.unwrap().len(), 3);
+    assert_eq!(db.engine.get_project_tasks(project.id, None).await.unwrap().len(), 3);
+
+    let csv = db.engine.export_tasks_csv(GetTasksInput::default()).await.unwrap();
+
+    // header plus one page of rows
+    assert_eq!(csv.lines().count(), 4);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn reorder_task_moves_the_bottom_task_to_the_top_across_gaps() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let tasks = create_project_tasks(&db, &owner, &project, 5).await;
+
+    // leaves a gap in the positions
+    db.engine.delete_task(tasks[2].id).await.unwrap();
+
+    db.engine.reorder_task(tasks[4].id, 0).await.unwrap();
+
+    assert_eq!(
+        titles_by_position(&db, &project).await,
+        ["task 4", "task 0", "task 1", "task 3"]
+    );
+
+    let moved = db.engine.reorder_task(tasks[0].id, 1000).await.unwrap();
+
+    assert_eq!(moved.position, 3);
+    assert_eq!(
+        titles_by_position(&db, &project).await,
+        ["task 4", "task 1", "task 3", "task 0"]
+    );
+
+    assert!(matches!(
+        db.engine.reorder_task(Uuid::new_v4(), 0).await,
+        Err(SDKError::NotFound { .. })
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn materialize_recurring_tasks_creates_weekly_occurrences_once() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let anchor = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+    let until = Utc.with_ymd_and_hms(2026, 3, 31, 23, 59, 59).unwrap();
+
+    let template = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("weekly report".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .due_date(anchor)
+                .allow_past_due(true)
+                .recurrence("FREQ=WEEKLY".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // two overlapping runs must not both create the same occurrence
+    let (first, second) = tokio::join!(
+        db.engine.materialize_recurring_tasks(until),
+        db.engine.materialize_recurring_tasks(until),
+    );
+
+    let mut due_dates: Vec<_> = first
+        .unwrap()
+        .into_iter()
+        .chain(second.unwrap())
+        .map(|task| task.due_date.unwrap())
+        .collect();
+    due_dates.sort();
+
+    assert_eq!(
+        due_dates,
+        [9, 16, 23, 30].map(|day| Utc.with_ymd_and_hms(2026, 3, day, 9, 0, 0).unwrap())
+    );
+
+    assert!(db.engine.materialize_recurring_tasks(until).await.unwrap().is_empty());
+
+    let instances = db
+        .engine
+        .get_tasks(Some(
+            GetTasksInputBuilder::default()
+                .filter(
+                    GetTasksWhereBuilder::default()
+                        .title("weekly report".to_string())
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap(),
+        ))
+        .await
+        .unwrap();
+
+    // the template plus four instances
+    assert_eq!(instances.len(), 5);
+    assert!(instances
+        .iter()
+        .all(|task| task.id == template.id || task.recurrence.is_none()));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn updating_a_missing_task_is_not_found() {
+    let db = TestDatabase::new().await.unwrap();
+    let (_, project) = owner_and_project(&db).await;
+    let missing = Uuid::new_v4();
+
+    let status_change = UpdateTaskInputBuilder::default()
+        .status(TaskStatus::InProgress)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.update_task(missing, status_change).await,
+        Err(SDKError::NotFound { resource: "task", id }) if id == missing
+    ));
+    assert!(matches!(
+        db.engine.move_task_to_project(missing, Some(project.id)).await,
+        Err(SDKError::NotFound { resource: "task", id }) if id == missing
+    ));
+    assert!(matches!(
+        db.engine.move_task_to_project(missing, None).await,
+        Err(SDKError::NotFound { resource: "task", id }) if id == missing
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_tasks_filters_by_status() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let statuses = [
+        TaskStatus::ToDo,
+        TaskStatus::Done,
+        TaskStatus::InProgress,
+        TaskStatus::Done,
+    ];
+
+    let tasks = statuses
+        .iter()
+        .enumerate()
+        .map(|(i, status)| {
+            CreateTaskInputBuilder::default()
+                .title(format!("task {}", i))
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .status(*status)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    db.engine
+        .create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let with_filter = |filter| GetTasksInputBuilder::default().filter(filter).build().unwrap();
+
+    let done = db
+        .engine
+        .get_tasks(Some(with_filter(
+            GetTasksWhereBuilder::default()
+                .status(TaskStatus::Done)
+                .build()
+                .unwrap(),
+        )))
+        .await
+        .unwrap();
+
+    assert_eq!(done.len(), 2);
+    assert!(done.iter().all(|task| task.status == TaskStatus::Done));
+
+    let done_or_in_progress = db
+        .engine
+        .get_tasks(Some(with_filter(
+            GetTasksWhereBuilder::default()
+                ._or(vec![
+                    GetTasksWhereBuilder::default()
+                        .status(TaskStatus::Done)
+                        .build()
+                        .unwrap(),
+                    GetTasksWhereBuilder::default()
+                        .status(TaskStatus::InProgress)
+                        .build()
+                        .unwrap(),
+                ])
+                .build()
+                .unwrap(),
+        )))
+        .await
+        .unwrap();
+
+    assert_eq!(done_or_in_progress.len(), 3);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn task_labels_can_be_added_twice_and_removed() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("bug".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    db.engine.add_label_to_task(task.id, label.id).await.unwrap();
+    db.engine.add_label_to_task(task.id, label.id).await.unwrap();
+
+    let labels = db.engine.get_task_labels(task.id).await.unwrap();
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].id, label.id);
+
+    db.engine.remove_label_from_task(task.id, label.id).await.unwrap();
+
+    assert!(db.engine.get_task_labels(task.id).await.unwrap().is_empty());
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn assignees_load_through_the_member_loader() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    let mut assignees = Vec::new();
+
+    for email in ["ada@example.com", "grace@example.com"] {
+        let member = db
+            .engine
+            .create_member(
+                CreateMemberInputBuilder::default()
+                    .name(email.to_string())
+                    .email(email.to_string())
+                    .role(MemberRole::Member)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        db.engine.assign_task(task.id, member.id).await.unwrap();
+        assignees.push(member.id);
+    }
+
+    let loaders = SDKLoaders::new(Arc::new(db.engine.clone()));
+
+    let mut loaded: Vec<Uuid> = task
+        .assignees(&loaders)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|member| member.id)
+        .collect();
+
+    loaded.sort();
+    assignees.sort();
+
+    assert_eq!(loaded, assignees);
+    assert_eq!(task.owner(&loaders).await.unwrap().id, owner.id);
+
+    db.engine.unassign_task(task.id, assignees[0]).await.unwrap();
+
+    let remaining = db.engine.get_task_assignees(task.id).await.unwrap();
+
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, assignees[1]);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn update_task_rejects_invalid_status_transitions() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    let to = |status| UpdateTaskInputBuilder::default().status(status).build().unwrap();
+
+    db.engine.update_task(task.id, to(TaskStatus::Canceled)).await.unwrap();
+
+    assert!(matches!(
+        db.engine.update_task(task.id, to(TaskStatus::InProgress)).await,
+        Err(SDKError::InvalidStatusTransition {
+            from: TaskStatus::Canceled,
+            to: TaskStatus::InProgress
+        })
+    ));
+
+    let reopened = db.engine.update_task(task.id, to(TaskStatus::ToDo)).await.unwrap();
+
+    assert_eq!(reopened.status, TaskStatus::ToDo);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn update_task_leaves_omitted_fields_and_clears_listed_ones() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let due_date = Utc.with_ymd_and_hms(2099, 1, 1, 12, 0, 0).unwrap();
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("draft".to_string())
+                .description("keep me".to_string())
+                .due_date(due_date)
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let renamed = db
+        .engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .title("final".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(renamed.title, "final");
+    assert_eq!(renamed.description.as_deref(), Some("keep me"));
+    assert_eq!(renamed.due_date, Some(due_date));
+    assert_eq!(renamed.project_id, Some(project.id));
+
+    let cleared = db
+        .engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .clear(vec![ClearableTaskField::DueDate, ClearableTaskField::ProjectId])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(cleared.due_date, None);
+    assert_eq!(cleared.project_id, None);
+    assert_eq!(cleared.description.as_deref(), Some("keep me"));
+
+    // clearing wins over a value given for the same field
+    let both = db
+        .engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .description("replaced".to_string())
+                .clear(vec![ClearableTaskField::Description])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(both.description, None);
+    assert_eq!(both.title, "final");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_task_update_is_a_conflict() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    let rename = |title: &str, version| {
+        UpdateTaskInputBuilder::default()
+            .title(title.to_string())
+            .expected_updated_at(version)
+            .build()
+            .unwrap()
+    };
+
+    // both clients read `task`; the first write wins
+    let first = db
+        .engine
+        .update_task(task.id, rename("first", task.updated_at))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.update_task(task.id, rename("second", task.updated_at)).await,
+        Err(SDKError::Conflict { resource: "task", id }) if id == task.id
+    ));
+    assert_eq!(db.engine.get_task(task.id).await.unwrap().title, "first");
+
+    // retrying against the fresh version goes through
+    let retried = db
+        .engine
+        .update_task(task.id, rename("second", first.updated_at))
+        .await
+        .unwrap();
+
+    assert_eq!(retried.title, "second");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn update_tasks_status_updates_only_the_given_tasks() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 5).await;
+
+    // an unknown id is skipped
+    let ids = vec![tasks[0].id, tasks[2].id, tasks[4].id, Uuid::new_v4()];
+
+    let updated = db
+        .engine
+        .update_tasks_status(ids, TaskStatus::ToDo, None)
+        .await
+        .unwrap();
+
+    assert_eq!(updated.len(), 3);
+    assert!(updated.iter().all(|task| task.status == TaskStatus::ToDo));
+
+    let todo = GetTasksInputBuilder::default()
+        .filter(
+            GetTasksWhereBuilder::default()
+                .status(TaskStatus::ToDo)
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(db.engine.get_tasks(Some(todo)).await.unwrap().len(), 3);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn add_dependency_rejects_cycles() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 3).await;
+    let (a, b, c) = (tasks[0].id, tasks[1].id, tasks[2].id);
+
+    db.engine.add_dependency(a, b).await.unwrap();
+    db.engine.add_dependency(b, c).await.unwrap();
+
+    assert!(matches!(
+        db.engine.add_dependency(c, a).await,
+        Err(SDKError::DependencyCycle)
+    ));
+
+    assert_eq!(db.engine.get_blockers(b).await.unwrap()[0].id, a);
+    assert_eq!(db.engine.get_blocked_by(b).await.unwrap()[0].id, c);
+
+    db.engine.remove_dependency(a, b).await.unwrap();
+
+    assert!(db.engine.get_blockers(b).await.unwrap().is_empty());
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_opposite_dependencies_cannot_both_be_added() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 20).await;
+
+    // each pair races a -> b against b -> a; without serialization both see no path and both insert
+    let races = tasks.chunks(2).map(|pair| {
+        let (a, b) = (pair[0].id, pair[1].id);
+        let engine = &db.engine;
+
+        async move { tokio::join!(engine.add_dependency(a, b), engine.add_dependency(b, a)) }
+    });
+
+    for (forward, backward) in join_all(races).await {
+        assert!(
+            matches!(
+                (&forward, &backward),
+                (Ok(()), Err(SDKError::DependencyCycle)) | (Err(SDKError::DependencyCycle), Ok(()))
+            ),
+            "{forward:?} {backward:?}"
+        );
+    }
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn move_task_to_project_moves_detaches_and_validates() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    let target = db
+        .engine
+        .create_project(
+            CreateProjectInputBuilder::default()
+                .name("elsewhere".to_string())
+                .owner_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let moved = db.engine.move_task_to_project(task.id, Some(target.id)).await.unwrap();
+
+    assert_eq!(moved.project_id, Some(target.id));
+
+    assert!(matches!(
+        db.engine.move_task_to_project(task.id, Some(Uuid::new_v4())).await,
+        Err(SDKError::ProjectNotFound)
+    ));
+
+    let detached = db.engine.move_task_to_project(task.id, None).await.unwrap();
+
+    assert_eq!(detached.project_id, None);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn archived_tasks_are_hidden_unless_requested() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 3).await;
+
+    let archived = db.engine.archive_task(tasks[0].id).await.unwrap();
+
+    assert!(archived.archived_at.is_some());
+    assert_eq!(db.engine.get_tasks(None).await.unwrap().len(), 2);
+
+    let with_archived = GetTasksInputBuilder::default().include_archived(true).build().unwrap();
+
+    assert_eq!(db.engine.get_tasks(Some(with_archived)).await.unwrap().len(), 3);
+
+    db.engine.unarchive_task(tasks[0].id).await.unwrap();
+
+    assert_eq!(db.engine.get_tasks(None).await.unwrap().len(), 3);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_tasks_due_between_includes_the_start_and_excludes_the_end() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let start = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2030, 1, 8, 0, 0, 0).unwrap();
+
+    let due = |title: &str, due_date, status| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+            .project_id(project.id)
+            .due_date(due_date)
+            .status(status)
+            .build()
+            .unwrap()
+    };
+
+    db.engine
+        .create_tasks(CreateTasksInput {
+            tasks: vec![
+                due("midweek", start + chrono::Duration::days(3), TaskStatus::ToDo),
+                due("at start", start, TaskStatus::ToDo),
+                due("at end", end, TaskStatus::ToDo),
+                due("before", start - chrono::Duration::seconds(1), TaskStatus::ToDo),
+                due("finished", start + chrono::Duration::days(1), TaskStatus::Done),
+            ],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let titles: Vec<String> = db
+        .engine
+        .get_tasks_due_between(start, end, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|task| task.title)
+        .collect();
+
+    assert_eq!(titles, ["at start", "midweek"]);
+
+    let someone_else = db
+        .engine
+        .get_tasks_due_between(start, end, Some(Uuid::new_v4()))
+        .await
+        .unwrap();
+
+    assert!(someone_else.is_empty());
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_tasks_writes_the_whole_tree_or_nothing() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let (owner, _) = owner_and_project(&db).await;
+
+    let task = |title: &str| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+    };
+    let all_tasks = || db.engine.get_tasks(None);
+
+    // caught by validation, before anything is written
+    let invalid = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks: vec![task("launch")
+                .subtasks(vec![task("write docs")
+                    .due_date(Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap())
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap()],
+            ..Default::default()
+        })
+        .await;
+
+    assert!(matches!(
+        invalid,
+        Err(SDKError::Validation(errors)) if errors.len() == 1 && errors[0].field == "subtasks[0].due_date"
+    ));
+    assert!(all_tasks().await.unwrap().is_empty());
+
+    // fails in the database, after the parent was inserted
+    let orphan = CreateTaskInputBuilder::default()
+        .title("write docs".to_string())
+        .owner_id(Uuid::new_v4())
+        .build()
+        .unwrap();
+
+    let failed = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks: vec![task("launch").subtasks(vec![orphan]).build().unwrap()],
+            ..Default::default()
+        })
+        .await;
+
+    assert!(failed.is_err());
+    assert!(all_tasks().await.unwrap().is_empty());
+
+    let created = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks: vec![task("launch")
+                .subtasks(vec![
+                    task("write docs").build().unwrap(),
+                    task("ship it").build().unwrap(),
+                ])
+                .build()
+                .unwrap()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let tasks = all_tasks().await.unwrap();
+    let parent = &created[0];
+
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks.iter().filter(|task| task.parent_id == Some(parent.id)).count(), 2);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_task_tree_loads_three_levels_in_one_query_and_detects_cycles() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let (owner, _) = owner_and_project(&db).await;
+
+    let task = |title: &str| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+    };
+
+    let root = db
+        .engine
+        .create_task(
+            task("launch")
+                .subtasks(vec![
+                    task("write docs")
+                        .subtasks(vec![task("proofread").build().unwrap()])
+                        .build()
+                        .unwrap(),
+                    task("ship it").build().unwrap(),
+                ])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    let tree = db.engine.get_task_tree(root.id).await.unwrap();
+
+    drop(guard);
+
+    assert_eq!(trace.queries(), 1);
+    assert_eq!(tree.task.id, root.id);
+
+    let mut children: Vec<&str> = tree.children.iter().map(|node| node.task.title.as_str()).collect();
+    children.sort();
+
+    assert_eq!(children, ["ship it", "write docs"]);
+
+    let docs = tree
+        .children
+        .iter()
+        .find(|node| node.task.title == "write docs")
+        .unwrap();
+
+    assert_eq!(docs.children.len(), 1);
+    assert_eq!(docs.children[0].task.title, "proofread");
+    assert!(docs.children[0].children.is_empty());
+
+    let subtasks = root
+        .subtasks(&SDKLoaders::new(Arc::new(db.engine.clone())))
+        .await
+        .unwrap();
+
+    assert_eq!(subtasks.len(), 2);
+
+    // corrupt the data so the root hangs below its own grandchild
+    sqlx::query("UPDATE tasks SET parent_id = $1 WHERE id = $2")
+        .bind(docs.children[0].task.id)
+        .bind(root.id)
+        .execute(db.engine.db_pool.as_ref())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.get_task_tree(root.id).await,
+        Err(SDKError::DependencyCycle)
+    ));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn export_tasks_csv_escapes_commas_and_line_breaks() {
+    let db = TestDatabase::new().await.unwrap();
+
+    let (owner, _) = owner_and_project(&db).await;
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("docs, api\nand \"more\"".to_string())
+                .owner_id(owner.id)
+                .status(TaskStatus::ToDo)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csv = db.engine.export_tasks_csv(GetTasksInput::default()).await.unwrap();
+
+    assert_eq!(
+        csv,
+        format!(
+            "id,title,status,priority,owner_id,due_date\r\n{},\"docs, api\nand \"\"more\"\"\",ToDo,{},{},\r\n",
+            task.id, task.priority, owner.id
+        )
+    );
+
+    db.teardown().await.unwrap();
+}
+
+async fn audit_columns(db: &TestDatabase, table: &str, id: Uuid) -> (Option<Uuid>, Option<Uuid>) {
+    sqlx::query_as(&format!("SELECT created_by, updated_by FROM {} WHERE id = $1", table))
+        .bind(id)
+        .fetch_one(db.engine.db_pool.as_ref())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn writes_record_the_acting_member() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let editor = Uuid::new_v4();
+
+    let task = db
+        .engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("audited".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .actor_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        audit_columns(&db, "tasks", task.id).await,
+        (Some(owner.id), Some(owner.id))
+    );
+
+    db.engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .title("edited".to_string())
+                .actor_id(editor)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        audit_columns(&db, "tasks", task.id).await,
+        (Some(owner.id), Some(editor))
+    );
+
+    // a bulk status change names its own actor, not the previous writer
+    let reviewer = Uuid::new_v4();
+
+    db.engine
+        .update_tasks_status(vec![task.id], TaskStatus::ToDo, Some(reviewer))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        audit_columns(&db, "tasks", task.id).await,
+        (Some(owner.id), Some(reviewer))
+    );
+
+    let label = db
+        .engine
+        .create_label(
+            CreateLabelInputBuilder::default()
+                .name("audited".to_string())
+                .owner_id(owner.id)
+                .actor_id(owner.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    db.engine
+        .update_label(
+            label.id,
+            UpdateLabelInputBuilder::default()
+                .color("#ff0000".to_string())
+                .actor_id(editor)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        audit_columns(&db, "labels", label.id).await,
+        (Some(owner.id), Some(editor))
+    );
+
+    // system operations leave the actor empty
+    let system = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    assert_eq!(audit_columns(&db, "tasks", system.id).await, (None, None));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_tasks_by_ids_keeps_the_input_order_and_skips_missing_ids() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 3).await;
+
+    let ids = vec![tasks[2].id, Uuid::new_v4(), tasks[0].id, Uuid::new_v4(), tasks[1].id];
+
+    let found = db.engine.get_tasks_by_ids(ids).await.unwrap();
+
+    assert_eq!(
+        found.iter().map(|task| task.id).collect::<Vec<_>>(),
+        vec![tasks[2].id, tasks[0].id, tasks[1].id]
+    );
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn stream_tasks_yields_every_task_one_page_at_a_time() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 1000).await;
+
+    let streamed: Vec<Task> = db.engine.stream_tasks(None).try_collect().await.unwrap();
+
+    let mut expected: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let mut ids: Vec<Uuid> = streamed.iter().map(|task| task.id).collect();
+
+    assert_eq!(ids.len(), 1000);
+
+    expected.sort();
+    ids.sort();
+
+    assert_eq!(ids, expected);
+
+    // only the first page is fetched until it has been consumed
+    let trace = TraceCapture::default();
+    let guard = trace.install();
+
+    let first: Vec<Task> = db.engine.stream_tasks(None).take(10).try_collect().await.unwrap();
+
+    drop(guard);
+
+    assert_eq!(first.len(), 10);
+    assert_eq!(trace.queries(), 1);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn reading_an_unknown_stored_status_is_an_error() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let task = create_project_tasks(&db, &owner, &project, 1).await.remove(0);
+
+    sqlx::query("UPDATE tasks SET status = 'Sideways' WHERE id = $1")
+        .bind(task.id)
+        .execute(db.engine.db_pool.as_ref())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        db.engine.get_task(task.id).await,
+        Err(SDKError::InvalidTaskStatus(value)) if value == "Sideways"
+    ));
+    assert!(matches!(
+        db.engine.get_tasks(None).await,
+        Err(SDKError::InvalidTaskStatus(_))
+    ));
+
+    // a missing status is still read as `None`
+    sqlx::query("UPDATE tasks SET status = NULL WHERE id = $1")
+        .bind(task.id)
+        .execute(db.engine.db_pool.as_ref())
+        .await
+        .unwrap();
+
+    assert_eq!(db.engine.get_task(task.id).await.unwrap().status, TaskStatus::None);
+
+    db.teardown().await.unwrap();
+}
+
+#[derive(Default)]
+struct RecordedEvents(Mutex<Vec<SDKEvent>>);
+
+impl EventSink for RecordedEvents {
+    fn emit(&self, event: SDKEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test]
+async fn task_writes_emit_events_to_the_sink() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let events = Arc::new(RecordedEvents::default());
+    let engine = db.engine.clone().with_event_sink(events.clone());
+
+    let task = engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("announce".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .title("announced".to_string())
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // a failed write emits nothing
+    assert!(engine
+        .update_task(Uuid::new_v4(), UpdateTaskInputBuilder::default().build().unwrap())
+        .await
+        .is_err());
+
+    engine.delete_task(task.id).await.unwrap();
+
+    let events = events.0.lock().unwrap().clone();
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[0], SDKEvent::TaskCreated(created) if created.id == task.id));
+    assert!(matches!(&events[1], SDKEvent::TaskUpdated(updated) if updated.title == "announced"));
+    assert!(matches!(&events[2], SDKEvent::TaskDeleted { id } if *id == task.id));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn webhook_sink_delivers_a_created_task() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let endpoint = MockLlm::start(vec![]).await.unwrap();
+    let engine = db
+        .engine
+        .clone()
+        .with_event_sink(Arc::new(WebhookSink::new(endpoint.url())));
+
+    let task = engine
+        .create_task(
+            CreateTaskInputBuilder::default()
+                .title("deliver me".to_string())
+                .owner_id(owner.id)
+                .project_id(project.id)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // delivery happens in the background, after create_task has returned
+    let mut delivered = endpoint.requests();
+    for _ in 0..100 {
+        if !delivered.is_empty() {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        delivered = endpoint.requests();
+    }
+
+    assert_eq!(delivered.len(), 1);
+    assert_eq!(delivered[0]["type"], "task_created");
+    assert_eq!(delivered[0]["data"]["id"], task.id.to_string());
+    assert_eq!(delivered[0]["data"]["title"], "deliver me");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn past_due_dates_are_rejected_unless_allowed() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    // whole seconds, as postgres keeps only microseconds
+    let past = (Utc::now() - chrono::Duration::days(1)).trunc_subsecs(0);
+    let future = (Utc::now() + chrono::Duration::days(1)).trunc_subsecs(0);
+
+    let input = |due_date, allow_past_due| {
+        let mut input = CreateTaskInputBuilder::default()
+            .title("due".to_string())
+            .owner_id(owner.id)
+            .project_id(project.id)
+            .due_date(due_date);
+
+        if allow_past_due {
+            input = input.allow_past_due(true);
+        }
+
+        input.build().unwrap()
+    };
+
+    assert!(matches!(
+        db.engine.create_task(input(past, false)).await,
+        Err(SDKError::DueDateInPast(due_date)) if due_date == past
+    ));
+
+    let task = db.engine.create_task(input(future, false)).await.unwrap();
+    assert_eq!(task.due_date, Some(future));
+
+    let backfilled = db.engine.create_task(input(past, true)).await.unwrap();
+    assert_eq!(backfilled.due_date, Some(past));
+
+    assert!(matches!(
+        db.engine
+            .update_task(
+                task.id,
+                UpdateTaskInputBuilder::default().due_date(past).build().unwrap()
+            )
+            .await,
+        Err(SDKError::DueDateInPast(_))
+    ));
+
+    let moved = db
+        .engine
+        .update_task(
+            task.id,
+            UpdateTaskInputBuilder::default()
+                .due_date(past)
+                .allow_past_due(true)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(moved.due_date, Some(past));
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn overdue_tasks_are_unfinished_tasks_past_due_most_overdue_first() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let other = db
+        .engine
+        .create_member(
+            CreateMemberInputBuilder::default()
+                .name("other".to_string())
+                .email("other@example.com".to_string())
+                .role(MemberRole::Member)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+
+    let create = |title: &str, owner_id: Uuid, status: TaskStatus, due_in_days: Option<i64>| {
+        let mut input = CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner_id)
+            .project_id(project.id)
+            .status(status)
+            .allow_past_due(true);
+
+        if let Some(days) = due_in_days {
+            input = input.due_date(now + chrono::Duration::days(days));
+        }
+
+        db.engine.create_task(input.build().unwrap())
+    };
+
+    let late = create("late", owner.id, TaskStatus::ToDo, Some(-1)).await.unwrap();
+    let later = create("later", owner.id, TaskStatus::InProgress, Some(-3))
+        .await
+        .unwrap();
+    create("done", owner.id, TaskStatus::Done, Some(-5)).await.unwrap();
+    create("canceled", owner.id, TaskStatus::Canceled, Some(-5))
+        .await
+        .unwrap();
+    create("upcoming", owner.id, TaskStatus::ToDo, Some(1)).await.unwrap();
+    create("undated", owner.id, TaskStatus::ToDo, None).await.unwrap();
+    let others = create("others", other.id, TaskStatus::Backlog, Some(-2)).await.unwrap();
+
+    let ids = |tasks: Vec<Task>| tasks.into_iter().map(|task| task.id).collect::<Vec<_>>();
+
+    assert_eq!(
+        ids(db.engine.get_overdue_tasks(Some(owner.id), now).await.unwrap()),
+        vec![later.id, late.id]
+    );
+    assert_eq!(
+        ids(db.engine.get_overdue_tasks(None, now).await.unwrap()),
+        vec![later.id, others.id, late.id]
+    );
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn task_owners_resolve_by_email_and_are_created_on_request() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    assert_eq!(
+        db.engine
+            .resolve_member_by_email("Owner@Example.com".to_string())
+            .await
+            .unwrap(),
+        owner.id
+    );
+    assert!(matches!(
+        db.engine.resolve_member_by_email("new@example.com".to_string()).await,
+        Err(SDKError::MemberNotFound(email)) if email == "new@example.com"
+    ));
+
+    let input = |email: &str, create: bool| {
+        let mut input = CreateTaskInputBuilder::default()
+            .title("imported".to_string())
+            .owner_email(email)
+            .project_id(project.id);
+
+        if create {
+            input = input.create_owner_if_missing(true);
+        }
+
+        input.build().unwrap()
+    };
+
+    let existing = db.engine.create_task(input("owner@example.com", false)).await.unwrap();
+    assert_eq!(existing.owner_id, owner.id);
+
+    assert!(matches!(
+        db.engine.create_task(input("new@example.com", false)).await,
+        Err(SDKError::MemberNotFound(_))
+    ));
+    assert!(db
+        .engine
+        .resolve_member_by_email("new@example.com".to_string())
+        .await
+        .is_err());
+
+    let created = db.engine.create_task(input("new@example.com", true)).await.unwrap();
+    let new_owner = db.engine.get_member(created.owner_id).await.unwrap();
+
+    assert_ne!(new_owner.id, owner.id);
+    assert_eq!(new_owner.email, "new@example.com");
+    assert_eq!(new_owner.name, "new");
+
+    // a second import reuses the member created by the first
+    let again = db.engine.create_task(input("new@example.com", true)).await.unwrap();
+    assert_eq!(again.owner_id, new_owner.id);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn dry_run_previews_tasks_without_writing_them() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let task = |title: &str| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+            .project_id(project.id)
+            .build()
+            .unwrap()
+    };
+
+    let preview = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks: vec![task("plan"), task("build")],
+            dry_run: Some(true),
+        })
+        .await
+        .unwrap();
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks")
+        .fetch_one(db.engine.db_pool.as_ref())
+        .await
+        .unwrap();
+
+    assert_eq!(stored, 0);
+    assert_eq!(preview.len(), 2);
+    assert_eq!(preview[0].title, "plan");
+    assert_eq!(preview[1].title, "build");
+
+    // submitting the previewed ids commits the same tasks
+    let committed = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks: preview
+                .iter()
+                .map(|previewed| CreateTaskInput {
+                    id: Some(previewed.id),
+                    ..task(&previewed.title)
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        committed.iter().map(|task| task.id).collect::<Vec<_>>(),
+        preview.iter().map(|task| task.id).collect::<Vec<_>>()
+    );
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_tasks_reports_every_invalid_field_in_the_batch() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let task = |title: &str| {
+        CreateTaskInputBuilder::default()
+            .title(title.to_string())
+            .owner_id(owner.id)
+            .project_id(project.id)
+    };
+
+    let tasks = vec![
+        task("fine").build().unwrap(),
+        task("   ").build().unwrap(),
+        task("late")
+            .due_date(Utc::now() - chrono::Duration::days(1))
+            .subtasks(vec![task("repeat")
+                .recurrence("FREQ=SOMETIMES".to_string())
+                .build()
+                .unwrap()])
+            .build()
+            .unwrap(),
+    ];
+
+    let Err(SDKError::Validation(errors)) = db
+        .engine
+        .create_tasks(CreateTasksInput {
+            tasks,
+            ..Default::default()
+        })
+        .await
+    else {
+        panic!("expected a validation error");
+    };
+
+    let fields: Vec<_> = errors.iter().map(|err| (err.index, err.field.as_str())).collect();
+    assert_eq!(
+        fields,
+        vec![(1, "title"), (2, "due_date"), (2, "subtasks[0].recurrence")]
+    );
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks")
+        .fetch_one(db.engine.db_pool.as_ref())
+        .await
+        .unwrap();
+    assert_eq!(stored, 0);
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn titles_are_trimmed_and_must_be_non_empty_and_short_enough() {
+    let db = TestDatabase::new().await.unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+
+    let input = |title: String| {
+        CreateTaskInputBuilder::default()
+            .title(title)
+            .owner_id(owner.id)
+            .project_id(project.id)
+            .build()
+            .unwrap()
+    };
+
+    let is_title_error = |result: Result<Task, SDKError>| matches!(result, Err(SDKError::Validation(errors)) if errors.len() == 1 && errors[0].field == "title");
+
+    assert!(is_title_error(db.engine.create_task(input(String::new())).await));
+    assert!(is_title_error(db.engine.create_task(input(" \t\n ".to_string())).await));
+    assert!(is_title_error(db.engine.create_task(input("x".repeat(513))).await));
+
+    // the limit counts characters after trimming
+    let longest = db
+        .engine
+        .create_task(input(format!("  {}  ", "é".repeat(512))))
+        .await
+        .unwrap();
+    assert_eq!(longest.title, "é".repeat(512));
+
+    let task = db.engine.create_task(input("  padded  ".to_string())).await.unwrap();
+    assert_eq!(task.title, "padded");
+
+    let update = |title: &str| {
+        UpdateTaskInputBuilder::default()
+            .title(title.to_string())
+            .build()
+            .unwrap()
+    };
+
+    assert!(is_title_error(db.engine.update_task(task.id, update("   ")).await));
+    assert!(is_title_error(
+        db.engine.update_task(task.id, update(&"x".repeat(513))).await
+    ));
+
+    let renamed = db.engine.update_task(task.id, update(" renamed ")).await.unwrap();
+    assert_eq!(renamed.title, "renamed");
+
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test]
+async fn get_tasks_page_counts_unarchived_tasks_and_rejects_cursors() {
+    let db = TestDatabase::with_config(|config| config.max_page_size(3))
+        .await
+        .unwrap();
+    let (owner, project) = owner_and_project(&db).await;
+    let tasks = create_project_tasks(&db, &owner, &project, 5).await;
+
+    db.engine.archive_task(tasks[0].id).await.unwrap();
+
+    let page = db.engine.get_tasks_page(GetTasksInput::default(), true).await.unwrap();
+
+    assert_eq!(page.limit, 3);
+    assert_eq!(page.items.len(), 3);
+    assert_eq!(page.total, Some(4));
+    assert!(page.has_more);
+
+    let archived_too = db
+        .engine
+        .get_tasks_page(
+            GetTasksInput {
+                include_archived: Some(true),
+                ..Default::default()
+            },
+            true,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(archived_too.total, Some(5));
+
+    assert!(matches!(
+        db.engine
+            .get_tasks_page(
+                GetTasksInput {
+                    cursor: Some(page.items[0].id.to_string()),
+                    ..Default::default()
+                },
+                false
+            )
+            .await,
+        Err(SDKError::InvalidPagination(_))
+    ));
+
+    db.teardown().await.unwrap();
+}